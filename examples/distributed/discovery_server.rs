@@ -40,7 +40,7 @@ impl DiscoveryServer {
             NetEvent::Connected(_, _) => unreachable!(), // There is no connect() calls.
             NetEvent::Accepted(_, _) => (),              // All endpoint accepted
             NetEvent::Message(endpoint, input_data) => {
-                let message: Message = bincode::deserialize(&input_data).unwrap();
+                let message: Message = bincode::deserialize(input_data).unwrap();
                 match message {
                     Message::RegisterParticipant(name, addr) => {
                         self.register(&name, addr, endpoint);
@@ -62,6 +62,7 @@ impl DiscoveryServer {
                     self.unregister(&name);
                 }
             }
+            _ => (), // Events added by newer versions
         });
     }
 
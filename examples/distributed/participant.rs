@@ -70,7 +70,7 @@ impl Participant {
             }
             NetEvent::Accepted(_, _) => (),
             NetEvent::Message(_, input_data) => {
-                let message: Message = bincode::deserialize(&input_data).unwrap();
+                let message: Message = bincode::deserialize(input_data).unwrap();
                 match message {
                     Message::ParticipantList(participants) => {
                         println!("Participant list received ({} participants)", participants.len());
@@ -95,12 +95,12 @@ impl Participant {
                     _ => unreachable!(),
                 }
             }
-            NetEvent::Disconnected(endpoint) => {
-                if endpoint == self.discovery_endpoint {
-                    println!("Discovery server disconnected, closing");
-                    self.handler.stop();
-                }
+            NetEvent::Disconnected(endpoint) if endpoint == self.discovery_endpoint => {
+                println!("Discovery server disconnected, closing");
+                self.handler.stop();
             }
+            NetEvent::Disconnected(_) => (),
+            _ => (), // Events added by newer versions
         });
     }
 
@@ -27,7 +27,7 @@ pub fn run() {
         NetEvent::Connected(_, _) => unreachable!(),
         NetEvent::Accepted(_, _) => (),
         NetEvent::Message(endpoint, input_data) => {
-            let message: SenderMsg = bincode::deserialize(&input_data).unwrap();
+            let message: SenderMsg = bincode::deserialize(input_data).unwrap();
             match message {
                 SenderMsg::FileRequest(name, size) => {
                     let able = match File::create(format!("{}.recv", name)) {
@@ -49,7 +49,7 @@ pub fn run() {
                 }
                 SenderMsg::Chunk(data) => {
                     let transfer = transfers.get_mut(&endpoint).unwrap();
-                    transfer.file.write(&data).unwrap();
+                    transfer.file.write_all(&data).unwrap();
                     transfer.current_size += data.len();
 
                     let current = transfer.current_size as f32;
@@ -64,12 +64,12 @@ pub fn run() {
                 }
             }
         }
-        NetEvent::Disconnected(endpoint) => {
-            // Unexpected sender disconnection. Cleaning.
-            if transfers.contains_key(&endpoint) {
-                println!("\nUnexpected Sender disconnected");
-                transfers.remove(&endpoint);
-            }
+        // Unexpected sender disconnection. Cleaning.
+        NetEvent::Disconnected(endpoint) if transfers.contains_key(&endpoint) => {
+            println!("\nUnexpected Sender disconnected");
+            transfers.remove(&endpoint);
         }
+        NetEvent::Disconnected(_) => (),
+        _ => (), // Events added by newer versions
     });
 }
@@ -22,7 +22,7 @@ pub fn run(file_path: String) {
 
     let file_size = fs::metadata(&file_path).unwrap().len() as usize;
     let mut file = File::open(&file_path).unwrap();
-    let file_name: String = file_path.rsplit('/').into_iter().next().unwrap_or(&file_path).into();
+    let file_name: String = file_path.rsplit('/').next().unwrap_or(&file_path).into();
 
     let mut file_bytes_sent = 0;
     listener.for_each(move |event| match event {
@@ -40,7 +40,7 @@ pub fn run(file_path: String) {
             }
             NetEvent::Accepted(_, _) => unreachable!(),
             NetEvent::Message(_, input_data) => {
-                let message: ReceiverMsg = bincode::deserialize(&input_data).unwrap();
+                let message: ReceiverMsg = bincode::deserialize(input_data).unwrap();
                 match message {
                     ReceiverMsg::CanReceive(can) => match can {
                         true => handler.signals().send(Signal::SendChunk), // Start sending
@@ -55,6 +55,7 @@ pub fn run(file_path: String) {
                 handler.stop();
                 println!("\nReceiver disconnected");
             }
+            _ => (), // Events added by newer versions
         },
         NodeEvent::Signal(signal) => match signal {
             Signal::SendChunk => {
@@ -24,8 +24,9 @@ fn main() {
         }
         NetEvent::Accepted(_, _) => unreachable!(), // UDP is not connection-oriented
         NetEvent::Message(_, data) => {
-            println!("{} greets to the network!", String::from_utf8_lossy(&data));
+            println!("{} greets to the network!", String::from_utf8_lossy(data));
         }
         NetEvent::Disconnected(_) => (),
+        _ => (), // Events added by newer versions
     });
 }
@@ -30,7 +30,7 @@ pub fn run(transport: Transport, remote_addr: RemoteAddr) {
             }
             NetEvent::Accepted(_, _) => unreachable!(), // Only generated when a listener accepts
             NetEvent::Message(_, input_data) => {
-                let message: FromServerMessage = bincode::deserialize(&input_data).unwrap();
+                let message: FromServerMessage = bincode::deserialize(input_data).unwrap();
                 match message {
                     FromServerMessage::Pong(count) => {
                         println!("Pong from server: {} times", count)
@@ -42,6 +42,8 @@ pub fn run(transport: Transport, remote_addr: RemoteAddr) {
                 println!("Server is disconnected");
                 handler.stop();
             }
+            NetEvent::Error(_, kind) => println!("Server error: {:?}", kind), // Only by udp
+            _ => (), // Events added by newer versions
         },
         NodeEvent::Signal(signal) => match signal {
             Signal::Greet => {
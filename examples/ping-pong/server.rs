@@ -28,7 +28,7 @@ pub fn run(transport: Transport, addr: SocketAddr) {
             println!("Client ({}) connected (total clients: {})", endpoint.addr(), clients.len());
         }
         NetEvent::Message(endpoint, input_data) => {
-            let message: FromClientMessage = bincode::deserialize(&input_data).unwrap();
+            let message: FromClientMessage = bincode::deserialize(input_data).unwrap();
             match message {
                 FromClientMessage::Ping => {
                     let message = match clients.get_mut(&endpoint) {
@@ -58,5 +58,6 @@ pub fn run(transport: Transport, addr: SocketAddr) {
                 clients.len()
             );
         }
+        _ => (), // Events added by newer versions
     });
 }
@@ -60,6 +60,7 @@ fn throughput_message_io(transport: Transport, packet_size: usize) {
                 }
             }
             NetEvent::Disconnected(_) => (),
+            _ => (), // Events added by newer versions
         })
     };
 
@@ -144,7 +145,7 @@ fn throughput_native_tcp(packet_size: usize) {
 
                 let start_time = Instant::now();
                 while total_sent < EXPECTED_BYTES {
-                    sender.write(&message).unwrap();
+                    sender.write_all(&message).unwrap();
                     total_sent += message.len();
                 }
                 start_time
@@ -186,8 +187,8 @@ fn throughput_native_framed_tcp(packet_size: usize) {
                 let start_time = Instant::now();
                 while total_sent < EXPECTED_BYTES {
                     let encoded_size = encoding::encode_size(&message, &mut framming);
-                    sender.write(&encoded_size).unwrap();
-                    sender.write(&message).unwrap();
+                    sender.write_all(encoded_size).unwrap();
+                    sender.write_all(&message).unwrap();
                     total_sent += message.len();
                 }
                 start_time
@@ -234,7 +235,7 @@ fn throughput_native_ws(packet_size: usize) {
                 let (mut sender, _) = ws_connect(Url::parse(&url_addr).unwrap()).unwrap();
                 let start_time = Instant::now();
                 while total_sent < EXPECTED_BYTES {
-                    sender.write_message(Message::Binary(message.clone())).unwrap();
+                    sender.send(Message::Binary(message.clone())).unwrap();
                     total_sent += message.len();
                 }
                 start_time
@@ -245,7 +246,7 @@ fn throughput_native_ws(packet_size: usize) {
     let mut receiver = ws_accept(listener.accept().unwrap().0).unwrap();
     let mut total_received = 0;
     while total_received < EXPECTED_BYTES {
-        total_received += receiver.read_message().unwrap().len();
+        total_received += receiver.read().unwrap().len();
     }
     let end_time = Instant::now();
 
@@ -261,14 +262,14 @@ impl std::fmt::Display for ThroughputMeasure {
         if bytes_per_sec < 1000.0 {
             write!(f, "{:.2} B/s", bytes_per_sec)
         }
-        else if bytes_per_sec < 1000_000.0 {
+        else if bytes_per_sec < 1_000_000.0 {
             write!(f, "{:.2} KB/s", bytes_per_sec / 1000.0)
         }
-        else if bytes_per_sec < 1000_000_000.0 {
-            write!(f, "{:.2} MB/s", bytes_per_sec / 1000_000.0)
+        else if bytes_per_sec < 1_000_000_000.0 {
+            write!(f, "{:.2} MB/s", bytes_per_sec / 1_000_000.0)
         }
         else {
-            write!(f, "{:.2} GB/s", bytes_per_sec / 1000_000_000.0)
+            write!(f, "{:.2} GB/s", bytes_per_sec / 1_000_000_000.0)
         }
     }
 }
@@ -1,5 +1,16 @@
 mod template;
 
+#[cfg(any(feature = "tcp", feature = "udp"))]
+mod device;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+mod tos;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+mod socket_hook;
+#[cfg(feature = "udp")]
+mod pmtu;
+#[cfg(feature = "compression")]
+mod frame_compression;
+
 #[cfg(feature = "tcp")]
 pub mod tcp;
 #[cfg(feature = "tcp")]
@@ -8,5 +19,7 @@ pub mod framed_tcp;
 pub mod udp;
 #[cfg(feature = "websocket")]
 pub mod ws;
+#[cfg(feature = "in-process")]
+pub mod in_process;
 // Add new adapters here
 // ...
@@ -0,0 +1,34 @@
+use socket2::{Socket};
+
+use std::io::{self};
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+use std::ffi::{CString};
+#[cfg(target_os = "macos")]
+use std::num::{NonZeroU32};
+
+/// Binds the socket to the network interface named `device` (`SO_BINDTODEVICE`).
+/// The socket will only send and receive the traffic of that interface,
+/// regardless of the routing tables.
+/// In systems where it is not available, an [`io::ErrorKind::Unsupported`] error is returned.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn bind_to_device(socket: &Socket, device: &str) -> io::Result<()> {
+    let device = CString::new(device)?;
+    socket.bind_device(Some(device.as_bytes()))
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn bind_to_device(socket: &Socket, device: &str) -> io::Result<()> {
+    let device = CString::new(device)?;
+    match NonZeroU32::new(unsafe { libc::if_nametoindex(device.as_ptr()) }) {
+        Some(index) => socket.bind_device_by_index(Some(index)),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "Bind device interface not found")),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos")))]
+pub(crate) fn bind_to_device(_socket: &Socket, device: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Can not bind to the device '{}': not supported in this system", device),
+    ))
+}
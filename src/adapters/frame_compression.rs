@@ -0,0 +1,198 @@
+use crate::network::{SendStatus};
+use crate::util::encoding::{self, MAX_ENCODED_SIZE};
+use crate::util::thread::{OTHER_THREAD_ERR};
+
+use std::cell::{RefCell};
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// First byte of the frames once the compression is negotiated.
+pub(crate) const UNCOMPRESSED_FLAG: u8 = 0;
+pub(crate) const COMPRESSED_FLAG: u8 = 1;
+
+// Frames of the negotiation, see FramedTcpConnectConfig::with_compression().
+// OFFER is followed by the id of the codec offered.
+pub(crate) const NEGOTIATION_PREFIX: &[u8] = b"\xffmessage-io/compression/";
+const OFFER_TAG: u8 = 0;
+pub(crate) const SWITCH_TAG: u8 = 1;
+
+// Maximum expansion of the LZ4 block format, used to reject malformed sizes.
+const MAX_LZ4_RATIO: usize = 255;
+
+/// Codec used to compress the frames.
+/// See [`crate::adapters::framed_tcp::FramedTcpConnectConfig::with_compression()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    /// The [LZ4](https://lz4.github.io/lz4/) block format, fast and with moderate ratios.
+    Lz4,
+}
+
+impl Compression {
+    /// Identification of the codec in the negotiation.
+    fn id(self) -> u8 {
+        match self {
+            Compression::Lz4 => 1,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Compression::Lz4 => lz4_flex::block::compress(data),
+        }
+    }
+
+    fn decompress(self, data: &[u8], size: usize) -> Option<Vec<u8>> {
+        match self {
+            Compression::Lz4 => match size <= data.len().saturating_mul(MAX_LZ4_RATIO) {
+                true => lz4_flex::block::decompress(data, size).ok(),
+                false => None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FrameCompression {
+    compression: Compression,
+    min_size: usize,
+}
+
+impl FrameCompression {
+    pub fn new(compression: Compression, min_size: usize) -> Self {
+        Self { compression, min_size }
+    }
+
+    /// Compresses `data`, prefixed by its decompressed size.
+    /// Returns `None` if the data is too small or it does not get smaller by compressing it.
+    fn compress(&self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < self.min_size {
+            return None
+        }
+
+        let mut buf = [0; MAX_ENCODED_SIZE];
+        let mut payload = encoding::encode_size(data, &mut buf).to_vec();
+        payload.extend_from_slice(&self.compression.compress(data));
+        match payload.len() < data.len() {
+            true => Some(payload),
+            false => None,
+        }
+    }
+
+    pub fn decompress(&self, payload: &[u8]) -> Option<Vec<u8>> {
+        let (size, used_bytes) = encoding::decode_size(payload)?;
+        self.compression.decompress(&payload[used_bytes..], size)
+    }
+
+    pub fn offer_frame(&self) -> Vec<u8> {
+        [NEGOTIATION_PREFIX, &[OFFER_TAG, self.compression.id()]].concat()
+    }
+}
+
+/// Format of the frames received by a connection.
+struct IncomingFormat {
+    first_frame: bool, // The offer of the peer, if any, is its first frame.
+    compressed: bool,  // The peer has switched to the flagged frames.
+}
+
+/// State of the compression negotiated by a connection.
+/// The incoming format is only used by the thread that receives from the connection.
+pub(crate) struct Negotiation {
+    compression: Option<FrameCompression>,
+    peer_offered: AtomicBool, // The peer offered the same compression, set by the receive.
+    compressing: Mutex<bool>, // The switch was sent and the outgoing frames are flagged.
+    incoming: RefCell<IncomingFormat>,
+}
+
+impl Negotiation {
+    pub fn new(compression: Option<FrameCompression>) -> Self {
+        Self {
+            compression,
+            peer_offered: AtomicBool::new(false),
+            compressing: Mutex::new(false),
+            incoming: RefCell::new(IncomingFormat { first_frame: true, compressed: false }),
+        }
+    }
+
+    /// The frame to send before any other, if the compression is enabled.
+    pub fn offer(&self) -> Option<Vec<u8>> {
+        self.compression.as_ref().map(FrameCompression::offer_frame)
+    }
+
+    /// Locks the format of the outgoing frames while a frame is sent, if the compression is
+    /// enabled. Once the peer has offered the same compression, the switch to the flagged
+    /// frames is sent here by `send_control`, so it is ordered with the frames of the rest
+    /// of senders.
+    pub fn lock_compressing(
+        &self,
+        send_control: impl FnOnce(&[u8]) -> SendStatus,
+    ) -> Option<MutexGuard<'_, bool>> {
+        self.compression.as_ref()?;
+        let mut compressing = self.compressing.lock().expect(OTHER_THREAD_ERR);
+        if !*compressing && self.peer_offered.load(Ordering::Acquire) {
+            let switch = [NEGOTIATION_PREFIX, &[SWITCH_TAG]].concat();
+            *compressing = send_control(&switch) == SendStatus::Sent;
+        }
+        Some(compressing)
+    }
+
+    /// Compresses `data` if the compression was negotiated and it is worth it.
+    /// Returns the flag of the frame, if the frames are flagged,
+    /// and the compressed data, if it was compressed.
+    pub fn compress(
+        &self,
+        compressing: Option<&bool>,
+        data: &[u8],
+    ) -> (Option<u8>, Option<Vec<u8>>) {
+        match (&self.compression, compressing) {
+            (Some(compression), Some(true)) => match compression.compress(data) {
+                Some(compressed) => (Some(COMPRESSED_FLAG), Some(compressed)),
+                None => (Some(UNCOMPRESSED_FLAG), None),
+            },
+            _ => (None, None),
+        }
+    }
+
+    /// Processes a frame received, giving its message to `process_data`, if it is not
+    /// a frame of the negotiation.
+    /// Returns `false` if the frame is malformed.
+    pub fn receive(&self, frame: &[u8], process_data: &mut impl FnMut(&[u8])) -> bool {
+        let mut incoming = self.incoming.borrow_mut();
+        let negotiation = frame.strip_prefix(NEGOTIATION_PREFIX);
+        if std::mem::take(&mut incoming.first_frame) {
+            if let Some(negotiation) = negotiation {
+                self.process_negotiation(negotiation);
+                return true
+            }
+        }
+        let compression = match &self.compression {
+            Some(compression) if incoming.compressed => compression,
+            Some(_) if negotiation == Some(&[SWITCH_TAG]) => {
+                incoming.compressed = true;
+                return true
+            }
+            _ => {
+                process_data(frame);
+                return true
+            }
+        };
+        match frame.split_first() {
+            Some((&UNCOMPRESSED_FLAG, data)) => process_data(data),
+            Some((&COMPRESSED_FLAG, data)) => match compression.decompress(data) {
+                Some(data) => process_data(&data),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Checks the negotiation frame `frame` of the peer, without the negotiation prefix.
+    fn process_negotiation(&self, frame: &[u8]) {
+        match (&self.compression, frame) {
+            (Some(compression), &[OFFER_TAG, id]) if id == compression.compression.id() => {
+                self.peer_offered.store(true, Ordering::Release);
+            }
+            _ => log::trace!("FramedTcp: ignored compression negotiation {:?}", frame),
+        }
+    }
+}
@@ -1,22 +1,34 @@
-pub use socket2::{TcpKeepalive};
+pub use socket2::{TcpKeepalive, Socket};
+#[cfg(feature = "compression")]
+pub use super::frame_compression::{Compression};
 
 use crate::network::adapter::{
     Resource, Remote, Local, Adapter, SendStatus, AcceptedType, ReadStatus, ConnectionInfo,
     ListeningInfo, PendingStatus,
 };
+use crate::network::{FramingStats, SendCompletion};
 use crate::network::{RemoteAddr, Readiness, TransportConnect, TransportListen};
-use crate::util::encoding::{self, Decoder, MAX_ENCODED_SIZE};
+use super::socket_hook::{SocketHook};
+#[cfg(feature = "compression")]
+use super::frame_compression::{FrameCompression, Negotiation};
+use crate::util::encoding::{Decoder, MAX_ENCODED_SIZE};
+use crate::util::thread::{self, OTHER_THREAD_ERR};
 
 use mio::net::{TcpListener, TcpStream};
 use mio::event::{Source};
 
-use socket2::{Socket};
+use socket2::{Domain, Type, Protocol};
+use integer_encoding::{VarInt};
 
-use std::net::{SocketAddr};
+use std::net::{SocketAddr, Shutdown};
 use std::io::{self, ErrorKind, Read, Write};
-use std::ops::{Deref};
 use std::cell::{RefCell};
+use std::collections::{VecDeque};
 use std::mem::{forget, MaybeUninit};
+use std::sync::{Condvar, Mutex};
+#[cfg(feature = "compression")]
+use std::sync::{MutexGuard};
+use std::time::{Duration};
 #[cfg(target_os = "windows")]
 use std::os::windows::io::{FromRawSocket, AsRawSocket};
 #[cfg(not(target_os = "windows"))]
@@ -24,30 +36,273 @@ use std::os::{fd::AsRawFd, unix::io::FromRawFd};
 
 const INPUT_BUFFER_SIZE: usize = u16::MAX as usize; // 2^16 - 1
 
+// Wait of a send blocked by a full queue before writing the queue by itself,
+// in case the network thread no longer writes it, as when the connection is removed.
+const FULL_QUEUE_WAIT: Duration = Duration::from_millis(100);
+
+/// Maximum amount of data that the outbound queue of a connection can hold.
+/// See [`FramedTcpConnectConfig::with_queue_capacity()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QueueCapacity {
+    /// Bytes of the queued frames, including their headers.
+    Bytes(usize),
+
+    /// Number of queued messages.
+    Messages(usize),
+}
+
+/// What to do with a message sent to a connection whose outbound queue is full.
+/// See [`FramedTcpConnectConfig::with_queue_capacity()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DropPolicy {
+    /// The new message is discarded and the send returns [`SendStatus::Dropped`].
+    DropNewest,
+
+    /// The oldest queued messages are discarded to make room for the new one,
+    /// and the send returns [`SendStatus::SentDroppingOldest`].
+    /// A message that has started to be written is never discarded.
+    DropOldest,
+
+    /// The send waits until the network thread writes the queue and there is room
+    /// for the new message.
+    /// A send from the network thread, as from an event callback, can not wait for itself:
+    /// the new message is discarded as by [`DropPolicy::DropNewest`].
+    Block,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct QueueLimit {
+    capacity: QueueCapacity,
+    policy: DropPolicy,
+}
+
+/// Returns the bytes written before the payload of a frame: the size of the frame
+/// and the compression flag, if the compression is enabled.
+fn frame_header(
+    payload_len: usize,
+    flag: Option<u8>,
+    buf: &mut [u8; MAX_ENCODED_SIZE + 1],
+) -> &[u8] {
+    let flag_len = flag.is_some() as usize;
+    let size_len = (payload_len + flag_len).encode_var(buf);
+    if let Some(flag) = flag {
+        buf[size_len] = flag;
+    }
+    &buf[..size_len + flag_len]
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct FramedTcpConnectConfig {
+    bind_device: Option<String>,
     keepalive: Option<TcpKeepalive>,
+    write_chunk_size: Option<usize>,
+    user_timeout: Option<Duration>,
+    nodelay: bool,
+    coalesce_writes: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
+    #[cfg(feature = "compression")]
+    compression: Option<FrameCompression>,
+    queue_limit: Option<QueueLimit>,
 }
 
 impl FramedTcpConnectConfig {
+    /// Bind the connection to a specific interface, identified by its name.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_bind_device()`].
+    pub fn with_bind_device(mut self, device: String) -> Self {
+        self.bind_device = Some(device);
+        self
+    }
+
     /// Enables TCP keepalive settings on the socket.
     pub fn with_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
         self.keepalive = Some(keepalive);
         self
     }
+
+    /// Enables the chunked writing of messages.
+    /// Instead of blocking the sender until the whole message is written, the message is queued
+    /// and written by the network thread in chunks of at most `size` bytes, interleaved with
+    /// the processing of other resources.
+    /// This way, sending big messages does not stall the rest of the connections.
+    ///
+    /// Note that with this option [`SendStatus::Sent`] means that the message was queued.
+    /// Any queued data not yet written is lost if the resource is removed.
+    pub fn with_write_chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "The write chunk size must be greater than 0");
+        self.write_chunk_size = Some(size);
+        self
+    }
+
+    /// Sets the `TCP_USER_TIMEOUT` option on the socket.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_user_timeout()`].
+    pub fn with_user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `TCP_NODELAY` option on the socket.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_nodelay()`].
+    pub fn with_nodelay(mut self) -> Self {
+        self.nodelay = true;
+        self
+    }
+
+    /// Enables the coalescing of messages.
+    /// Instead of writing each message when it is sent, the messages sent to the same endpoint
+    /// are appended to a buffer that the network thread writes at once in its next iteration.
+    /// This way, sending several small messages in a row costs a single write.
+    /// The frame boundaries are kept, the receiver gets the same messages.
+    ///
+    /// Note that with this option [`SendStatus::Sent`] means that the message was queued,
+    /// as with [`FramedTcpConnectConfig::with_write_chunk_size()`], that can be combined with it.
+    pub fn with_coalesced_writes(mut self) -> Self {
+        self.coalesce_writes = true;
+        self
+    }
+
+    /// Sets the type of service byte of the IP header of the packets sent by the socket.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_tos()`].
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket of the connection to set any option not offered by
+    /// this configuration.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_socket()`].
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
+
+    /// Compresses the messages by `compression` (available through the *compression* feature).
+    /// The messages smaller than `min_size` bytes, or that do not get smaller when compressed,
+    /// are sent uncompressed.
+    /// Each frame carries a flag that tells if it is compressed,
+    /// so the receiver always gets the original messages.
+    ///
+    /// The compression is negotiated when the connection is established:
+    /// each end configured with a compression offers its codec by the first frame,
+    /// and starts compressing once the peer offers the same one.
+    /// If the peer does not offer it, as a listener without
+    /// [`FramedTcpListenConfig::with_compression()`], the messages are sent uncompressed.
+    /// Note that peers without this negotiation, of older versions or without the
+    /// *compression* feature, receive the offer as a message.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compression: Compression, min_size: usize) -> Self {
+        self.compression = Some(FrameCompression::new(compression, min_size));
+        self
+    }
+
+    /// Limits the outbound queue of the connection to `capacity`.
+    /// When a message does not fit, `policy` decides which messages are discarded,
+    /// if any, and the send reports it by its [`SendStatus`].
+    /// A message always fits into an empty queue, whatever its size.
+    ///
+    /// The messages are queued as with [`FramedTcpConnectConfig::with_write_chunk_size()`],
+    /// that can be combined with it, so a slow peer only congests its own connection.
+    pub fn with_queue_capacity(mut self, capacity: QueueCapacity, policy: DropPolicy) -> Self {
+        self.queue_limit = Some(QueueLimit { capacity, policy });
+        self
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct FramedTcpListenConfig {
+    bind_device: Option<String>,
     keepalive: Option<TcpKeepalive>,
+    write_chunk_size: Option<usize>,
+    user_timeout: Option<Duration>,
+    nodelay: bool,
+    coalesce_writes: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
+    #[cfg(feature = "compression")]
+    compression: Option<FrameCompression>,
+    queue_limit: Option<QueueLimit>,
 }
 
 impl FramedTcpListenConfig {
+    /// Bind the listener to a specific interface, identified by its name.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_bind_device()`].
+    pub fn with_bind_device(mut self, device: String) -> Self {
+        self.bind_device = Some(device);
+        self
+    }
+
     /// Enables TCP keepalive settings on client connection sockets.
     pub fn with_keepalive(mut self, keepalive: TcpKeepalive) -> Self {
         self.keepalive = Some(keepalive);
         self
     }
+
+    /// Enables the chunked writing of messages on client connection sockets.
+    /// See [`FramedTcpConnectConfig::with_write_chunk_size()`].
+    pub fn with_write_chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "The write chunk size must be greater than 0");
+        self.write_chunk_size = Some(size);
+        self
+    }
+
+    /// Sets the `TCP_USER_TIMEOUT` option on client connection sockets.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_user_timeout()`].
+    pub fn with_user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `TCP_NODELAY` option on client connection sockets.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_nodelay()`].
+    pub fn with_nodelay(mut self) -> Self {
+        self.nodelay = true;
+        self
+    }
+
+    /// Enables the coalescing of messages on client connection sockets.
+    /// See [`FramedTcpConnectConfig::with_coalesced_writes()`].
+    pub fn with_coalesced_writes(mut self) -> Self {
+        self.coalesce_writes = true;
+        self
+    }
+
+    /// Sets the type of service byte of the packets sent by the listener and its client
+    /// connection sockets.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_tos()`].
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket of the listener before binding it.
+    /// See [`crate::adapters::tcp::TcpListenConfig::with_socket()`].
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
+
+    /// Compresses the messages of client connection sockets by `compression`,
+    /// if the clients are configured with the same [`Compression`]
+    /// (available through the *compression* feature).
+    /// See [`FramedTcpConnectConfig::with_compression()`].
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compression: Compression, min_size: usize) -> Self {
+        self.compression = Some(FrameCompression::new(compression, min_size));
+        self
+    }
+
+    /// Limits the outbound queue of the accepted connections.
+    /// See [`FramedTcpConnectConfig::with_queue_capacity()`].
+    pub fn with_queue_capacity(mut self, capacity: QueueCapacity, policy: DropPolicy) -> Self {
+        self.queue_limit = Some(QueueLimit { capacity, policy });
+        self
+    }
 }
 
 pub(crate) struct FramedTcpAdapter;
@@ -56,20 +311,375 @@ impl Adapter for FramedTcpAdapter {
     type Local = LocalResource;
 }
 
+/// Frame queued to be written, with its header apart so the data does not need to be copied.
+struct QueuedFrame {
+    header: Vec<u8>,
+    data: Vec<u8>,
+    messages: usize, // Messages contained, several if they are coalesced.
+    completions: Vec<SendCompletion>, // Dropped with the frame if it is not written.
+}
+
+impl QueuedFrame {
+    fn len(&self) -> usize {
+        self.header.len() + self.data.len()
+    }
+
+    /// Bytes of the frame from `offset` until `end` or the end of its header.
+    fn slice(&self, offset: usize, end: usize) -> &[u8] {
+        match offset < self.header.len() {
+            true => &self.header[offset..end.min(self.header.len())],
+            false => &self.data[offset - self.header.len()..end - self.header.len()],
+        }
+    }
+}
+
+/// Result of making room in the queue for a new frame.
+enum Admission {
+    /// The frame fits, after dropping this number of queued messages.
+    Fits(usize),
+
+    /// The frame must be dropped.
+    Dropped,
+
+    /// The sender must wait until the queued data is written.
+    Full,
+}
+
+/// Frames pending to be written when the chunked writing or the coalescing is enabled.
+struct WriteQueue {
+    chunk_size: usize,
+    coalesce: bool, // The frames are appended to the last one to be written at once.
+    limit: Option<QueueLimit>,
+    frames: VecDeque<QueuedFrame>,
+    offset: usize, // Bytes of the front frame already written.
+    bytes: usize,  // Bytes queued and not written yet.
+    messages: usize,
+    blocked: bool, // The last write could not be completed, waiting for a write readiness.
+}
+
+impl WriteQueue {
+    fn new(chunk_size: usize, coalesce: bool, limit: Option<QueueLimit>) -> Self {
+        Self {
+            chunk_size,
+            coalesce,
+            limit,
+            frames: VecDeque::new(),
+            offset: 0,
+            bytes: 0,
+            messages: 0,
+            blocked: false,
+        }
+    }
+
+    /// Only creates the queue if the writes are deferred to the network thread.
+    fn with_config(
+        chunk_size: Option<usize>,
+        coalesce: bool,
+        limit: Option<QueueLimit>,
+    ) -> Option<Self> {
+        match (chunk_size, coalesce, limit) {
+            (None, false, None) => None,
+            (chunk_size, coalesce, limit) => {
+                Some(Self::new(chunk_size.unwrap_or(usize::MAX), coalesce, limit))
+            }
+        }
+    }
+
+    fn fits(&self, limit: &QueueLimit, size: usize) -> bool {
+        self.frames.is_empty()
+            || match limit.capacity {
+                QueueCapacity::Bytes(bytes) => self.bytes + size <= bytes,
+                QueueCapacity::Messages(messages) => self.messages < messages,
+            }
+    }
+
+    /// Makes room for a frame of `size` bytes following the drop policy of the queue.
+    fn admit(&mut self, size: usize) -> Admission {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Admission::Fits(0),
+        };
+        if self.fits(&limit, size) {
+            return Admission::Fits(0)
+        }
+
+        match limit.policy {
+            DropPolicy::DropNewest => Admission::Dropped,
+            DropPolicy::Block => Admission::Full,
+            DropPolicy::DropOldest => {
+                // The frame being written can not be dropped without corrupting the stream.
+                let first = match self.offset > 0 {
+                    true => 1,
+                    false => 0,
+                };
+                let mut dropped = 0;
+                while !self.fits(&limit, size) && self.frames.len() > first {
+                    let frame = self.frames.remove(first).unwrap();
+                    self.bytes -= frame.len();
+                    self.messages -= frame.messages;
+                    dropped += frame.messages;
+                }
+                match self.fits(&limit, size) {
+                    true => Admission::Fits(dropped),
+                    false => Admission::Dropped,
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, data: &[u8], flag: Option<u8>) {
+        let mut buf = [0; MAX_ENCODED_SIZE + 1]; // used to avoid a heap allocation
+        let header = frame_header(data.len(), flag, &mut buf);
+        self.bytes += header.len() + data.len();
+        self.messages += 1;
+
+        if self.coalesce {
+            if let Some(frame) = self.frames.back_mut() {
+                frame.data.extend_from_slice(header);
+                frame.data.extend_from_slice(data);
+                frame.messages += 1;
+                return
+            }
+        }
+
+        let mut frame = Vec::with_capacity(header.len() + data.len());
+        frame.extend_from_slice(header);
+        frame.extend_from_slice(data);
+        self.frames.push_back(QueuedFrame {
+            header: Vec::new(),
+            data: frame,
+            messages: 1,
+            completions: Vec::new(),
+        });
+    }
+
+    fn push_owned(&mut self, data: Vec<u8>, flag: Option<u8>) {
+        if self.coalesce {
+            return self.push(&data, flag)
+        }
+
+        let mut buf = [0; MAX_ENCODED_SIZE + 1];
+        let header = frame_header(data.len(), flag, &mut buf);
+        self.bytes += header.len() + data.len();
+        self.messages += 1;
+
+        // The header is the only part copied, the data is queued as it is.
+        self.frames.push_back(QueuedFrame {
+            header: header.to_vec(),
+            data,
+            messages: 1,
+            completions: Vec::new(),
+        });
+    }
+
+    /// Completes `completion` once the last frame pushed is written.
+    fn track_last(&mut self, completion: SendCompletion) {
+        let frame = self.frames.back_mut().expect("A frame was just pushed");
+        frame.completions.push(completion);
+    }
+
+    /// Writes at most `chunk_size` bytes of the queued frames.
+    fn write_chunk(&mut self, mut stream: &TcpStream) -> io::Result<()> {
+        self.blocked = false;
+        let mut total_bytes_sent = 0;
+        while total_bytes_sent < self.chunk_size {
+            let frame = match self.frames.front() {
+                Some(frame) => frame,
+                None => break,
+            };
+            let chunk_left = self.chunk_size - total_bytes_sent;
+            let end = frame.len().min(self.offset.saturating_add(chunk_left));
+            match stream.write(frame.slice(self.offset, end)) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(bytes_sent) => {
+                    total_bytes_sent += bytes_sent;
+                    self.offset += bytes_sent;
+                    self.bytes -= bytes_sent;
+                    if self.offset == frame.len() {
+                        self.messages -= frame.messages;
+                        let frame = self.frames.pop_front().unwrap();
+                        frame.completions.into_iter().for_each(SendCompletion::complete);
+                        self.offset = 0;
+                    }
+                }
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.blocked = true;
+                    break
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    fn wants_write(&self) -> bool {
+        !self.blocked && !self.frames.is_empty()
+    }
+}
+
 pub(crate) struct RemoteResource {
     stream: TcpStream,
     decoder: RefCell<Decoder>,
+    framing_stats: Mutex<FramingStats>, // Copied from the decoder after each read.
     keepalive: Option<TcpKeepalive>,
+    write_queue: Option<Mutex<WriteQueue>>,
+    written: Condvar, // Notified when the network thread writes the queue.
+    #[cfg(feature = "compression")]
+    negotiation: Negotiation,
 }
 
 // SAFETY:
 // That RefCell<Decoder> can be used with Sync because the decoder is only used in the read_event,
 // that will be called always from the same thread. This way, we save the cost of a Mutex.
+// The same applies to the incoming format of the compression negotiation.
 unsafe impl Sync for RemoteResource {}
 
 impl RemoteResource {
-    fn new(stream: TcpStream, keepalive: Option<TcpKeepalive>) -> Self {
-        Self { stream, decoder: RefCell::new(Decoder::default()), keepalive }
+    fn new(
+        stream: TcpStream,
+        keepalive: Option<TcpKeepalive>,
+        write_chunk_size: Option<usize>,
+        coalesce_writes: bool,
+        queue_limit: Option<QueueLimit>,
+    ) -> Self {
+        Self {
+            stream,
+            decoder: RefCell::new(Decoder::default()),
+            framing_stats: Mutex::new(FramingStats::default()),
+            keepalive,
+            write_queue: WriteQueue::with_config(write_chunk_size, coalesce_writes, queue_limit)
+                .map(Mutex::new),
+            written: Condvar::new(),
+            #[cfg(feature = "compression")]
+            negotiation: Negotiation::new(None),
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    fn with_compression(mut self, compression: Option<FrameCompression>) -> Self {
+        self.negotiation = Negotiation::new(compression);
+        self
+    }
+
+    /// Locks the format of the outgoing frames while the returned guard lives,
+    /// and gives the flag of the frame of `data` and its compressed data, if any.
+    /// See [`Negotiation::compress()`].
+    #[cfg(feature = "compression")]
+    fn format(&self, data: &[u8]) -> (Option<MutexGuard<'_, bool>>, Option<u8>, Option<Vec<u8>>) {
+        let compressing = self.negotiation.lock_compressing(|frame| self.send_control(frame));
+        let (flag, compressed) = self.negotiation.compress(compressing.as_deref(), data);
+        (compressing, flag, compressed)
+    }
+
+    /// Without the compression the frames have no flag.
+    #[cfg(not(feature = "compression"))]
+    fn format(&self, _data: &[u8]) -> ((), Option<u8>, Option<Vec<u8>>) {
+        ((), None, None)
+    }
+
+    /// Gives the message of `frame` to `process_data`, if it has one.
+    /// Returns `false` if the frame is malformed.
+    #[cfg(feature = "compression")]
+    fn process_frame(&self, frame: &[u8], process_data: &mut impl FnMut(&[u8])) -> bool {
+        self.negotiation.receive(frame, process_data)
+    }
+
+    #[cfg(not(feature = "compression"))]
+    fn process_frame(&self, frame: &[u8], process_data: &mut impl FnMut(&[u8])) -> bool {
+        process_data(frame);
+        true
+    }
+
+    /// Sends a frame of the negotiation, that is queued whatever the queue limit is.
+    #[cfg(feature = "compression")]
+    fn send_control(&self, frame: &[u8]) -> SendStatus {
+        match &self.write_queue {
+            Some(write_queue) => {
+                write_queue.lock().expect(OTHER_THREAD_ERR).push(frame, None);
+                SendStatus::Sent
+            }
+            None => self.write_frame(frame, None),
+        }
+    }
+
+    /// Writes a frame with `data` as payload, preceded by `flag` if any.
+    fn write_frame(&self, data: &[u8], flag: Option<u8>) -> SendStatus {
+        if let Some(write_queue) = &self.write_queue {
+            // The network thread will write it.
+            return self.enqueue(write_queue, data.len(), flag, |queue| queue.push(data, flag))
+        }
+
+        let mut buf = [0; MAX_ENCODED_SIZE + 1]; // used to avoid a heap allocation
+        let encoded_size = frame_header(data.len(), flag, &mut buf);
+
+        let mut total_bytes_sent = 0;
+        let total_bytes = encoded_size.len() + data.len();
+        loop {
+            let data_to_send = match total_bytes_sent < encoded_size.len() {
+                true => &encoded_size[total_bytes_sent..],
+                false => &data[total_bytes_sent - encoded_size.len()..],
+            };
+
+            let mut stream = &self.stream;
+            match stream.write(data_to_send) {
+                Ok(bytes_sent) => {
+                    total_bytes_sent += bytes_sent;
+                    if total_bytes_sent == total_bytes {
+                        break SendStatus::Sent
+                    }
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(ref err) if super::tcp::is_disconnection(err) => {
+                    log::trace!("TCP send to a closed connection: {}", err);
+                    break SendStatus::ResourceNotFound
+                }
+                Err(err) => {
+                    log::error!("TCP send error: {}", err);
+                    break SendStatus::ResourceNotFound // should not happen
+                }
+            }
+        }
+    }
+
+    /// Queues a frame of `payload_len` bytes by `push` if the queue limit allows it.
+    fn enqueue(
+        &self,
+        write_queue: &Mutex<WriteQueue>,
+        payload_len: usize,
+        flag: Option<u8>,
+        push: impl FnOnce(&mut WriteQueue),
+    ) -> SendStatus {
+        let mut buf = [0; MAX_ENCODED_SIZE + 1];
+        let size = frame_header(payload_len, flag, &mut buf).len() + payload_len;
+        let mut queue = write_queue.lock().expect(OTHER_THREAD_ERR);
+        let status = loop {
+            match queue.admit(size) {
+                Admission::Fits(0) => break SendStatus::Sent,
+                Admission::Fits(dropped) => {
+                    log::trace!("FramedTcp: dropped {} queued messages", dropped);
+                    break SendStatus::SentDroppingOldest
+                }
+                Admission::Dropped => return SendStatus::Dropped,
+                // The thread that writes the queue can not wait for itself.
+                Admission::Full if thread::is_network_thread() => return SendStatus::Dropped,
+                Admission::Full => {
+                    let (guard, wait) = self
+                        .written
+                        .wait_timeout(queue, FULL_QUEUE_WAIT)
+                        .expect(OTHER_THREAD_ERR);
+                    queue = guard;
+                    if wait.timed_out() {
+                        if let Err(err) = queue.write_chunk(&self.stream) {
+                            log::trace!("TCP send error while the queue is full: {}", err);
+                            return SendStatus::ResourceNotFound
+                        }
+                    }
+                }
+            }
+        };
+        push(&mut queue);
+        status
     }
 }
 
@@ -89,10 +699,52 @@ impl Remote for RemoteResource {
             _ => panic!("Internal error: Got wrong config"),
         };
         let peer_addr = *remote_addr.socket_addr();
-        let stream = TcpStream::connect(peer_addr)?;
+
+        let socket =
+            Socket::new(Domain::for_address(peer_addr), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_nonblocking(true)?;
+
+        if config.nodelay {
+            socket.set_nodelay(true)?;
+        }
+
+        if let Some(timeout) = config.user_timeout {
+            super::tcp::set_user_timeout(&socket, timeout)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, peer_addr, tos)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
+        if let Some(bind_device) = config.bind_device {
+            super::device::bind_to_device(&socket, &bind_device)?;
+        }
+
+        match socket.connect(&peer_addr.into()) {
+            #[cfg(unix)]
+            Err(e) if e.raw_os_error() != Some(libc::EINPROGRESS) => return Err(e),
+            #[cfg(windows)]
+            Err(e) if e.kind() != io::ErrorKind::WouldBlock => return Err(e),
+            _ => {}
+        }
+
+        let stream = TcpStream::from_std(socket.into());
         let local_addr = stream.local_addr()?;
+        let remote = RemoteResource::new(
+            stream,
+            config.keepalive,
+            config.write_chunk_size,
+            config.coalesce_writes,
+            config.queue_limit,
+        );
+        #[cfg(feature = "compression")]
+        let remote = remote.with_compression(config.compression);
         Ok(ConnectionInfo {
-            remote: RemoteResource::new(stream, config.keepalive),
+            remote,
             local_addr,
             peer_addr,
         })
@@ -103,15 +755,31 @@ impl Remote for RemoteResource {
         let mut input_buffer = unsafe { buffer.assume_init() }; // Avoid to initialize the array
 
         loop {
-            let stream = &self.stream;
-            match stream.deref().read(&mut input_buffer) {
+            let mut stream = &self.stream;
+            match stream.read(&mut input_buffer) {
                 Ok(0) => break ReadStatus::Disconnected,
                 Ok(size) => {
                     let data = &input_buffer[..size];
                     log::trace!("Decoding {} bytes", data.len());
-                    self.decoder.borrow_mut().decode(data, |decoded_data| {
-                        process_data(decoded_data);
+                    let mut decoder = self.decoder.borrow_mut();
+                    let mut frames_decoded = 0;
+                    let mut malformed = false;
+                    decoder.decode(data, |decoded_data| {
+                        frames_decoded += 1;
+                        if !malformed {
+                            malformed = !self.process_frame(decoded_data, &mut process_data);
+                        }
                     });
+
+                    let mut stats = self.framing_stats.lock().expect(OTHER_THREAD_ERR);
+                    stats.buffered_bytes = decoder.stored_size();
+                    stats.frame_size = decoder.stored_message_size();
+                    stats.frames_decoded += frames_decoded;
+
+                    if malformed {
+                        log::error!("FramedTcp: malformed compressed frame, closing connection");
+                        break ReadStatus::Disconnected
+                    }
                 }
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
                 Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
@@ -129,30 +797,38 @@ impl Remote for RemoteResource {
     }
 
     fn send(&self, data: &[u8]) -> SendStatus {
-        let mut buf = [0; MAX_ENCODED_SIZE]; // used to avoid a heap allocation
-        let encoded_size = encoding::encode_size(data, &mut buf);
+        let (_format, flag, compressed) = self.format(data);
+        self.write_frame(compressed.as_deref().unwrap_or(data), flag)
+    }
 
-        let mut total_bytes_sent = 0;
-        let total_bytes = encoded_size.len() + data.len();
-        loop {
-            let data_to_send = match total_bytes_sent < encoded_size.len() {
-                true => &encoded_size[total_bytes_sent..],
-                false => &data[total_bytes_sent - encoded_size.len()..],
-            };
+    fn send_owned(&self, data: Vec<u8>) -> SendStatus {
+        match &self.write_queue {
+            Some(write_queue) => {
+                let (_format, flag, compressed) = self.format(&data);
+                let data = compressed.unwrap_or(data);
+                self.enqueue(write_queue, data.len(), flag, |queue| queue.push_owned(data, flag))
+            }
+            None => self.send(&data),
+        }
+    }
 
-            let stream = &self.stream;
-            match stream.deref().write(data_to_send) {
-                Ok(bytes_sent) => {
-                    total_bytes_sent += bytes_sent;
-                    if total_bytes_sent == total_bytes {
-                        break SendStatus::Sent
-                    }
-                }
-                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
-                Err(err) => {
-                    log::error!("TCP receive error: {}", err);
-                    break SendStatus::ResourceNotFound // should not happen
+    fn send_tracked(&self, data: Vec<u8>, completion: SendCompletion) -> SendStatus {
+        match &self.write_queue {
+            Some(write_queue) => {
+                let (_format, flag, compressed) = self.format(&data);
+                let data = compressed.unwrap_or(data);
+                self.enqueue(write_queue, data.len(), flag, |queue| {
+                    queue.push_owned(data, flag);
+                    queue.track_last(completion);
+                })
+            }
+            None => {
+                // Written completely before returning.
+                let status = self.send(&data);
+                if status == SendStatus::Sent {
+                    completion.complete();
                 }
+                status
             }
         }
     }
@@ -161,6 +837,12 @@ impl Remote for RemoteResource {
         let status = super::tcp::check_stream_ready(&self.stream);
 
         if status == PendingStatus::Ready {
+            #[cfg(feature = "compression")]
+            if let Some(offer) = self.negotiation.offer() {
+                // First frame of the connection, before the user can send any message.
+                self.send_control(&offer);
+            }
+
             if let Some(keepalive) = &self.keepalive {
                 #[cfg(target_os = "windows")]
                 let socket = unsafe { Socket::from_raw_socket(self.stream.as_raw_socket()) };
@@ -178,16 +860,64 @@ impl Remote for RemoteResource {
 
         status
     }
+
+    fn ready_to_write(&self) -> bool {
+        if let Some(write_queue) = &self.write_queue {
+            let mut write_queue = write_queue.lock().expect(OTHER_THREAD_ERR);
+            let result = write_queue.write_chunk(&self.stream);
+            self.written.notify_all();
+            if let Err(err) = result {
+                log::error!("TCP send error: {}", err);
+                return false
+            }
+        }
+        true
+    }
+
+    fn wants_write(&self) -> bool {
+        match &self.write_queue {
+            Some(write_queue) => write_queue.lock().expect(OTHER_THREAD_ERR).wants_write(),
+            None => false,
+        }
+    }
+
+    fn has_queued_data(&self) -> bool {
+        match &self.write_queue {
+            Some(write_queue) => !write_queue.lock().expect(OTHER_THREAD_ERR).frames.is_empty(),
+            None => false,
+        }
+    }
+
+    fn queued_bytes(&self) -> Option<usize> {
+        let write_queue = self.write_queue.as_ref()?;
+        Some(write_queue.lock().expect(OTHER_THREAD_ERR).bytes)
+    }
+
+    fn framing_stats(&self) -> Option<FramingStats> {
+        Some(*self.framing_stats.lock().expect(OTHER_THREAD_ERR))
+    }
+
+    fn close(&self) {
+        if let Err(err) = self.stream.shutdown(Shutdown::Write) {
+            log::trace!("TCP shutdown error: {}", err);
+        }
+    }
 }
 
 pub(crate) struct LocalResource {
-    listener: TcpListener,
+    listener: Mutex<TcpListener>,
     keepalive: Option<TcpKeepalive>,
+    write_chunk_size: Option<usize>,
+    nodelay: bool,
+    coalesce_writes: bool,
+    #[cfg(feature = "compression")]
+    compression: Option<FrameCompression>,
+    queue_limit: Option<QueueLimit>,
 }
 
 impl Resource for LocalResource {
     fn source(&mut self) -> &mut dyn Source {
-        &mut self.listener
+        self.listener.get_mut().expect(OTHER_THREAD_ERR)
     }
 }
 
@@ -199,25 +929,241 @@ impl Local for LocalResource {
             TransportListen::FramedTcp(config) => config,
             _ => panic!("Internal error: Got wrong config"),
         };
-        let listener = TcpListener::bind(addr)?;
+
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_nonblocking(true)?;
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+
+        // The accepted sockets inherit them from the listener.
+        if let Some(timeout) = config.user_timeout {
+            super::tcp::set_user_timeout(&socket, timeout)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, addr, tos)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
+        if let Some(bind_device) = config.bind_device {
+            super::device::bind_to_device(&socket, &bind_device)?;
+        }
+
+        socket.bind(&addr.into())?;
+        socket.listen(super::tcp::LISTENER_BACKLOG)?;
+
+        let listener = TcpListener::from_std(socket.into());
         let local_addr = listener.local_addr().unwrap();
         Ok(ListeningInfo {
-            local: { LocalResource { listener, keepalive: config.keepalive } },
+            local: {
+                LocalResource {
+                    listener: Mutex::new(listener),
+                    keepalive: config.keepalive,
+                    write_chunk_size: config.write_chunk_size,
+                    nodelay: config.nodelay,
+                    coalesce_writes: config.coalesce_writes,
+                    #[cfg(feature = "compression")]
+                    compression: config.compression,
+                    queue_limit: config.queue_limit,
+                }
+            },
             local_addr,
         })
     }
 
-    fn accept(&self, mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
-        loop {
-            match self.listener.accept() {
-                Ok((stream, addr)) => accept_remote(AcceptedType::Remote(
-                    addr,
-                    RemoteResource::new(stream, self.keepalive.clone()),
-                )),
-                Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+    fn accept(&self, accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
+        self.accept_limited(usize::MAX, accept_remote);
+    }
+
+    fn accept_limited(
+        &self,
+        limit: usize,
+        mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>),
+    ) -> bool {
+        let mut accepted = 0;
+        while accepted < limit {
+            let result = self.listener.lock().expect(OTHER_THREAD_ERR).accept();
+            match result {
+                Ok((stream, addr)) => {
+                    accepted += 1;
+                    if self.nodelay {
+                        if let Err(err) = stream.set_nodelay(true) {
+                            log::error!("TCP set nodelay error: {}", err);
+                        }
+                    }
+                    let remote = RemoteResource::new(
+                        stream,
+                        self.keepalive.clone(),
+                        self.write_chunk_size,
+                        self.coalesce_writes,
+                        self.queue_limit,
+                    );
+                    #[cfg(feature = "compression")]
+                    let remote = remote.with_compression(self.compression);
+                    accept_remote(AcceptedType::Remote(addr, remote))
+                }
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => return false,
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
-                Err(err) => break log::error!("TCP accept error: {}", err), // Should not happen
+                Err(err) => {
+                    log::error!("TCP accept error: {}", err); // Should not happen
+                    return false
+                }
             }
         }
+        true
+    }
+
+    fn update_source(&self, update: &mut dyn FnMut(&mut dyn Source)) {
+        update(&mut *self.listener.lock().expect(OTHER_THREAD_ERR));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::encoding::{self};
+
+    #[test]
+    fn write_queue_push_owned_without_copy() {
+        let mut queue = WriteQueue::new(1024, false, None);
+        let data = vec![42; 1000];
+        let data_ptr = data.as_ptr();
+
+        queue.push_owned(data, None);
+        assert_eq!(queue.frames.len(), 1);
+        assert_eq!(queue.frames[0].data.as_ptr(), data_ptr);
+
+        let mut buf = [0; MAX_ENCODED_SIZE];
+        let frame = &queue.frames[0];
+        assert_eq!(frame.header, encoding::encode_size(&frame.data, &mut buf));
+    }
+
+    #[test]
+    fn write_queue_coalesced() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let stream = TcpStream::from_std(stream);
+        let (mut peer, _) = listener.accept().unwrap();
+
+        let mut queue = WriteQueue::new(usize::MAX, true, None);
+        for i in 0..100u8 {
+            queue.push(&[i; 10], None);
+        }
+        queue.push_owned(vec![100; 10], None);
+
+        // All the messages are written by the same write.
+        assert_eq!(queue.frames.len(), 1);
+        queue.write_chunk(&stream).unwrap();
+        assert!(queue.frames.is_empty());
+        drop(stream);
+
+        let mut data = Vec::new();
+        peer.read_to_end(&mut data).unwrap();
+        let mut messages = Vec::new();
+        Decoder::default().decode(&data, |message| messages.push(message.to_vec()));
+        assert_eq!(messages, (0..=100u8).map(|i| vec![i; 10]).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compressed_frames_on_the_wire() {
+        use crate::network::{self, NetEvent};
+        use super::super::frame_compression::{
+            COMPRESSED_FLAG, UNCOMPRESSED_FLAG, NEGOTIATION_PREFIX, SWITCH_TAG,
+        };
+
+        let large_message = b"message-io ".repeat(1000);
+        let small_message = b"message-io ".to_vec();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let compression = FrameCompression::new(Compression::Lz4, 100);
+
+        let (controller, mut processor) = network::split();
+        let config = FramedTcpConnectConfig::default().with_compression(Compression::Lz4, 100);
+        let addr = listener.local_addr().unwrap();
+        let (endpoint, _) =
+            controller.connect_with(TransportConnect::FramedTcp(config), addr).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+        processor.process_poll_event(Some(Duration::from_secs(1)), |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            _ => unreachable!(),
+        });
+        // Sent uncompressed, the peer has not offered the compression yet.
+        controller.send(endpoint, &small_message);
+
+        let offer = compression.offer_frame();
+        let mut buf = [0; MAX_ENCODED_SIZE];
+        peer.write_all(encoding::encode_size(&offer, &mut buf)).unwrap();
+        peer.write_all(&offer).unwrap();
+        processor.process_poll_events_until_timeout(Duration::from_millis(100), |_| {
+            unreachable!() // The offer is not a message
+        });
+        controller.send(endpoint, &large_message);
+        controller.send(endpoint, &small_message);
+        controller.remove(endpoint.resource_id());
+
+        let mut data = Vec::new();
+        peer.read_to_end(&mut data).unwrap();
+        let mut frames = Vec::new();
+        Decoder::default().decode(&data, |frame| frames.push(frame.to_vec()));
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0], offer);
+        assert_eq!(frames[1], small_message);
+        assert_eq!(frames[2], [NEGOTIATION_PREFIX, &[SWITCH_TAG]].concat());
+        assert_eq!(frames[3][0], COMPRESSED_FLAG);
+        assert!(frames[3].len() < large_message.len() / 10);
+        assert_eq!(compression.decompress(&frames[3][1..]).unwrap(), large_message);
+        assert_eq!(frames[4], [&[UNCOMPRESSED_FLAG], &small_message[..]].concat());
+
+        // Both ends compressing, with the queued writes.
+        let config = FramedTcpListenConfig::default().with_compression(Compression::Lz4, 100);
+        let (_, addr) =
+            controller.listen_with(TransportListen::FramedTcp(config), "127.0.0.1:0").unwrap();
+        let config = FramedTcpConnectConfig::default()
+            .with_compression(Compression::Lz4, 100)
+            .with_coalesced_writes();
+        controller.connect_with(TransportConnect::FramedTcp(config), addr).unwrap();
+
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(Duration::from_secs(1), |net_event| {
+            match net_event {
+                NetEvent::Connected(endpoint, _) => {
+                    controller.send(endpoint, &b"compressed ".repeat(100));
+                    controller.send_owned(endpoint, b"raw".to_vec());
+                }
+                NetEvent::Accepted(..) => (),
+                NetEvent::Message(_, data) => received.push(data.to_vec()),
+                _ => unreachable!(),
+            }
+        });
+        assert_eq!(received, vec![b"compressed ".repeat(100), b"raw".to_vec()]);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn compression_only_by_one_end() {
+        use crate::network::{self, NetEvent};
+
+        let message = b"message-io ".repeat(100);
+        let (controller, mut processor) = network::split();
+        let config = FramedTcpListenConfig::default().with_compression(Compression::Lz4, 100);
+        let (_, addr) =
+            controller.listen_with(TransportListen::FramedTcp(config), "127.0.0.1:0").unwrap();
+        controller.connect_with(TransportConnect::FramedTcp(Default::default()), addr).unwrap();
+
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(Duration::from_secs(1), |net_event| {
+            match net_event {
+                NetEvent::Connected(endpoint, _) | NetEvent::Accepted(endpoint, _) => {
+                    controller.send(endpoint, &message);
+                }
+                NetEvent::Message(_, data) => received.push(data.to_vec()),
+                _ => unreachable!(),
+            }
+        });
+        assert_eq!(received, vec![message.clone(), message]);
     }
 }
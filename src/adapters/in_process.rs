@@ -0,0 +1,365 @@
+use crate::network::adapter::{
+    Resource, Remote, Local, Adapter, SendStatus, AcceptedType, ReadStatus, ConnectionInfo,
+    ListeningInfo, PendingStatus,
+};
+use crate::network::{RemoteAddr, Readiness, ReadinessNotifier, TransportConnect, TransportListen};
+use crate::util::thread::{OTHER_THREAD_ERR};
+
+use mio::{Interest, Registry, Token};
+use mio::event::{Source};
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::io::{self, ErrorKind};
+use std::sync::{Arc, Mutex};
+
+/// Scheme of the addresses used to connect to a listener by its name,
+/// as `inproc://my-service`. See [`InProcessListenConfig::with_name()`].
+pub const NAME_SCHEME: &str = "inproc://";
+
+lazy_static::lazy_static! {
+    static ref LISTENERS: Mutex<Listeners> = Mutex::new(Listeners::default());
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InProcessConnectConfig {}
+
+#[derive(Clone, Debug, Default)]
+pub struct InProcessListenConfig {
+    name: Option<String>,
+}
+
+impl InProcessListenConfig {
+    /// Registers the listener also by `name`, so the remotes can connect to it with
+    /// `inproc://{name}` instead of its address.
+    /// If other listener of the process has the same name,
+    /// the listening fails with an [`std::io::ErrorKind::AddrInUse`] error.
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = Some(name);
+        self
+    }
+}
+
+/// Listeners of the process, by their virtual address and their name.
+#[derive(Default)]
+struct Listeners {
+    by_addr: HashMap<SocketAddr, Arc<Backlog>>,
+    by_name: HashMap<String, SocketAddr>,
+    last_port: u16,
+}
+
+impl Listeners {
+    /// Finds the listener of `addr`, or the listener of its port in the unspecified address,
+    /// as a socket listening in `0.0.0.0` receives the connections to `127.0.0.1`.
+    fn find(&self, addr: SocketAddr) -> Option<&Arc<Backlog>> {
+        let unspecified = SocketAddr::new(unspecified_ip(addr.ip()), addr.port());
+        self.by_addr.get(&addr).or_else(|| self.by_addr.get(&unspecified))
+    }
+
+    /// Gives a virtual port not used by any listener.
+    fn next_port(&mut self) -> io::Result<u16> {
+        for _ in 0..u16::MAX {
+            self.last_port = self.last_port.checked_add(1).unwrap_or(1);
+            let port = self.last_port;
+            if !self.by_addr.keys().any(|addr| addr.port() == port) {
+                return Ok(port)
+            }
+        }
+        Err(io::Error::new(ErrorKind::AddrNotAvailable, "No virtual port available"))
+    }
+}
+
+fn unspecified_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    }
+}
+
+/// The in-process resources are not OS resources, their readiness is generated by themselves
+/// through their [`ReadinessNotifier`], so their source does nothing in the poll.
+struct VirtualSource;
+impl Source for VirtualSource {
+    fn register(&mut self, _: &Registry, _: Token, _: Interest) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn reregister(&mut self, _: &Registry, _: Token, _: Interest) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _: &Registry) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Messages sent in one direction of a connection.
+#[derive(Default)]
+struct Channel(Mutex<ChannelState>);
+
+#[derive(Default)]
+struct ChannelState {
+    messages: VecDeque<Vec<u8>>,
+    receiver: Option<ReadinessNotifier>,
+    sender_closed: bool,
+    receiver_closed: bool,
+}
+
+/// Connections waiting to be accepted by a listener.
+#[derive(Default)]
+struct Backlog(Mutex<BacklogState>);
+
+#[derive(Default)]
+struct BacklogState {
+    remotes: VecDeque<(SocketAddr, RemoteResource)>,
+    notifier: Option<ReadinessNotifier>,
+}
+
+pub(crate) struct InProcessAdapter;
+impl Adapter for InProcessAdapter {
+    type Remote = RemoteResource;
+    type Local = LocalResource;
+}
+
+pub(crate) struct RemoteResource {
+    source: VirtualSource,
+    link: Option<Link>, // None if there was no listener to connect to.
+}
+
+struct Link {
+    inbox: Arc<Channel>,
+    outbox: Arc<Channel>,
+}
+
+impl RemoteResource {
+    /// Creates the two ends of a connection.
+    fn pair() -> (Self, Self) {
+        let (one, other) = (Arc::new(Channel::default()), Arc::new(Channel::default()));
+        let link = Link { inbox: one.clone(), outbox: other.clone() };
+        let peer_link = Link { inbox: other, outbox: one };
+        (
+            Self { source: VirtualSource, link: Some(link) },
+            Self { source: VirtualSource, link: Some(peer_link) },
+        )
+    }
+
+    fn push(&self, data: Vec<u8>) -> SendStatus {
+        let link = match &self.link {
+            Some(link) => link,
+            None => return SendStatus::ResourceNotFound,
+        };
+        let mut outbox = link.outbox.0.lock().expect(OTHER_THREAD_ERR);
+        if outbox.receiver_closed {
+            return SendStatus::ResourceNotFound
+        }
+        outbox.messages.push_back(data);
+        // The receiver reads all the messages for each notification.
+        if outbox.messages.len() == 1 {
+            if let Some(receiver) = &outbox.receiver {
+                receiver.notify(Readiness::Read);
+            }
+        }
+        SendStatus::Sent
+    }
+}
+
+impl Resource for RemoteResource {
+    fn source(&mut self) -> &mut dyn Source {
+        &mut self.source
+    }
+
+    fn set_notifier(&mut self, notifier: ReadinessNotifier) {
+        // The write readiness establishes the connection, as in a socket.
+        notifier.notify(Readiness::Write);
+        if let Some(link) = &self.link {
+            let mut inbox = link.inbox.0.lock().expect(OTHER_THREAD_ERR);
+            if !inbox.messages.is_empty() || inbox.sender_closed {
+                notifier.notify(Readiness::Read);
+            }
+            inbox.receiver = Some(notifier);
+        }
+    }
+}
+
+impl Remote for RemoteResource {
+    fn connect_with(
+        _config: TransportConnect,
+        remote_addr: RemoteAddr,
+    ) -> io::Result<ConnectionInfo<Self>> {
+        let mut listeners = LISTENERS.lock().expect(OTHER_THREAD_ERR);
+        let peer_addr = match remote_addr {
+            RemoteAddr::Socket(addr) => addr,
+            RemoteAddr::Str(string) => {
+                let name = string.strip_prefix(NAME_SCHEME).ok_or_else(|| {
+                    let msg = format!("The address must be a socket address or {NAME_SCHEME}name");
+                    io::Error::new(ErrorKind::InvalidInput, msg)
+                })?;
+                *listeners.by_name.get(name).ok_or_else(|| {
+                    let msg = format!("No in-process listener named '{name}'");
+                    io::Error::new(ErrorKind::NotFound, msg)
+                })?
+            }
+        };
+
+        let local_addr = SocketAddr::new(peer_addr.ip(), listeners.next_port()?);
+        let remote = match listeners.find(peer_addr) {
+            Some(backlog) => {
+                let (remote, accepted) = Self::pair();
+                let mut backlog = backlog.0.lock().expect(OTHER_THREAD_ERR);
+                backlog.remotes.push_back((local_addr, accepted));
+                if backlog.remotes.len() == 1 {
+                    if let Some(notifier) = &backlog.notifier {
+                        notifier.notify(Readiness::Read);
+                    }
+                }
+                remote
+            }
+            // As a socket, the refused connection is reported once it is registered.
+            None => Self { source: VirtualSource, link: None },
+        };
+        Ok(ConnectionInfo { remote, local_addr, peer_addr })
+    }
+
+    fn receive(&self, mut process_data: impl FnMut(&[u8])) -> ReadStatus {
+        let inbox = match &self.link {
+            Some(link) => &link.inbox,
+            None => return ReadStatus::Disconnected,
+        };
+        loop {
+            // Not locked while processing, the callback could send through the same connection.
+            let message = inbox.0.lock().expect(OTHER_THREAD_ERR).messages.pop_front();
+            match message {
+                Some(message) => process_data(&message),
+                None => match inbox.0.lock().expect(OTHER_THREAD_ERR).sender_closed {
+                    true => break ReadStatus::Disconnected,
+                    false => break ReadStatus::WaitNextEvent,
+                },
+            }
+        }
+    }
+
+    fn send(&self, data: &[u8]) -> SendStatus {
+        self.push(data.to_vec())
+    }
+
+    fn send_owned(&self, data: Vec<u8>) -> SendStatus {
+        self.push(data)
+    }
+
+    fn pending(&self, _readiness: Readiness) -> PendingStatus {
+        match self.link {
+            Some(_) => PendingStatus::Ready,
+            None => PendingStatus::Disconnected,
+        }
+    }
+
+    fn connect_error(&self) -> Option<io::Error> {
+        let msg = "No in-process listener at the address";
+        self.link.is_none().then(|| io::Error::new(ErrorKind::ConnectionRefused, msg))
+    }
+}
+
+impl Drop for RemoteResource {
+    fn drop(&mut self) {
+        if let Some(link) = &self.link {
+            let mut outbox = link.outbox.0.lock().expect(OTHER_THREAD_ERR);
+            outbox.sender_closed = true;
+            if let Some(receiver) = &outbox.receiver {
+                receiver.notify(Readiness::Read);
+            }
+            drop(outbox);
+
+            let mut inbox = link.inbox.0.lock().expect(OTHER_THREAD_ERR);
+            inbox.receiver_closed = true;
+            inbox.messages.clear();
+        }
+    }
+}
+
+pub(crate) struct LocalResource {
+    source: VirtualSource,
+    backlog: Arc<Backlog>,
+    addr: SocketAddr,
+    name: Option<String>,
+}
+
+impl Resource for LocalResource {
+    fn source(&mut self) -> &mut dyn Source {
+        &mut self.source
+    }
+
+    fn set_notifier(&mut self, notifier: ReadinessNotifier) {
+        let mut backlog = self.backlog.0.lock().expect(OTHER_THREAD_ERR);
+        if !backlog.remotes.is_empty() {
+            notifier.notify(Readiness::Read);
+        }
+        backlog.notifier = Some(notifier);
+    }
+}
+
+impl Local for LocalResource {
+    type Remote = RemoteResource;
+
+    fn listen_with(config: TransportListen, addr: SocketAddr) -> io::Result<ListeningInfo<Self>> {
+        let config = match config {
+            TransportListen::InProcess(config) => config,
+            _ => panic!("Internal error: Got wrong config"),
+        };
+
+        let mut listeners = LISTENERS.lock().expect(OTHER_THREAD_ERR);
+        let addr = match addr.port() {
+            0 => SocketAddr::new(addr.ip(), listeners.next_port()?),
+            _ => addr,
+        };
+        if listeners.by_addr.contains_key(&addr) {
+            let msg = format!("Other in-process listener is listening at {addr}");
+            return Err(io::Error::new(ErrorKind::AddrInUse, msg))
+        }
+        if let Some(name) = &config.name {
+            if listeners.by_name.contains_key(name) {
+                let msg = format!("Other in-process listener is named '{name}'");
+                return Err(io::Error::new(ErrorKind::AddrInUse, msg))
+            }
+            listeners.by_name.insert(name.clone(), addr);
+        }
+
+        let backlog = Arc::new(Backlog::default());
+        listeners.by_addr.insert(addr, backlog.clone());
+        let local = LocalResource { source: VirtualSource, backlog, addr, name: config.name };
+        Ok(ListeningInfo { local, local_addr: addr })
+    }
+
+    fn accept(&self, accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
+        self.accept_limited(usize::MAX, accept_remote);
+    }
+
+    fn accept_limited(
+        &self,
+        limit: usize,
+        mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>),
+    ) -> bool {
+        for _ in 0..limit {
+            // Not locked while accepting, the remote notifies its readiness when registered.
+            let accepted = self.backlog.0.lock().expect(OTHER_THREAD_ERR).remotes.pop_front();
+            match accepted {
+                Some((addr, remote)) => accept_remote(AcceptedType::Remote(addr, remote)),
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Drop for LocalResource {
+    fn drop(&mut self) {
+        let mut listeners = LISTENERS.lock().expect(OTHER_THREAD_ERR);
+        listeners.by_addr.remove(&self.addr);
+        if let Some(name) = &self.name {
+            listeners.by_name.remove(name);
+        }
+        drop(listeners);
+
+        // The connections not accepted yet are refused, disconnecting their remotes.
+        self.backlog.0.lock().expect(OTHER_THREAD_ERR).remotes.clear();
+    }
+}
@@ -0,0 +1,106 @@
+use super::udp::{PmtuDiscovery};
+
+use socket2::{Socket};
+
+use std::net::{SocketAddr};
+use std::io::{self};
+
+/// Sets the path MTU discovery mode of the socket, that decides the Don't-Fragment bit
+/// of the datagrams it sends.
+/// In Linux and Android all the modes are available, by `IP_MTU_DISCOVER` for IPv4 addresses
+/// and `IPV6_MTU_DISCOVER` for IPv6 ones.
+/// In macOS and FreeBSD only the Don't-Fragment bit is set, by `IP_DONTFRAG`
+/// and `IPV6_DONTFRAG`, so [`PmtuDiscovery::Probe`] is not available.
+/// In the rest of systems, or with an unavailable mode,
+/// an [`io::ErrorKind::Unsupported`] error is returned.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn set_pmtu_discovery(
+    socket: &Socket,
+    addr: SocketAddr,
+    mode: PmtuDiscovery,
+) -> io::Result<()> {
+    let (level, name, value) = match addr {
+        SocketAddr::V4(_) => (
+            libc::IPPROTO_IP,
+            libc::IP_MTU_DISCOVER,
+            match mode {
+                PmtuDiscovery::Do => libc::IP_PMTUDISC_DO,
+                PmtuDiscovery::Dont => libc::IP_PMTUDISC_DONT,
+                PmtuDiscovery::Probe => libc::IP_PMTUDISC_PROBE,
+            },
+        ),
+        SocketAddr::V6(_) => (
+            libc::IPPROTO_IPV6,
+            libc::IPV6_MTU_DISCOVER,
+            match mode {
+                PmtuDiscovery::Do => libc::IPV6_PMTUDISC_DO,
+                PmtuDiscovery::Dont => libc::IPV6_PMTUDISC_DONT,
+                PmtuDiscovery::Probe => libc::IPV6_PMTUDISC_PROBE,
+            },
+        ),
+    };
+    set_option(socket, level, name, value)
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+pub(crate) fn set_pmtu_discovery(
+    socket: &Socket,
+    addr: SocketAddr,
+    mode: PmtuDiscovery,
+) -> io::Result<()> {
+    let value = match mode {
+        PmtuDiscovery::Do => 1,
+        PmtuDiscovery::Dont => 0,
+        PmtuDiscovery::Probe => return Err(unsupported(mode)),
+    };
+    match addr {
+        SocketAddr::V4(_) => set_option(socket, libc::IPPROTO_IP, libc::IP_DONTFRAG, value),
+        SocketAddr::V6(_) => set_option(socket, libc::IPPROTO_IPV6, libc::IPV6_DONTFRAG, value),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+)))]
+pub(crate) fn set_pmtu_discovery(
+    _socket: &Socket,
+    _addr: SocketAddr,
+    mode: PmtuDiscovery,
+) -> io::Result<()> {
+    Err(unsupported(mode))
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+))]
+fn set_option(
+    socket: &Socket,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    use std::os::unix::io::{AsRawFd};
+
+    let value_ptr = &value as *const libc::c_int as *const libc::c_void;
+    let value_len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    match unsafe { libc::setsockopt(socket.as_raw_fd(), level, name, value_ptr, value_len) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[allow(dead_code)] // Only used in some systems.
+fn unsupported(mode: PmtuDiscovery) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Can not set the path MTU discovery {:?}: not supported in this system", mode),
+    )
+}
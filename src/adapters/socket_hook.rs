@@ -0,0 +1,47 @@
+use socket2::{Socket};
+
+use std::fmt::{self};
+use std::hash::{Hash, Hasher};
+use std::io::{self};
+use std::sync::{Arc};
+
+/// User function that configures a socket before the adapter uses it.
+/// See [`crate::adapters::tcp::TcpConnectConfig::with_socket()`].
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub(crate) struct SocketHook(Arc<dyn Fn(&Socket) -> io::Result<()> + Send + Sync>);
+
+impl SocketHook {
+    pub fn new(hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(hook))
+    }
+
+    pub fn apply(&self, socket: &Socket) -> io::Result<()> {
+        (self.0)(socket)
+    }
+
+    fn addr(&self) -> *const () {
+        Arc::as_ptr(&self.0) as *const ()
+    }
+}
+
+impl fmt::Debug for SocketHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SocketHook({:p})", self.addr())
+    }
+}
+
+// The functions can not be compared, two hooks are equal if they are clones of the same one.
+impl PartialEq for SocketHook {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr() == other.addr()
+    }
+}
+
+impl Eq for SocketHook {}
+
+impl Hash for SocketHook {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.addr().hash(state)
+    }
+}
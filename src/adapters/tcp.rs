@@ -1,25 +1,24 @@
-pub use socket2::{TcpKeepalive};
+pub use socket2::{TcpKeepalive, Socket};
 
 use crate::network::adapter::{
     Resource, Remote, Local, Adapter, SendStatus, AcceptedType, ReadStatus, ConnectionInfo,
     ListeningInfo, PendingStatus,
 };
 use crate::network::{RemoteAddr, Readiness, TransportConnect, TransportListen};
+use super::socket_hook::{SocketHook};
+use crate::util::thread::{OTHER_THREAD_ERR};
 
 use mio::net::{TcpListener, TcpStream};
 use mio::event::{Source};
 
-use socket2::{Socket, Domain, Type, Protocol};
+use socket2::{Domain, Type, Protocol};
 
-use std::net::{SocketAddr};
-#[cfg(unix)]
-use std::ffi::{CString};
+use std::net::{SocketAddr, Shutdown};
 use std::io::{self, ErrorKind, Read, Write};
-#[cfg(target_os = "macos")]
-use std::num::NonZeroU32;
-use std::ops::{Deref};
 use std::mem::{forget, MaybeUninit};
 use std::os::raw::c_int;
+use std::sync::{Mutex};
+use std::time::{Duration};
 #[cfg(target_os = "windows")]
 use std::os::windows::io::{FromRawSocket, AsRawSocket};
 #[cfg(not(target_os = "windows"))]
@@ -28,6 +27,11 @@ use std::os::{fd::AsRawFd, unix::io::FromRawFd};
 /// Size of the internal reading buffer.
 /// It implies that at most the generated [`crate::network::NetEvent::Message`]
 /// will contains a chunk of data of this value.
+///
+/// Each [`crate::network::NetEvent::Message`] contains the data returned by a single `read()`
+/// of the socket. The data of consecutive reads is never merged into one event,
+/// although one read can return several writes of the peer (or part of one)
+/// if they were already together in the OS buffer.
 pub const INPUT_BUFFER_SIZE: usize = u16::MAX as usize; // 2^16 - 1
 
 /// The maximum length of the pending (unaccepted) connection queue of a listener.
@@ -38,11 +42,16 @@ pub struct TcpConnectConfig {
     bind_device: Option<String>,
     source_address: Option<SocketAddr>,
     keepalive: Option<TcpKeepalive>,
+    user_timeout: Option<Duration>,
+    nodelay: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
 }
 
 impl TcpConnectConfig {
-    /// Bind the TCP connection to a specific interface, identified by its name. This option works
-    /// in Unix, on other systems, it will be ignored.
+    /// Bind the TCP connection to a specific interface, identified by its name
+    /// (`SO_BINDTODEVICE`). This option works in Linux and macOS, on other systems the connection
+    /// fails with an [`std::io::ErrorKind::Unsupported`] error.
     pub fn with_bind_device(mut self, device: String) -> Self {
         self.bind_device = Some(device);
         self
@@ -59,17 +68,66 @@ impl TcpConnectConfig {
         self.source_address = Some(source_address);
         self
     }
+
+    /// Sets the `TCP_USER_TIMEOUT` option on the socket: the maximum time that sent data can
+    /// remain unacknowledged before the connection is considered dead.
+    /// It detects dead peers faster than keepalive when there is data in flight.
+    /// This option works in Linux, on other systems, it will be ignored.
+    pub fn with_user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `TCP_NODELAY` option on the socket, disabling the Nagle's algorithm.
+    /// The data is sent as soon as possible instead of being buffered to fill bigger segments,
+    /// which reduces the latency of small messages.
+    pub fn with_nodelay(mut self) -> Self {
+        self.nodelay = true;
+        self
+    }
+
+    /// Sets the type of service byte of the IP header of the packets sent by the socket
+    /// (`IP_TOS` for IPv4, `IPV6_TCLASS` for IPv6), used to prioritize the traffic.
+    /// The byte contains the DSCP value in its 6 upper bits and the ECN in the 2 lower ones.
+    /// For example, the expedited forwarding class (DSCP 46) is `46 << 2`.
+    ///
+    /// Note that the routers of the path may ignore or rewrite this value.
+    /// In systems where it is not available, the connection fails with an
+    /// [`std::io::ErrorKind::Unsupported`] error.
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket of the connection to set any option not offered by
+    /// this configuration.
+    /// It is called once the options of this configuration are set,
+    /// before connecting the socket. An error returned by the hook makes the connection fail.
+    ///
+    /// The socket is in non-blocking mode and it must remain that way:
+    /// a blocking socket would block the network thread of the node.
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct TcpListenConfig {
     bind_device: Option<String>,
     keepalive: Option<TcpKeepalive>,
+    user_timeout: Option<Duration>,
+    nodelay: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
 }
 
 impl TcpListenConfig {
-    /// Bind the TCP listener to a specific interface, identified by its name. This option works in
-    /// Unix, on other systems, it will be ignored.
+    /// Bind the TCP listener to a specific interface, identified by its name.
+    /// See [`TcpConnectConfig::with_bind_device()`].
     pub fn with_bind_device(mut self, device: String) -> Self {
         self.bind_device = Some(device);
         self
@@ -80,6 +138,39 @@ impl TcpListenConfig {
         self.keepalive = Some(keepalive);
         self
     }
+
+    /// Sets the `TCP_USER_TIMEOUT` option on client connection sockets.
+    /// See [`TcpConnectConfig::with_user_timeout()`].
+    pub fn with_user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `TCP_NODELAY` option on client connection sockets.
+    /// See [`TcpConnectConfig::with_nodelay()`].
+    pub fn with_nodelay(mut self) -> Self {
+        self.nodelay = true;
+        self
+    }
+
+    /// Sets the type of service byte of the packets sent by the listener and its client
+    /// connection sockets.
+    /// See [`TcpConnectConfig::with_tos()`].
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket of the listener before binding it.
+    /// The client connection sockets inherit most of the options from it.
+    /// See [`TcpConnectConfig::with_socket()`].
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
 }
 
 pub(crate) struct TcpAdapter;
@@ -120,27 +211,28 @@ impl Remote for RemoteResource {
         )?;
         socket.set_nonblocking(true)?;
 
+        if config.nodelay {
+            socket.set_nodelay(true)?;
+        }
+
+        if let Some(timeout) = config.user_timeout {
+            set_user_timeout(&socket, timeout)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, peer_addr, tos)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
         if let Some(source_address) = config.source_address {
             socket.bind(&source_address.into())?;
         }
 
-        #[cfg(unix)]
         if let Some(bind_device) = config.bind_device {
-            let device = CString::new(bind_device)?;
-
-            #[cfg(not(target_os = "macos"))]
-            socket.bind_device(Some(device.as_bytes()))?;
-
-            #[cfg(target_os = "macos")]
-            match NonZeroU32::new(unsafe { libc::if_nametoindex(device.as_ptr()) }) {
-                Some(index) => socket.bind_device_by_index(Some(index))?,
-                None => {
-                    return Err(io::Error::new(
-                        ErrorKind::NotFound,
-                        "Bind device interface not found",
-                    ))
-                }
-            }
+            super::device::bind_to_device(&socket, &bind_device)?;
         }
 
         match socket.connect(&peer_addr.into()) {
@@ -165,9 +257,10 @@ impl Remote for RemoteResource {
         let mut input_buffer = unsafe { buffer.assume_init() }; // Avoid to initialize the array
 
         loop {
-            let stream = &self.stream;
-            match stream.deref().read(&mut input_buffer) {
+            let mut stream = &self.stream;
+            match stream.read(&mut input_buffer) {
                 Ok(0) => break ReadStatus::Disconnected,
+                // Each read is delivered on its own, without waiting to fill the buffer.
                 Ok(size) => process_data(&input_buffer[..size]),
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
                 Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
@@ -191,8 +284,8 @@ impl Remote for RemoteResource {
         // this only occurs in the case when the receiver is full because reads slower that it sends.
         let mut total_bytes_sent = 0;
         loop {
-            let stream = &self.stream;
-            match stream.deref().write(&data[total_bytes_sent..]) {
+            let mut stream = &self.stream;
+            match stream.write(&data[total_bytes_sent..]) {
                 Ok(bytes_sent) => {
                     total_bytes_sent += bytes_sent;
                     if total_bytes_sent == data.len() {
@@ -203,8 +296,12 @@ impl Remote for RemoteResource {
 
                 // Others errors are considered fatal for the connection.
                 // a Event::Disconnection will be generated later.
+                Err(ref err) if is_disconnection(err) => {
+                    log::trace!("TCP send to a closed connection: {}", err);
+                    break SendStatus::ResourceNotFound
+                }
                 Err(err) => {
-                    log::error!("TCP receive error: {}", err);
+                    log::error!("TCP send error: {}", err);
                     break SendStatus::ResourceNotFound // should not happen
                 }
             }
@@ -232,6 +329,12 @@ impl Remote for RemoteResource {
 
         status
     }
+
+    fn close(&self) {
+        if let Err(err) = self.stream.shutdown(Shutdown::Write) {
+            log::trace!("TCP shutdown error: {}", err);
+        }
+    }
 }
 
 /// Check if a TcpStream can be considered connected.
@@ -249,14 +352,43 @@ pub fn check_stream_ready(stream: &TcpStream) -> PendingStatus {
     }
 }
 
+/// Check if a stream error means that the connection was closed by the other side.
+pub(crate) fn is_disconnection(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+    )
+}
+
+/// Sets the `TCP_USER_TIMEOUT` option of a socket.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn set_user_timeout(socket: &impl AsRawFd, timeout: Duration) -> io::Result<()> {
+    let socket = unsafe { Socket::from_raw_fd(socket.as_raw_fd()) };
+    let result = socket.set_tcp_user_timeout(Some(timeout));
+
+    // Don't drop so the underlying socket is not closed.
+    forget(socket);
+    result
+}
+
+/// The `TCP_USER_TIMEOUT` option only exists in Linux, it is ignored in other systems.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn set_user_timeout<S>(_socket: &S, _timeout: Duration) -> io::Result<()> {
+    Ok(())
+}
+
 pub(crate) struct LocalResource {
-    listener: TcpListener,
+    listener: Mutex<TcpListener>,
     keepalive: Option<TcpKeepalive>,
+    nodelay: bool,
 }
 
 impl Resource for LocalResource {
     fn source(&mut self) -> &mut dyn Source {
-        &mut self.listener
+        self.listener.get_mut().expect(OTHER_THREAD_ERR)
     }
 }
 
@@ -280,23 +412,21 @@ impl Local for LocalResource {
         socket.set_nonblocking(true)?;
         socket.set_reuse_address(true)?;
 
-        #[cfg(unix)]
+        // The accepted sockets inherit them from the listener.
+        if let Some(timeout) = config.user_timeout {
+            set_user_timeout(&socket, timeout)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, addr, tos)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
         if let Some(bind_device) = config.bind_device {
-            let device = CString::new(bind_device)?;
-
-            #[cfg(not(target_os = "macos"))]
-            socket.bind_device(Some(device.as_bytes()))?;
-
-            #[cfg(target_os = "macos")]
-            match NonZeroU32::new(unsafe { libc::if_nametoindex(device.as_ptr()) }) {
-                Some(index) => socket.bind_device_by_index(Some(index))?,
-                None => {
-                    return Err(io::Error::new(
-                        ErrorKind::NotFound,
-                        "Bind device interface not found",
-                    ))
-                }
-            }
+            super::device::bind_to_device(&socket, &bind_device)?;
         }
 
         socket.bind(&addr.into())?;
@@ -306,22 +436,67 @@ impl Local for LocalResource {
 
         let local_addr = listener.local_addr().unwrap();
         Ok(ListeningInfo {
-            local: { LocalResource { listener, keepalive: config.keepalive } },
+            local: {
+                LocalResource {
+                    listener: Mutex::new(listener),
+                    keepalive: config.keepalive,
+                    nodelay: config.nodelay,
+                }
+            },
             local_addr,
         })
     }
 
-    fn accept(&self, mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
-        loop {
-            match self.listener.accept() {
-                Ok((stream, addr)) => accept_remote(AcceptedType::Remote(
-                    addr,
-                    RemoteResource { stream, keepalive: self.keepalive.clone() },
-                )),
-                Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+    fn accept(&self, accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
+        self.accept_limited(usize::MAX, accept_remote);
+    }
+
+    fn accept_limited(
+        &self,
+        limit: usize,
+        mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>),
+    ) -> bool {
+        let mut accepted = 0;
+        while accepted < limit {
+            let result = self.listener.lock().expect(OTHER_THREAD_ERR).accept();
+            match result {
+                Ok((stream, addr)) => {
+                    accepted += 1;
+                    if self.nodelay {
+                        if let Err(err) = stream.set_nodelay(true) {
+                            log::error!("TCP set nodelay error: {}", err);
+                        }
+                    }
+                    accept_remote(AcceptedType::Remote(
+                        addr,
+                        RemoteResource { stream, keepalive: self.keepalive.clone() },
+                    ))
+                }
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => return false,
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
-                Err(err) => break log::error!("TCP accept error: {}", err), // Should not happen
+                Err(err) => {
+                    log::error!("TCP accept error: {}", err); // Should not happen
+                    return false
+                }
             }
         }
+        true
+    }
+
+    fn update_source(&self, update: &mut dyn FnMut(&mut dyn Source)) {
+        update(&mut *self.listener.lock().expect(OTHER_THREAD_ERR));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn user_timeout() {
+        let socket = Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP)).unwrap();
+        set_user_timeout(&socket, Duration::from_millis(1500)).unwrap();
+        assert_eq!(socket.tcp_user_timeout().unwrap(), Some(Duration::from_millis(1500)));
     }
 }
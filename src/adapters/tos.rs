@@ -0,0 +1,70 @@
+use socket2::{Socket};
+
+use std::net::{SocketAddr};
+use std::io::{self};
+
+/// Sets the type of service byte of the IP header of the packets sent by the socket:
+/// `IP_TOS` for IPv4 addresses and `IPV6_TCLASS` for IPv6 ones.
+/// In systems where it is not available, an [`io::ErrorKind::Unsupported`] error is returned.
+pub(crate) fn set_tos(socket: &Socket, addr: SocketAddr, tos: u8) -> io::Result<()> {
+    match addr {
+        SocketAddr::V4(_) => set_tos_v4(socket, tos),
+        SocketAddr::V6(_) => set_tclass_v6(socket, tos),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "illumos",
+)))]
+fn set_tos_v4(socket: &Socket, tos: u8) -> io::Result<()> {
+    socket.set_tos(tos as u32)
+}
+
+#[cfg(any(
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "illumos",
+))]
+fn set_tos_v4(_socket: &Socket, tos: u8) -> io::Result<()> {
+    Err(unsupported(tos))
+}
+
+#[cfg(any(
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+fn set_tclass_v6(socket: &Socket, tos: u8) -> io::Result<()> {
+    socket.set_tclass_v6(tos as u32)
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "fuchsia",
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "openbsd",
+)))]
+fn set_tclass_v6(_socket: &Socket, tos: u8) -> io::Result<()> {
+    Err(unsupported(tos))
+}
+
+#[allow(dead_code)] // Only used in some systems.
+fn unsupported(tos: u8) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Can not set the type of service {}: not supported in this system", tos),
+    )
+}
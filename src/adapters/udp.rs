@@ -1,13 +1,29 @@
+pub use socket2::{Socket};
+
 use crate::network::adapter::{
     Resource, Remote, Local, Adapter, SendStatus, AcceptedType, ReadStatus, ConnectionInfo,
-    ListeningInfo, PendingStatus,
+    ListeningInfo, PendingStatus, Direction,
 };
 use crate::network::{RemoteAddr, Readiness, TransportConnect, TransportListen};
+use super::socket_hook::{SocketHook};
 
 use mio::net::{UdpSocket};
 use mio::event::{Source};
 
-use socket2::{Socket, Domain, Type, Protocol};
+use socket2::{Domain, Type, Protocol};
+#[cfg(not(any(
+    target_os = "aix",
+    target_os = "haiku",
+    target_os = "illumos",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "nto",
+    target_os = "espidf",
+    target_os = "vita",
+)))]
+use socket2::{InterfaceIndexOrAddress};
 
 #[cfg(target_os = "linux")]
 use nix::errno::{Errno};
@@ -20,9 +36,11 @@ use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr};
 #[cfg(target_os = "linux")]
 use std::net::{IpAddr, Ipv6Addr};
 use std::io::{self, ErrorKind};
-use std::mem::{MaybeUninit};
-#[cfg(target_os = "linux")]
-use std::os::fd::AsRawFd;
+use std::mem::{forget, MaybeUninit};
+#[cfg(target_os = "windows")]
+use std::os::windows::io::{FromRawSocket, AsRawSocket};
+#[cfg(not(target_os = "windows"))]
+use std::os::{fd::AsRawFd, unix::io::FromRawFd};
 
 /// Maximun payload that UDP can send over the internet to be mostly compatible.
 pub const MAX_INTERNET_PAYLOAD_LEN: usize = 1500 - 20 - 8;
@@ -37,15 +55,70 @@ pub const MAX_LOCAL_PAYLOAD_LEN: usize = 65535 - 20 - 8;
 #[cfg(target_os = "macos")]
 pub const MAX_LOCAL_PAYLOAD_LEN: usize = 9216 - 20 - 8;
 
+/// Size of the checksum appended to each datagram when it is enabled by
+/// [`UdpConnectConfig::with_checksum()`] or [`UdpListenConfig::with_checksum()`].
+/// The maximum size of the messages is reduced by this value.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Network interface of the host, identified by an IPv4 address assigned to it
+/// or by its index.
+/// See [`UdpListenConfig::with_multicast_interface()`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MulticastInterface {
+    /// An IPv4 address assigned to the interface.
+    Address(Ipv4Addr),
+
+    /// The index of the interface, as given by `if_nametoindex()`.
+    /// It is not supported by all systems, in those the listening fails with an
+    /// [`std::io::ErrorKind::Unsupported`] error.
+    Index(u32),
+}
+
+/// Path MTU discovery mode of a socket, that decides if its datagrams are sent with
+/// the Don't-Fragment bit.
+/// See [`UdpConnectConfig::with_pmtu_discovery()`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PmtuDiscovery {
+    /// The datagrams are sent with the Don't-Fragment bit and the path MTU is discovered,
+    /// so the datagrams larger than the known path MTU can not be sent
+    /// (`IP_PMTUDISC_DO` in Linux).
+    Do,
+
+    /// The datagrams are sent without the Don't-Fragment bit,
+    /// so they are fragmented when they do not fit in the path MTU (`IP_PMTUDISC_DONT`).
+    Dont,
+
+    /// The datagrams are sent with the Don't-Fragment bit, but the path MTU is ignored,
+    /// only the MTU of the interface limits them, to probe the path (`IP_PMTUDISC_PROBE`).
+    /// Only available in Linux and Android.
+    Probe,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct UdpConnectConfig {
+    bind_device: Option<String>,
     source_address: SocketAddr,
     broadcast: bool,
     reuse_address: bool,
     reuse_port: bool,
+    checksum: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
+    direction: Direction,
+    multicast_outgoing_interface: Option<Ipv4Addr>,
+    pmtu_discovery: Option<PmtuDiscovery>,
 }
 
 impl UdpConnectConfig {
+    /// Bind the socket to a specific interface, identified by its name (`SO_BINDTODEVICE`).
+    /// The socket only sends and receives the traffic of that interface.
+    /// This option works in Linux and macOS, on other systems the connection fails with an
+    /// [`std::io::ErrorKind::Unsupported`] error.
+    pub fn with_bind_device(mut self, device: String) -> Self {
+        self.bind_device = Some(device);
+        self
+    }
+
     /// Specify the source address and port.
     pub fn with_source_address(mut self, addr: SocketAddr) -> Self {
         self.source_address = addr;
@@ -74,28 +147,131 @@ impl UdpConnectConfig {
         self.reuse_port = true;
         self
     }
+
+    /// Appends a CRC32 checksum to each datagram sent and validates it in each datagram received.
+    /// The datagrams with an invalid checksum are dropped and a
+    /// [`crate::network::NetEvent::Error`] with [`std::io::ErrorKind::InvalidData`] is generated.
+    /// It detects the corruptions that the UDP checksum could miss, since it is only
+    /// 16 bits long and it can be disabled in IPv4.
+    ///
+    /// The other side must enable it too.
+    /// The checksum uses [`CHECKSUM_LEN`] bytes of each datagram,
+    /// see [`UdpConnectConfig::max_message_size()`].
+    pub fn with_checksum(mut self) -> Self {
+        self.checksum = true;
+        self
+    }
+
+    /// Sets the type of service byte of the IP header of the datagrams sent by the socket.
+    /// It allows, for example, to mark real-time traffic as expedited forwarding (`46 << 2`).
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_tos()`].
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket to set any option not offered by this configuration.
+    /// It is called once the options of this configuration are set, before binding the socket.
+    /// An error returned by the hook makes the connection fail.
+    ///
+    /// The socket is in non-blocking mode and it must remain that way:
+    /// a blocking socket would block the network thread of the node.
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
+
+    /// Sets the directions in which the socket transfers data.
+    /// A [`Direction::WriteOnly`] socket, as a multicast publisher,
+    /// is not registered to read, and a [`Direction::ReadOnly`] socket can not send.
+    /// By default, it is [`Direction::ReadWrite`].
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets the interface, by an IPv4 address assigned to it, by which the multicast datagrams
+    /// are sent when connecting to a multicast group (the `IP_MULTICAST_IF` option).
+    /// By default, the system chooses it by its routing tables.
+    /// See [`UdpListenConfig::with_multicast_interface()`] to choose where they are received.
+    pub fn with_multicast_outgoing_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.multicast_outgoing_interface = Some(interface);
+        self
+    }
+
+    /// Sets the path MTU discovery mode of the socket, that controls the Don't-Fragment bit
+    /// of the datagrams, to avoid that the large datagrams are dropped silently by a path
+    /// with a small MTU.
+    /// With [`PmtuDiscovery::Do`], a datagram larger than the path MTU known by the system
+    /// is not sent, and the send returns [`SendStatus::MaxPacketSizeExceeded`].
+    /// The system learns the path MTU by the ICMP messages of the routers,
+    /// so the datagrams sent before knowing it could still be lost.
+    /// By default, the mode of the system is used.
+    ///
+    /// The modes depend on the system: Linux and Android offer all of them
+    /// (`IP_MTU_DISCOVER` and `IPV6_MTU_DISCOVER`), macOS and FreeBSD only set
+    /// the Don't-Fragment bit (`IP_DONTFRAG` and `IPV6_DONTFRAG`), without
+    /// [`PmtuDiscovery::Probe`].
+    /// In other systems, as Windows, or with a mode not offered,
+    /// the connection fails with an [`std::io::ErrorKind::Unsupported`] error.
+    pub fn with_pmtu_discovery(mut self, mode: PmtuDiscovery) -> Self {
+        self.pmtu_discovery = Some(mode);
+        self
+    }
+
+    /// Maximum message size that can be sent with this configuration.
+    /// It is [`MAX_LOCAL_PAYLOAD_LEN`], minus [`CHECKSUM_LEN`] if the checksum is enabled.
+    pub fn max_message_size(&self) -> usize {
+        max_message_size(self.checksum)
+    }
 }
 
 impl Default for UdpConnectConfig {
     fn default() -> Self {
         Self {
+            bind_device: None,
             source_address: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0).into(),
             broadcast: false,
             reuse_address: false,
             reuse_port: false,
+            checksum: false,
+            tos: None,
+            socket_hook: None,
+            direction: Direction::default(),
+            multicast_outgoing_interface: None,
+            pmtu_discovery: None,
         }
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
 pub struct UdpListenConfig {
+    bind_device: Option<String>,
     send_broadcasts: bool,
     receive_broadcasts: bool,
     reuse_address: bool,
     reuse_port: bool,
+    checksum: bool,
+    tos: Option<u8>,
+    socket_hook: Option<SocketHook>,
+    direction: Direction,
+    multicast_interface: Option<MulticastInterface>,
+    multicast_outgoing_interface: Option<Ipv4Addr>,
+    pmtu_discovery: Option<PmtuDiscovery>,
 }
 
 impl UdpListenConfig {
+    /// Bind the socket to a specific interface, identified by its name.
+    /// Only the messages received by that interface will be read.
+    /// See [`UdpConnectConfig::with_bind_device()`].
+    pub fn with_bind_device(mut self, device: String) -> Self {
+        self.bind_device = Some(device);
+        self
+    }
+
     /// Enables the socket capabilities to send broadcast messages when the listening socket is
     /// also used for sending with
     /// [`Endpoint::from_listener`](crate::network::Endpoint::from_listener).
@@ -124,10 +300,93 @@ impl UdpListenConfig {
     /// to `bind` may allow reuse of local addresses. For IPv4 sockets this means that a socket may
     /// bind even when there’s a socket already listening on this port. This option is always-on
     /// on Windows and cannot be configured.
+    ///
+    /// See [`node::split_udp_group()`](crate::node::split_udp_group) to distribute
+    /// the traffic of a port among several nodes using this option.
     pub fn with_reuse_port(mut self) -> Self {
         self.reuse_port = true;
         self
     }
+
+    /// Appends a CRC32 checksum to each datagram sent from the listener and validates it
+    /// in each datagram received.
+    /// Since there is no endpoint to notify, the datagrams with an invalid checksum
+    /// are dropped silently.
+    /// See [`UdpConnectConfig::with_checksum()`].
+    pub fn with_checksum(mut self) -> Self {
+        self.checksum = true;
+        self
+    }
+
+    /// Sets the type of service byte of the IP header of the datagrams sent from the listener.
+    /// See [`crate::adapters::tcp::TcpConnectConfig::with_tos()`].
+    pub fn with_tos(mut self, tos: u8) -> Self {
+        self.tos = Some(tos);
+        self
+    }
+
+    /// Calls `hook` with the socket of the listener before binding it.
+    /// See [`UdpConnectConfig::with_socket()`].
+    pub fn with_socket(
+        mut self,
+        hook: impl Fn(&Socket) -> io::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.socket_hook = Some(SocketHook::new(hook));
+        self
+    }
+
+    /// Sets the directions in which the listener transfers data.
+    /// A [`Direction::WriteOnly`] listener only sends with
+    /// [`Endpoint::from_listener`](crate::network::Endpoint::from_listener),
+    /// it does not read the messages received, even its own multicast messages.
+    /// See [`UdpConnectConfig::with_direction()`].
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets the interface that joins the multicast group when listening in a multicast address,
+    /// so the datagrams of the group are received by that interface.
+    /// By default, the system chooses it by its routing tables, that in a host with
+    /// several interfaces could be a different one than expected.
+    ///
+    /// This is the membership interface, it only selects where the datagrams are received.
+    /// The datagrams sent from the listener with
+    /// [`Endpoint::from_listener`](crate::network::Endpoint::from_listener) go out by the
+    /// interface set by [`UdpListenConfig::with_multicast_outgoing_interface()`],
+    /// that is independent, so usually both are set to the same interface.
+    pub fn with_multicast_interface(mut self, interface: MulticastInterface) -> Self {
+        self.multicast_interface = Some(interface);
+        self
+    }
+
+    /// Sets the interface, by an IPv4 address assigned to it, by which the multicast datagrams
+    /// sent from the listener go out (the `IP_MULTICAST_IF` option).
+    /// See [`UdpListenConfig::with_multicast_interface()`].
+    pub fn with_multicast_outgoing_interface(mut self, interface: Ipv4Addr) -> Self {
+        self.multicast_outgoing_interface = Some(interface);
+        self
+    }
+
+    /// Sets the path MTU discovery mode of the datagrams sent from the listener.
+    /// See [`UdpConnectConfig::with_pmtu_discovery()`].
+    pub fn with_pmtu_discovery(mut self, mode: PmtuDiscovery) -> Self {
+        self.pmtu_discovery = Some(mode);
+        self
+    }
+
+    /// Maximum message size that can be sent from the listener with this configuration.
+    /// See [`UdpConnectConfig::max_message_size()`].
+    pub fn max_message_size(&self) -> usize {
+        max_message_size(self.checksum)
+    }
+}
+
+fn max_message_size(checksum: bool) -> usize {
+    match checksum {
+        true => MAX_LOCAL_PAYLOAD_LEN - CHECKSUM_LEN,
+        false => MAX_LOCAL_PAYLOAD_LEN,
+    }
 }
 
 pub(crate) struct UdpAdapter;
@@ -138,6 +397,8 @@ impl Adapter for UdpAdapter {
 
 pub(crate) struct RemoteResource {
     socket: UdpSocket,
+    checksum: bool,
+    direction: Direction,
 }
 
 impl Resource for RemoteResource {
@@ -173,47 +434,95 @@ impl Remote for RemoteResource {
         socket.set_reuse_port(config.reuse_port)?;
         socket.set_broadcast(config.broadcast)?;
 
+        if let Some(bind_device) = &config.bind_device {
+            super::device::bind_to_device(&socket, bind_device)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, peer_addr, tos)?;
+        }
+
+        if let Some(mode) = config.pmtu_discovery {
+            super::pmtu::set_pmtu_discovery(&socket, peer_addr, mode)?;
+        }
+
+        if let Some(interface) = &config.multicast_outgoing_interface {
+            socket.set_multicast_if_v4(interface)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
         socket.bind(&config.source_address.into())?;
         socket.connect(&peer_addr.into())?;
 
         let socket = UdpSocket::from_std(socket.into());
         let local_addr = socket.local_addr()?;
-        Ok(ConnectionInfo { remote: RemoteResource { socket }, local_addr, peer_addr })
+        Ok(ConnectionInfo {
+            remote: RemoteResource {
+                socket,
+                checksum: config.checksum,
+                direction: config.direction,
+            },
+            local_addr,
+            peer_addr,
+        })
     }
 
     fn receive(&self, mut process_data: impl FnMut(&[u8])) -> ReadStatus {
         let buffer: MaybeUninit<[u8; MAX_LOCAL_PAYLOAD_LEN]> = MaybeUninit::uninit();
         let mut input_buffer = unsafe { buffer.assume_init() }; // Avoid to initialize the array
 
+        let mut icmp_error = None;
+        let mut corrupted = false;
         loop {
             match self.socket.recv(&mut input_buffer) {
-                Ok(size) => process_data(&mut input_buffer[..size]),
+                Ok(size) => match self.checksum {
+                    false => process_data(&input_buffer[..size]),
+                    true => match strip_checksum(&input_buffer[..size]) {
+                        Some(data) => process_data(data),
+                        None => corrupted = true,
+                    },
+                },
                 Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
-                    break ReadStatus::WaitNextEvent
-                }
-                Err(ref err) if err.kind() == ErrorKind::ConnectionRefused => {
-                    // Avoid ICMP generated error to be logged
-                    break ReadStatus::WaitNextEvent
+                    break match icmp_error {
+                        Some(kind) => ReadStatus::Error(kind),
+                        None if corrupted => ReadStatus::Error(ErrorKind::InvalidData),
+                        None => ReadStatus::WaitNextEvent,
+                    }
                 }
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
                 Err(err) => {
-                    log::error!("UDP receive error: {}", err);
-                    break ReadStatus::WaitNextEvent // Should not happen
+                    // The socket reports an ICMP error once, consumed by this read.
+                    // There could be more datagrams queued after it.
+                    if icmp_error.replace(err.kind()).is_some() {
+                        log::error!("UDP receive error: {}", err);
+                        break ReadStatus::Error(err.kind()) // Should not happen
+                    }
                 }
             }
         }
     }
 
     fn send(&self, data: &[u8]) -> SendStatus {
-        send_packet(data, |data| self.socket.send(data))
+        send_packet(data, self.checksum, |data| self.socket.send(data))
     }
 
     fn pending(&self, _readiness: Readiness) -> PendingStatus {
         PendingStatus::Ready
     }
+
+    fn direction(&self) -> Direction {
+        self.direction
+    }
 }
 
 pub(crate) struct LocalResource {
     socket: UdpSocket,
+    checksum: bool,
+    direction: Direction,
+    multicast: Option<(Ipv4Addr, Option<MulticastInterface>)>, // The group joined.
     #[cfg(target_os = "linux")]
     ingress_addresses: Option<Vec<IpAddr>>,
 }
@@ -280,8 +589,7 @@ impl LocalResource {
                         None => continue,
                     };
 
-                    let data = &mut input_buffer[..size];
-                    accept_remote(AcceptedType::Data(addr, data))
+                    self.accept_data(addr, &input_buffer[..size], &mut accept_remote)
                 }
                 Err(Errno::EWOULDBLOCK) => break,
                 Err(err) => break log::error!("UDP accept error: {}", err), // Should never happen
@@ -327,6 +635,26 @@ impl Local for LocalResource {
         }
         socket.set_broadcast(config.send_broadcasts)?;
 
+        if let Some(bind_device) = &config.bind_device {
+            super::device::bind_to_device(&socket, bind_device)?;
+        }
+
+        if let Some(tos) = config.tos {
+            super::tos::set_tos(&socket, addr, tos)?;
+        }
+
+        if let Some(mode) = config.pmtu_discovery {
+            super::pmtu::set_pmtu_discovery(&socket, addr, mode)?;
+        }
+
+        if let Some(interface) = &config.multicast_outgoing_interface {
+            socket.set_multicast_if_v4(interface)?;
+        }
+
+        if let Some(socket_hook) = &config.socket_hook {
+            socket_hook.apply(&socket)?;
+        }
+
         #[cfg(target_os = "linux")]
         let ingress_addresses = if config.receive_broadcasts {
             // enable the socket packet info option
@@ -392,7 +720,7 @@ impl Local for LocalResource {
         };
 
         if let Some(multicast) = multicast {
-            socket.join_multicast_v4(multicast.ip(), &Ipv4Addr::UNSPECIFIED)?;
+            set_multicast_membership(&socket, multicast.ip(), config.multicast_interface, true)?;
             socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, addr.port()).into())?;
         }
         else {
@@ -405,6 +733,9 @@ impl Local for LocalResource {
             local: {
                 LocalResource {
                     socket,
+                    checksum: config.checksum,
+                    direction: config.direction,
+                    multicast: multicast.map(|addr| (*addr.ip(), config.multicast_interface)),
                     #[cfg(target_os = "linux")]
                     ingress_addresses,
                 }
@@ -426,8 +757,7 @@ impl Local for LocalResource {
         loop {
             match self.socket.recv_from(&mut input_buffer) {
                 Ok((size, addr)) => {
-                    let data = &mut input_buffer[..size];
-                    accept_remote(AcceptedType::Data(addr, data))
+                    self.accept_data(addr, &input_buffer[..size], &mut accept_remote)
                 }
                 Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
                 Err(err) => break log::error!("UDP accept error: {}", err), // Should never happen
@@ -436,21 +766,128 @@ impl Local for LocalResource {
     }
 
     fn send_to(&self, addr: SocketAddr, data: &[u8]) -> SendStatus {
-        send_packet(data, |data| self.socket.send_to(data, addr))
+        send_packet(data, self.checksum, |data| self.socket.send_to(data, addr))
+    }
+
+    fn direction(&self) -> Direction {
+        self.direction
+    }
+}
+
+impl LocalResource {
+    fn accept_data(
+        &self,
+        addr: SocketAddr,
+        packet: &[u8],
+        accept_remote: &mut impl FnMut(AcceptedType<'_, RemoteResource>),
+    ) {
+        match self.checksum {
+            false => accept_remote(AcceptedType::Data(addr, packet)),
+            true => match strip_checksum(packet) {
+                Some(data) => accept_remote(AcceptedType::Data(addr, data)),
+                None => log::trace!("UDP datagram from {} dropped: invalid checksum", addr),
+            },
+        }
     }
 }
 
 impl Drop for LocalResource {
     fn drop(&mut self) {
-        if let SocketAddr::V4(addr) = self.socket.local_addr().unwrap() {
-            if addr.ip().is_multicast() {
-                self.socket.leave_multicast_v4(addr.ip(), &Ipv4Addr::UNSPECIFIED).unwrap();
+        if let Some((group, interface)) = self.multicast {
+            #[cfg(target_os = "windows")]
+            let socket = unsafe { Socket::from_raw_socket(self.socket.as_raw_socket()) };
+            #[cfg(not(target_os = "windows"))]
+            let socket = unsafe { Socket::from_raw_fd(self.socket.as_raw_fd()) };
+
+            if let Err(err) = set_multicast_membership(&socket, &group, interface, false) {
+                log::trace!("UDP leave multicast error: {}", err);
             }
+
+            // Don't drop so the underlying socket is not closed.
+            forget(socket);
+        }
+    }
+}
+
+/// Joins the multicast `group` (or leaves it if `join` is `false`) by the `interface`,
+/// or by the interface chosen by the system if it is `None`.
+#[cfg(not(any(
+    target_os = "aix",
+    target_os = "haiku",
+    target_os = "illumos",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "nto",
+    target_os = "espidf",
+    target_os = "vita",
+)))]
+fn set_multicast_membership(
+    socket: &Socket,
+    group: &Ipv4Addr,
+    interface: Option<MulticastInterface>,
+    join: bool,
+) -> io::Result<()> {
+    let interface = match interface {
+        None => InterfaceIndexOrAddress::Address(Ipv4Addr::UNSPECIFIED),
+        Some(MulticastInterface::Address(addr)) => InterfaceIndexOrAddress::Address(addr),
+        Some(MulticastInterface::Index(index)) => InterfaceIndexOrAddress::Index(index),
+    };
+    match join {
+        true => socket.join_multicast_v4_n(group, &interface),
+        false => socket.leave_multicast_v4_n(group, &interface),
+    }
+}
+
+#[cfg(any(
+    target_os = "aix",
+    target_os = "haiku",
+    target_os = "illumos",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "nto",
+    target_os = "espidf",
+    target_os = "vita",
+))]
+fn set_multicast_membership(
+    socket: &Socket,
+    group: &Ipv4Addr,
+    interface: Option<MulticastInterface>,
+    join: bool,
+) -> io::Result<()> {
+    let interface = match interface {
+        None => Ipv4Addr::UNSPECIFIED,
+        Some(MulticastInterface::Address(addr)) => addr,
+        Some(MulticastInterface::Index(_)) => {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "Multicast interfaces by index are not supported in this system",
+            ))
         }
+    };
+    match join {
+        true => socket.join_multicast_v4(group, &interface),
+        false => socket.leave_multicast_v4(group, &interface),
     }
 }
 
-fn send_packet(data: &[u8], send_method: impl Fn(&[u8]) -> io::Result<usize>) -> SendStatus {
+fn send_packet(
+    data: &[u8],
+    checksum: bool,
+    send_method: impl Fn(&[u8]) -> io::Result<usize>,
+) -> SendStatus {
+    let packet;
+    let data = match checksum {
+        true => {
+            packet = append_checksum(data);
+            &packet
+        }
+        false => data,
+    };
+
     loop {
         match send_method(data) {
             Ok(_) => break SendStatus::Sent,
@@ -459,7 +896,8 @@ fn send_packet(data: &[u8], send_method: impl Fn(&[u8]) -> io::Result<usize>) ->
                 break SendStatus::ResourceNotFound
             }
             Err(ref err) if err.kind() == ErrorKind::WouldBlock => continue,
-            Err(ref err) if err.kind() == ErrorKind::Other => {
+            Err(ref err) if err.kind() == ErrorKind::Other || is_message_too_long(err) => {
+                log::trace!("UDP send of {} bytes error: {}", data.len(), err);
                 break SendStatus::MaxPacketSizeExceeded
             }
             Err(err) => {
@@ -469,3 +907,318 @@ fn send_packet(data: &[u8], send_method: impl Fn(&[u8]) -> io::Result<usize>) ->
         }
     }
 }
+
+/// The datagram is larger than the socket can send (`EMSGSIZE`),
+/// as when it does not fit in the path MTU with the Don't-Fragment bit.
+fn is_message_too_long(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    const EMSGSIZE: i32 = libc::EMSGSIZE;
+    #[cfg(windows)]
+    const EMSGSIZE: i32 = 10040; // WSAEMSGSIZE
+    err.raw_os_error() == Some(EMSGSIZE)
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// Table of the CRC32 (IEEE) reversed polynomial, computed at compile time.
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+fn append_checksum(data: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(data.len() + CHECKSUM_LEN);
+    packet.extend_from_slice(data);
+    packet.extend_from_slice(&crc32(data).to_be_bytes());
+    packet
+}
+
+/// Returns the data of the packet if its checksum is valid.
+fn strip_checksum(packet: &[u8]) -> Option<&[u8]> {
+    let data_len = packet.len().checked_sub(CHECKSUM_LEN)?;
+    let (data, checksum) = packet.split_at(data_len);
+    match crc32(data).to_be_bytes() == checksum {
+        true => Some(data),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{self, NetEvent, Transport, Endpoint};
+
+    use std::time::{Duration};
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn checksum_validation() {
+        let packet = append_checksum(b"data");
+        assert_eq!(packet.len(), 4 + CHECKSUM_LEN);
+        assert_eq!(strip_checksum(&packet), Some(&b"data"[..]));
+
+        let mut corrupted = packet.clone();
+        corrupted[1] ^= 0x10;
+        assert_eq!(strip_checksum(&corrupted), None);
+        assert_eq!(strip_checksum(&packet[..CHECKSUM_LEN - 1]), None);
+    }
+
+    #[test]
+    fn checksum_max_message_size() {
+        let config = TransportConnect::Udp(UdpConnectConfig::default().with_checksum());
+        assert_eq!(config.max_message_size(), MAX_LOCAL_PAYLOAD_LEN - CHECKSUM_LEN);
+        let config = TransportListen::Udp(UdpListenConfig::default());
+        assert_eq!(config.max_message_size(), Transport::Udp.max_message_size());
+    }
+
+    #[test]
+    fn corrupted_datagram_dropped_by_listener() {
+        let (controller, mut processor) = network::split();
+        let config = TransportListen::Udp(UdpListenConfig::default().with_checksum());
+        let (_, addr) = controller.listen_with(config, "127.0.0.1:0").unwrap();
+
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut corrupted = append_checksum(b"corrupted");
+        corrupted[0] ^= 0x01;
+        sender.send_to(&corrupted, addr).unwrap();
+        sender.send_to(&append_checksum(b"valid"), addr).unwrap();
+
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(endpoint.addr(), sender.local_addr().unwrap());
+                received.push(data.to_vec());
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(received, vec![b"valid".to_vec()]);
+    }
+
+    #[test]
+    fn corrupted_datagram_dropped_by_connection() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let (controller, mut processor) = network::split();
+        let config = TransportConnect::Udp(UdpConnectConfig::default().with_checksum());
+        let (_, local_addr) =
+            controller.connect_with(config, receiver.local_addr().unwrap()).unwrap();
+
+        let mut corrupted = append_checksum(b"corrupted");
+        *corrupted.last_mut().unwrap() ^= 0x01;
+        receiver.send_to(&corrupted, local_addr).unwrap();
+        receiver.send_to(&append_checksum(b"valid"), local_addr).unwrap();
+
+        let (mut received, mut errors) = (Vec::new(), 0);
+        processor.process_poll_events_until_timeout(TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Message(_, data) => received.push(data.to_vec()),
+            NetEvent::Error(_, kind) => {
+                assert_eq!(kind, ErrorKind::InvalidData);
+                errors += 1;
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(received, vec![b"valid".to_vec()]);
+        assert_eq!(errors, 1);
+    }
+
+    #[test]
+    fn checksum_appended_on_send() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let (controller, mut processor) = network::split();
+        let config = TransportConnect::Udp(UdpConnectConfig::default().with_checksum());
+        let (endpoint, _) =
+            controller.connect_with(config, receiver.local_addr().unwrap()).unwrap();
+        processor.process_poll_event(Some(TIMEOUT), |_| ());
+        assert_eq!(controller.send(endpoint, b"data"), SendStatus::Sent);
+
+        let mut buffer = [0; 64];
+        let size = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(strip_checksum(&buffer[..size]), Some(&b"data"[..]));
+    }
+
+    #[test]
+    fn write_only_multicast_publisher() {
+        let multicast_addr = "239.255.0.1:3016".parse().unwrap();
+        let (controller, mut processor) = network::split();
+
+        // The publisher belongs to the group, so it would receive its own messages.
+        let config = UdpListenConfig::default().with_direction(Direction::WriteOnly);
+        let config = TransportListen::Udp(config);
+        let (publisher_id, _) = controller.listen_with(config, multicast_addr).unwrap();
+
+        let config = UdpListenConfig::default().with_direction(Direction::ReadOnly);
+        let config = TransportListen::Udp(config);
+        let (subscriber_id, _) = controller.listen_with(config, multicast_addr).unwrap();
+
+        let subscriber = Endpoint::from_listener(subscriber_id, multicast_addr);
+        assert_eq!(controller.send(subscriber, b"data"), SendStatus::NotWritable);
+
+        let publisher = Endpoint::from_listener(publisher_id, multicast_addr);
+        assert_eq!(controller.send(publisher, b"data"), SendStatus::Sent);
+
+        let mut received = 0;
+        processor.process_poll_events_until_timeout(TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(endpoint.resource_id(), subscriber_id);
+                assert_eq!(data, b"data");
+                received += 1;
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(received, 1);
+    }
+
+    #[test]
+    fn write_only_connection() {
+        let receiver = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let (controller, mut processor) = network::split();
+        let config = UdpConnectConfig::default().with_direction(Direction::WriteOnly);
+        let config = TransportConnect::Udp(config);
+        let (endpoint, local_addr) =
+            controller.connect_with(config, receiver.local_addr().unwrap()).unwrap();
+        receiver.send_to(b"ignored", local_addr).unwrap();
+
+        processor.process_poll_events_until_timeout(TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            _ => unreachable!(),
+        });
+        assert_eq!(controller.send(endpoint, b"data"), SendStatus::Sent);
+
+        let mut buffer = [0; 64];
+        let size = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], b"data");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pmtu_discovery_modes() {
+        let receiver = match std::net::UdpSocket::bind("[::1]:0") {
+            Ok(receiver) => receiver,
+            Err(_) => return, // The environment has no IPv6 loopback to test with.
+        };
+        receiver.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+        // The MTU of the loopback fits any datagram, so the socket gets a smaller one.
+        let set_small_mtu = |socket: &Socket| {
+            let mtu: libc::c_int = 1280;
+            let mtu_ptr = &mtu as *const libc::c_int as *const libc::c_void;
+            let mtu_len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+            let (fd, level, name) = (socket.as_raw_fd(), libc::IPPROTO_IPV6, libc::IPV6_MTU);
+            match unsafe { libc::setsockopt(fd, level, name, mtu_ptr, mtu_len) } {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            }
+        };
+
+        let (controller, mut processor) = network::split();
+        let modes = [
+            (PmtuDiscovery::Do, SendStatus::MaxPacketSizeExceeded),
+            (PmtuDiscovery::Dont, SendStatus::Sent),
+            (PmtuDiscovery::Probe, SendStatus::MaxPacketSizeExceeded),
+        ];
+        for (mode, status) in modes {
+            let config = UdpConnectConfig::default()
+                .with_source_address("[::1]:0".parse().unwrap())
+                .with_pmtu_discovery(mode)
+                .with_socket(set_small_mtu);
+            let (endpoint, _) = controller
+                .connect_with(TransportConnect::Udp(config), receiver.local_addr().unwrap())
+                .unwrap();
+            processor.process_poll_event(Some(TIMEOUT), |_| ());
+
+            assert_eq!(controller.send(endpoint, &[1; 2000]), status, "{:?}", mode);
+            if status == SendStatus::Sent {
+                let mut buffer = [0; 4096];
+                assert_eq!(receiver.recv(&mut buffer).unwrap(), 2000); // Fragmented
+            }
+
+            // Larger than any datagram, whatever the mode.
+            let status = controller.send(endpoint, &[1; u16::MAX as usize]);
+            assert_eq!(status, SendStatus::MaxPacketSizeExceeded);
+            controller.remove(endpoint.resource_id());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn multicast_on_specified_interface() {
+        use nix::net::if_::{if_nametoindex, InterfaceFlags};
+
+        // A multicast capable interface with an IPv4 address, other than the loopback.
+        let interface = getifaddrs().unwrap().find_map(|ifaddr| {
+            let addr = ifaddr.address?.as_sockaddr_in().map(|addr| Ipv4Addr::from(addr.ip()))?;
+            let multicast = ifaddr.flags.contains(InterfaceFlags::IFF_MULTICAST);
+            match multicast && !addr.is_loopback() {
+                true => Some((ifaddr.interface_name, addr)),
+                false => None,
+            }
+        });
+        let (name, interface_addr) = match interface {
+            Some(interface) => interface,
+            None => return, // The environment has no interface to test with.
+        };
+        let index = if_nametoindex(name.as_str()).unwrap();
+
+        let (controller, mut processor) = network::split();
+        let interfaces =
+            [MulticastInterface::Address(interface_addr), MulticastInterface::Index(index)];
+        for (port, interface) in (3017..).zip(interfaces) {
+            let multicast_addr = SocketAddr::from(([239, 255, 0, 2], port));
+            let config = UdpListenConfig::default().with_multicast_interface(interface);
+            let (listener_id, _) =
+                controller.listen_with(TransportListen::Udp(config), multicast_addr).unwrap();
+
+            let config =
+                UdpConnectConfig::default().with_multicast_outgoing_interface(interface_addr);
+            let (endpoint, _) =
+                controller.connect_with(TransportConnect::Udp(config), multicast_addr).unwrap();
+
+            let mut received = Vec::new();
+            processor.process_poll_events_until_timeout(TIMEOUT, |net_event| match net_event {
+                NetEvent::Connected(endpoint, status) => {
+                    assert!(status);
+                    assert_eq!(controller.send(endpoint, b"data"), SendStatus::Sent);
+                }
+                NetEvent::Message(endpoint, data) => {
+                    assert_eq!(endpoint.resource_id(), listener_id);
+                    received.push(data.to_vec());
+                }
+                _ => unreachable!(),
+            });
+            assert_eq!(received, vec![b"data".to_vec()], "Multicast by {:?}", interface);
+            controller.remove(endpoint.resource_id());
+            controller.remove(listener_id);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::network::adapter::{
     Resource, Remote, Local, Adapter, SendStatus, AcceptedType, ReadStatus, ConnectionInfo,
-    ListeningInfo, PendingStatus,
+    ListeningInfo, PendingStatus, ControlFrame, ReceivedFrame,
 };
 use crate::network::{RemoteAddr, Readiness};
 use crate::util::thread::{OTHER_THREAD_ERR};
@@ -9,19 +9,24 @@ use crate::network::{TransportConnect, TransportListen};
 use mio::event::{Source};
 use mio::net::{TcpStream, TcpListener};
 
-use tungstenite::protocol::{WebSocket, Message};
-use tungstenite::{accept as ws_accept};
-use tungstenite::client::{client as ws_connect};
+use tungstenite::protocol::{WebSocket, Message, Role};
+use tungstenite::{accept_hdr as ws_accept};
+use tungstenite::client::{client as ws_connect, IntoClientRequest};
 use tungstenite::handshake::{
     HandshakeError, MidHandshake,
-    server::{ServerHandshake, NoCallback},
-    client::{ClientHandshake},
+    server::{ServerHandshake, Callback, ErrorResponse, Request as ServerRequest, Response},
+    client::{ClientHandshake, Request},
 };
-use tungstenite::error::{Error};
+use tungstenite::http::{header, HeaderValue, StatusCode};
+use tungstenite::handshake::client::{Response as HttpResponse};
+use tungstenite::error::{Error, ProtocolError};
 
-use url::Url;
+use url::{Url, Host};
 
-use std::sync::{Mutex, Arc};
+use std::sync::{
+    Mutex, Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use std::net::{SocketAddr};
 use std::io::{self, ErrorKind};
 use std::ops::{DerefMut};
@@ -30,6 +35,226 @@ use std::ops::{DerefMut};
 // From https://docs.rs/tungstenite/0.13.0/src/tungstenite/protocol/mod.rs.html#65
 pub const MAX_PAYLOAD_LEN: usize = 32 << 20;
 
+/// Max payload size of a control frame, as a ping.
+pub const MAX_CONTROL_PAYLOAD_LEN: usize = 125;
+
+/// Path of the uri used when connecting by a [`SocketAddr`].
+pub const DEFAULT_PATH: &str = "/message-io-default";
+
+/// Header of the handshake request that carries the resume token.
+/// See [`WsConnectConfig::with_resume_token()`].
+pub const RESUME_TOKEN_HEADER: &str = "x-message-io-resume-token";
+
+#[derive(Clone, Debug, Default)]
+pub struct WsConnectConfig {
+    control_frames: bool,
+    path: Option<String>,
+    host: Option<String>,
+    resume_token: Option<String>,
+    redirects: usize,
+}
+
+impl WsConnectConfig {
+    /// Sets the path of the uri requested in the handshake when connecting by a
+    /// [`crate::network::RemoteAddr::Socket`]. By default, [`DEFAULT_PATH`] is used.
+    /// When connecting by an url, its own path is used.
+    /// The path must start with `/`, otherwise the connection fails with an
+    /// [`ErrorKind::InvalidInput`] error.
+    pub fn with_path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Sets the `Host` header of the handshake when connecting by a
+    /// [`crate::network::RemoteAddr::Socket`]. By default, the socket address is used.
+    pub fn with_host(mut self, host: String) -> Self {
+        self.host = Some(host);
+        self
+    }
+
+    /// Presents an opaque `token` to the server in the handshake, in the
+    /// [`RESUME_TOKEN_HEADER`] header, that the server obtains by
+    /// [`crate::network::NetworkController::ws_resume_token()`].
+    /// Reconnecting with the same token allows the server to restore the session
+    /// of the client, distinguishing it from a new client.
+    ///
+    /// The token must be a valid header value (visible ASCII characters),
+    /// otherwise the connection fails with an [`std::io::ErrorKind::InvalidInput`] error.
+    pub fn with_resume_token(mut self, token: String) -> Self {
+        self.resume_token = Some(token);
+        self
+    }
+
+    /// Follows at most `max_hops` HTTP redirections (`3xx` responses with a `Location` header)
+    /// answered to the handshake, for example, by a load balancer.
+    /// Each redirection connects to its location, with the same resume token, if any.
+    /// The endpoint keeps the address of the first connection.
+    /// Only plain locations (`ws` or `http`) whose host is an IP address are followed,
+    /// so the thread that processes the network does not block resolving a domain.
+    /// Any other location is given as [`WsHandshakeRejected`], as a redirection not followed.
+    ///
+    /// By default, redirections are not followed: the connection fails and the response is
+    /// given as [`WsHandshakeRejected`] by
+    /// [`crate::network::NetworkController::connect_sync()`].
+    /// It is also given when `max_hops` redirections have already been followed.
+    pub fn with_redirects(mut self, max_hops: usize) -> Self {
+        self.redirects = max_hops;
+        self
+    }
+
+    /// Delivers the ping and pong frames received as [`crate::network::NetEvent::Control`]
+    /// events. By default, they are processed internally without generating any event.
+    ///
+    /// In both cases, each ping is answered with a pong containing its payload,
+    /// as the WebSocket protocol requires.
+    pub fn with_control_frames(mut self) -> Self {
+        self.control_frames = true;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WsListenConfig {
+    control_frames: bool,
+    path: Option<String>,
+}
+
+impl WsListenConfig {
+    /// Only accepts the handshakes that request the uri with this path.
+    /// The rest are answered with a `404 Not Found` response and closed
+    /// without generating any event.
+    /// By default, any path is accepted.
+    /// The path must start with `/`, otherwise the listening fails with an
+    /// [`ErrorKind::InvalidInput`] error.
+    pub fn with_path(mut self, path: String) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    /// Delivers the ping and pong frames received by client connections as events.
+    /// See [`WsConnectConfig::with_control_frames()`].
+    pub fn with_control_frames(mut self) -> Self {
+        self.control_frames = true;
+        self
+    }
+}
+
+/// The paths of the configurations must be an absolute path of an uri.
+fn check_path(path: Option<&str>) -> io::Result<()> {
+    match path {
+        Some(path) if !path.starts_with('/') => {
+            Err(io::Error::new(ErrorKind::InvalidInput, "The path must start with '/'"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A WebSocket connection whose handshake has already been performed outside message-io,
+/// e.g. a request upgraded by an HTTP server that shares the port with other HTTP services.
+///
+/// It is registered as a WebSocket endpoint passing it as [`TransportConnect::WsUpgraded`]
+/// to [`crate::network::NetworkController::connect_with()`].
+/// No handshake is performed: the `Connected` event is generated as soon as it is registered
+/// and then its messages are received normally.
+/// The endpoint address is the peer address of the stream,
+/// the address passed to `connect_with()` is not used.
+///
+/// Example
+/// ```no_run
+/// use message_io::node::{self};
+/// use message_io::network::{TransportConnect};
+/// use message_io::adapters::ws::{WsUpgraded};
+///
+/// # let stream = std::net::TcpStream::connect("127.0.0.1:8080").unwrap();
+/// // The stream comes from an HTTP request already upgraded to WebSocket.
+/// let upgraded = WsUpgraded::server(stream);
+/// let peer_addr = upgraded.peer_addr().unwrap();
+///
+/// let (handler, listener) = node::split::<()>();
+/// let config = TransportConnect::WsUpgraded(upgraded);
+/// let (endpoint, _) = handler.network().connect_with(config, peer_addr).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct WsUpgraded {
+    stream: std::net::TcpStream,
+    role: Role,
+    read_buffer: Vec<u8>,
+    control_frames: bool,
+}
+
+impl WsUpgraded {
+    /// Upgraded connection where this side accepted the handshake, as an HTTP server does.
+    pub fn server(stream: std::net::TcpStream) -> Self {
+        Self { stream, role: Role::Server, read_buffer: Vec::new(), control_frames: false }
+    }
+
+    /// Upgraded connection where this side requested the handshake.
+    pub fn client(stream: std::net::TcpStream) -> Self {
+        Self { stream, role: Role::Client, read_buffer: Vec::new(), control_frames: false }
+    }
+
+    /// Bytes already read from the stream after the handshake.
+    /// They are processed as WebSocket data before reading from the stream.
+    /// Usually it is the remaining buffer of the HTTP parser that performed the handshake.
+    pub fn with_read_buffer(mut self, read_buffer: Vec<u8>) -> Self {
+        self.read_buffer = read_buffer;
+        self
+    }
+
+    /// Delivers the ping and pong frames received as events.
+    /// See [`WsConnectConfig::with_control_frames()`].
+    pub fn with_control_frames(mut self) -> Self {
+        self.control_frames = true;
+        self
+    }
+
+    /// Address of the peer of the connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+}
+
+/// Response of the server that rejected the handshake of a connection,
+/// as a proxy that responds with a status other than `101 Switching Protocols`.
+///
+/// It is the inner error of the `ConnectionRefused` error returned by
+/// [`crate::network::NetworkController::connect_sync()`] for a WebSocket connection,
+/// obtained by [`io::Error::get_ref()`] and downcasting it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WsHandshakeRejected {
+    /// Status code of the response.
+    pub status: u16,
+
+    /// Headers of the response, in the order they were received.
+    /// The values that are not visible ASCII are replaced by its lossy UTF-8 version.
+    pub headers: Vec<(String, String)>,
+}
+
+impl WsHandshakeRejected {
+    fn new(response: &HttpResponse) -> Self {
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned())
+            })
+            .collect();
+        Self { status: response.status().as_u16(), headers }
+    }
+}
+
+impl std::fmt::Display for WsHandshakeRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WebSocket handshake rejected with status {}", self.status)?;
+        for (name, value) in &self.headers {
+            write!(f, ", {name}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for WsHandshakeRejected {}
+
 pub(crate) struct WsAdapter;
 impl Adapter for WsAdapter {
     type Remote = RemoteResource;
@@ -37,10 +262,47 @@ impl Adapter for WsAdapter {
 }
 
 enum PendingHandshake {
-    Connect(Url, ArcTcpStream),
-    Accept(ArcTcpStream),
+    Connect(Request, ArcTcpStream),
+    Accept(ArcTcpStream, RequestFilter),
     Client(MidHandshake<ClientHandshake<ArcTcpStream>>),
-    Server(MidHandshake<ServerHandshake<ArcTcpStream, NoCallback>>),
+    Server(MidHandshake<ServerHandshake<ArcTcpStream, RequestFilter>>),
+}
+
+/// Rejects the handshake requests of other path than the expected one, if any,
+/// and keeps the resume token of the accepted request.
+struct RequestFilter {
+    path: Option<String>,
+    resume_token: Arc<Mutex<Option<String>>>,
+}
+
+impl Callback for RequestFilter {
+    fn on_request(
+        self,
+        request: &ServerRequest,
+        response: Response,
+    ) -> Result<Response, ErrorResponse> {
+        match self.path {
+            Some(path) if request.uri().path() != path => {
+                let mut response = ErrorResponse::new(None);
+                *response.status_mut() = StatusCode::NOT_FOUND;
+                Err(response)
+            }
+            _ => {
+                let token = request.headers().get(RESUME_TOKEN_HEADER);
+                let token = token.and_then(|token| token.to_str().ok()).map(String::from);
+                *self.resume_token.lock().expect(OTHER_THREAD_ERR) = token;
+                Ok(response)
+            }
+        }
+    }
+}
+
+/// State of the redirections of a connection configured to follow them.
+struct Redirection {
+    hops_left: usize,
+    url: Url, // Requested by the current handshake, to resolve relative locations.
+    resume_token: Option<String>,
+    previous: Option<ArcTcpStream>, // Stream redirected, until it is replaced in the poll.
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -52,6 +314,11 @@ enum RemoteState {
 
 pub(crate) struct RemoteResource {
     state: Mutex<RemoteState>,
+    control_frames: bool,
+    resume_token: Arc<Mutex<Option<String>>>, // Presented by the client, if it was accepted.
+    redirection: Mutex<Option<Redirection>>,
+    rejection: Mutex<Option<WsHandshakeRejected>>,
+    closing: AtomicBool, // The close frame could not be written yet.
 }
 
 impl Resource for RemoteResource {
@@ -62,7 +329,7 @@ impl Resource for RemoteResource {
             }
             RemoteState::Handshake(Some(handshake)) => match handshake {
                 PendingHandshake::Connect(_, stream) => Arc::get_mut(&mut stream.0).unwrap(),
-                PendingHandshake::Accept(stream) => Arc::get_mut(&mut stream.0).unwrap(),
+                PendingHandshake::Accept(stream, _) => Arc::get_mut(&mut stream.0).unwrap(),
                 PendingHandshake::Client(handshake) => {
                     Arc::get_mut(&mut handshake.get_mut().get_mut().0).unwrap()
                 }
@@ -78,35 +345,62 @@ impl Resource for RemoteResource {
 
 impl Remote for RemoteResource {
     fn connect_with(
-        _: TransportConnect,
+        config: TransportConnect,
         remote_addr: RemoteAddr,
     ) -> io::Result<ConnectionInfo<Self>> {
-        let (peer_addr, url) = match remote_addr {
+        let config = match config {
+            TransportConnect::Ws(config) => config,
+            TransportConnect::WsUpgraded(upgraded) => return Self::adopt(upgraded),
+            _ => panic!("Internal error: Got wrong config"),
+        };
+        check_path(config.path.as_deref())?;
+
+        let (peer_addr, url, mut request) = match remote_addr {
             RemoteAddr::Socket(addr) => {
-                (addr, Url::parse(&format!("ws://{addr}/message-io-default")).unwrap())
+                let path = config.path.as_deref().unwrap_or(DEFAULT_PATH);
+                let url = Url::parse(&format!("ws://{addr}{path}"))
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))?;
+                let mut request = Self::request(url.clone())?;
+                if let Some(host) = &config.host {
+                    request.headers_mut().insert(header::HOST, Self::header_value(host)?);
+                }
+                (addr, url, request)
             }
             RemoteAddr::Str(path) => {
                 let url = Url::parse(&path).expect("A valid URL");
-                let addr = url
-                    .socket_addrs(|| match url.scheme() {
-                        "ws" => Some(80),   // Plain
-                        "wss" => Some(443), //Tls
-                        _ => None,
-                    })
-                    .unwrap()[0];
-                (addr, url)
+                let addr = Self::url_addr(&url).unwrap();
+                (addr, url.clone(), Self::request(url)?)
             }
         };
 
+        if let Some(token) = &config.resume_token {
+            request.headers_mut().insert(RESUME_TOKEN_HEADER, Self::header_value(token)?);
+        }
+
+        let redirection = match config.redirects {
+            0 => None,
+            hops_left => Some(Redirection {
+                hops_left,
+                url,
+                resume_token: config.resume_token,
+                previous: None,
+            }),
+        };
+
         let stream = TcpStream::connect(peer_addr)?;
         let local_addr = stream.local_addr()?;
 
         Ok(ConnectionInfo {
             remote: RemoteResource {
                 state: Mutex::new(RemoteState::Handshake(Some(PendingHandshake::Connect(
-                    url,
+                    request,
                     stream.into(),
                 )))),
+                control_frames: config.control_frames,
+                resume_token: Arc::default(),
+                redirection: Mutex::new(redirection),
+                rejection: Mutex::new(None),
+                closing: AtomicBool::new(false),
             },
             local_addr,
             peer_addr,
@@ -114,33 +408,50 @@ impl Remote for RemoteResource {
     }
 
     fn receive(&self, mut process_data: impl FnMut(&[u8])) -> ReadStatus {
+        self.receive_frames(|frame| {
+            if let ReceivedFrame::Message(data) = frame {
+                process_data(data)
+            }
+        })
+    }
+
+    fn receive_frames(&self, mut process_frame: impl FnMut(ReceivedFrame<'_>)) -> ReadStatus {
         loop {
             // "emulates" full duplex for the websocket case locking here and not outside the loop.
             let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
             let deref_state = state.deref_mut();
             match deref_state {
-                RemoteState::WebSocket(web_socket) => match web_socket.read_message() {
+                RemoteState::WebSocket(web_socket) => match web_socket.read() {
                     Ok(message) => match message {
                         Message::Binary(data) => {
                             // As an optimization.
                             // Fast check to know if there is more data to avoid call
-                            // WebSocket::read_message() again.
+                            // WebSocket::read() again.
                             // TODO: investigate why this code doesn't work in windows.
                             // Seems like windows consume the `WouldBlock` notification
                             // at peek() when it happens, and the poll never wakes it again.
                             #[cfg(not(target_os = "windows"))]
                             let _peek_result = web_socket.get_ref().0.peek(&mut [0; 0]);
 
-                            // We can not call process_data while the socket is blocked.
+                            // We can not call process_frame while the socket is blocked.
                             // The user could lock it again if sends from the callback.
                             drop(state);
-                            process_data(&data);
+                            process_frame(ReceivedFrame::Message(&data));
 
                             #[cfg(not(target_os = "windows"))]
                             if let Err(err) = _peek_result {
                                 break Self::io_error_to_read_status(&err)
                             }
                         }
+                        // No fast check here: the next read() writes the pong answer of a ping.
+                        Message::Ping(data) if self.control_frames => {
+                            drop(state);
+                            process_frame(ReceivedFrame::Control(ControlFrame::Ping, &data));
+                        }
+                        Message::Pong(data) if self.control_frames => {
+                            drop(state);
+                            process_frame(ReceivedFrame::Control(ControlFrame::Pong, &data));
+                        }
                         Message::Close(_) => break ReadStatus::Disconnected,
                         _ => continue,
                     },
@@ -157,29 +468,18 @@ impl Remote for RemoteResource {
     }
 
     fn send(&self, data: &[u8]) -> SendStatus {
-        let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
-        let deref_state = state.deref_mut();
-        match deref_state {
-            RemoteState::WebSocket(web_socket) => {
-                let message = Message::Binary(data.to_vec());
-                let mut result = web_socket.write_message(message);
-                loop {
-                    match result {
-                        Ok(_) => break SendStatus::Sent,
-                        Err(Error::Io(ref err)) if err.kind() == ErrorKind::WouldBlock => {
-                            result = web_socket.write_pending();
-                        }
-                        Err(Error::Capacity(_)) => break SendStatus::MaxPacketSizeExceeded,
-                        Err(err) => {
-                            log::error!("WS send error: {}", err);
-                            break SendStatus::ResourceNotFound // should not happen
-                        }
-                    }
-                }
-            }
-            RemoteState::Handshake(_) => unreachable!(),
-            RemoteState::Error(_) => unreachable!(),
+        self.send_message(Message::Binary(data.to_vec()))
+    }
+
+    fn send_ping(&self, data: &[u8]) -> SendStatus {
+        if data.len() > MAX_CONTROL_PAYLOAD_LEN {
+            return SendStatus::MaxPacketSizeExceeded
         }
+        self.send_message(Message::Ping(data.to_vec()))
+    }
+
+    fn resume_token(&self) -> Option<String> {
+        self.resume_token.lock().expect(OTHER_THREAD_ERR).clone()
     }
 
     fn pending(&self, _readiness: Readiness) -> PendingStatus {
@@ -188,15 +488,15 @@ impl Remote for RemoteResource {
         match deref_state {
             RemoteState::WebSocket(_) => PendingStatus::Ready,
             RemoteState::Handshake(pending) => match pending.take().unwrap() {
-                PendingHandshake::Connect(url, stream) => {
+                PendingHandshake::Connect(request, stream) => {
                     let tcp_status = super::tcp::check_stream_ready(&stream.0);
                     if tcp_status != PendingStatus::Ready {
                         // TCP handshake not ready yet.
-                        *pending = Some(PendingHandshake::Connect(url, stream));
+                        *pending = Some(PendingHandshake::Connect(request, stream));
                         return tcp_status
                     }
                     let stream_backup = stream.clone();
-                    match ws_connect(url, stream) {
+                    match ws_connect(request, stream) {
                         Ok((web_socket, _)) => {
                             *state = RemoteState::WebSocket(web_socket);
                             PendingStatus::Ready
@@ -205,20 +505,14 @@ impl Remote for RemoteResource {
                             *pending = Some(PendingHandshake::Client(mid_handshake));
                             PendingStatus::Incomplete
                         }
-                        Err(HandshakeError::Failure(Error::Io(_))) => {
-                            *state = RemoteState::Error(stream_backup);
-                            PendingStatus::Disconnected
-                        }
                         Err(HandshakeError::Failure(err)) => {
-                            *state = RemoteState::Error(stream_backup);
-                            log::error!("WS connect handshake error: {}", err);
-                            PendingStatus::Disconnected // should not happen
+                            self.handshake_failed(&mut state, err, Role::Client, stream_backup)
                         }
                     }
                 }
-                PendingHandshake::Accept(stream) => {
+                PendingHandshake::Accept(stream, filter) => {
                     let stream_backup = stream.clone();
-                    match ws_accept(stream) {
+                    match ws_accept(stream, filter) {
                         Ok(web_socket) => {
                             *state = RemoteState::WebSocket(web_socket);
                             PendingStatus::Ready
@@ -227,14 +521,8 @@ impl Remote for RemoteResource {
                             *pending = Some(PendingHandshake::Server(mid_handshake));
                             PendingStatus::Incomplete
                         }
-                        Err(HandshakeError::Failure(Error::Io(_))) => {
-                            *state = RemoteState::Error(stream_backup);
-                            PendingStatus::Disconnected
-                        }
                         Err(HandshakeError::Failure(err)) => {
-                            *state = RemoteState::Error(stream_backup);
-                            log::error!("WS accept handshake error: {}", err);
-                            PendingStatus::Disconnected
+                            self.handshake_failed(&mut state, err, Role::Server, stream_backup)
                         }
                     }
                 }
@@ -249,14 +537,8 @@ impl Remote for RemoteResource {
                             *pending = Some(PendingHandshake::Client(mid_handshake));
                             PendingStatus::Incomplete
                         }
-                        Err(HandshakeError::Failure(Error::Io(_))) => {
-                            *state = RemoteState::Error(stream_backup);
-                            PendingStatus::Disconnected
-                        }
                         Err(HandshakeError::Failure(err)) => {
-                            *state = RemoteState::Error(stream_backup);
-                            log::error!("WS client handshake error: {}", err);
-                            PendingStatus::Disconnected // should not happen
+                            self.handshake_failed(&mut state, err, Role::Client, stream_backup)
                         }
                     }
                 }
@@ -271,14 +553,8 @@ impl Remote for RemoteResource {
                             *pending = Some(PendingHandshake::Server(mid_handshake));
                             PendingStatus::Incomplete
                         }
-                        Err(HandshakeError::Failure(Error::Io(_))) => {
-                            *state = RemoteState::Error(stream_backup);
-                            PendingStatus::Disconnected
-                        }
                         Err(HandshakeError::Failure(err)) => {
-                            *state = RemoteState::Error(stream_backup);
-                            log::error!("WS server handshake error: {}", err);
-                            PendingStatus::Disconnected // should not happen
+                            self.handshake_failed(&mut state, err, Role::Server, stream_backup)
                         }
                     }
                 }
@@ -287,11 +563,45 @@ impl Remote for RemoteResource {
         }
     }
 
+    fn replace_source(&self, replace: &mut dyn FnMut(&mut dyn Source, &mut dyn Source)) {
+        let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
+        let mut redirection = self.redirection.lock().expect(OTHER_THREAD_ERR);
+        let previous = redirection.as_mut().and_then(|redirection| redirection.previous.take());
+        if let Some(mut previous) = previous {
+            if let RemoteState::Handshake(Some(PendingHandshake::Connect(_, stream))) =
+                state.deref_mut()
+            {
+                let previous = Arc::get_mut(&mut previous.0).unwrap();
+                replace(previous, Arc::get_mut(&mut stream.0).unwrap());
+            }
+        }
+    }
+
+    fn connect_error(&self) -> Option<io::Error> {
+        let rejection = self.rejection.lock().expect(OTHER_THREAD_ERR).clone()?;
+        Some(io::Error::new(ErrorKind::ConnectionRefused, rejection))
+    }
+
     fn ready_to_write(&self) -> bool {
+        if self.closing.load(Ordering::Relaxed) {
+            let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
+            if let RemoteState::WebSocket(web_socket) = state.deref_mut() {
+                match web_socket.flush() {
+                    Err(Error::Io(ref err)) if err.kind() == ErrorKind::WouldBlock => (),
+                    Err(Error::ConnectionClosed) | Ok(_) => {
+                        self.closing.store(false, Ordering::Relaxed);
+                    }
+                    Err(err) => {
+                        log::trace!("WS close error: {}", err);
+                        self.closing.store(false, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
         true
         /* Is this needed?
         match self.state.lock().expect(OTHER_THREAD_ERR).deref_mut() {
-            RemoteState::WebSocket(web_socket) => match web_socket.write_pending() {
+            RemoteState::WebSocket(web_socket) => match web_socket.flush() {
                 Ok(_) => true,
                 Err(Error::Io(ref err)) if err.kind() == ErrorKind::WouldBlock => true,
                 Err(_) => false, // Will be disconnected,
@@ -302,9 +612,221 @@ impl Remote for RemoteResource {
         }
         */
     }
+
+    fn close(&self) {
+        let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
+        if let RemoteState::WebSocket(web_socket) = state.deref_mut() {
+            // The close frame is sent without waiting the response of the other side.
+            // If it can not be written now, it is written by the next write readiness.
+            match web_socket.close(None) {
+                Err(Error::Io(ref err)) if err.kind() == ErrorKind::WouldBlock => {
+                    self.closing.store(true, Ordering::Relaxed);
+                }
+                Err(Error::ConnectionClosed) | Ok(_) => (),
+                Err(err) => log::trace!("WS close error: {}", err),
+            }
+        }
+    }
+
+    fn has_queued_data(&self) -> bool {
+        self.closing.load(Ordering::Relaxed)
+    }
 }
 
 impl RemoteResource {
+    fn request(url: Url) -> io::Result<Request> {
+        url.into_client_request().map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))
+    }
+
+    fn header_value(value: &str) -> io::Result<HeaderValue> {
+        HeaderValue::from_str(value).map_err(|err| io::Error::new(ErrorKind::InvalidInput, err))
+    }
+
+    fn url_addr(url: &Url) -> io::Result<SocketAddr> {
+        let addrs = url.socket_addrs(|| match url.scheme() {
+            "ws" => Some(80),   // Plain
+            "wss" => Some(443), //Tls
+            _ => None,
+        })?;
+        addrs.first().copied().ok_or_else(|| io::Error::from(ErrorKind::AddrNotAvailable))
+    }
+
+    /// Address of a redirection location, whose host must be an IP address.
+    fn location_addr(url: &Url) -> io::Result<SocketAddr> {
+        let port = url.port_or_known_default().unwrap_or(80);
+        match url.host() {
+            Some(Host::Ipv4(ip)) => Ok(SocketAddr::new(ip.into(), port)),
+            Some(Host::Ipv6(ip)) => Ok(SocketAddr::new(ip.into(), port)),
+            _ => Err(io::Error::new(ErrorKind::InvalidInput, "The host must be an IP address")),
+        }
+    }
+
+    /// Resolves a handshake of the `role` side that failed by `err`.
+    fn handshake_failed(
+        &self,
+        state: &mut RemoteState,
+        err: Error,
+        role: Role,
+        stream: ArcTcpStream,
+    ) -> PendingStatus {
+        match (err, role) {
+            (Error::Http(response), Role::Client) => {
+                self.handshake_rejected(state, &response, stream)
+            }
+            (err, _) => {
+                *state = RemoteState::Error(stream);
+                match err {
+                    Error::Io(_) => (),
+                    Error::Http(response) => {
+                        log::trace!("WS handshake rejected: {}", response.status())
+                    }
+                    // Should not happen
+                    err => log::error!("WS {:?} handshake error: {}", role, err),
+                }
+                PendingStatus::Disconnected
+            }
+        }
+    }
+
+    /// Processes the response of the server to a failed handshake:
+    /// the connection is redirected if the response allows it, otherwise, it is rejected.
+    fn handshake_rejected(
+        &self,
+        state: &mut RemoteState,
+        response: &HttpResponse,
+        stream: ArcTcpStream,
+    ) -> PendingStatus {
+        match self.follow_redirection(response, stream) {
+            Ok((request, stream)) => {
+                *state = RemoteState::Handshake(Some(PendingHandshake::Connect(request, stream)));
+                PendingStatus::Incomplete
+            }
+            Err(stream) => {
+                *state = RemoteState::Error(stream);
+                log::trace!("WS handshake rejected by the server: {}", response.status());
+                let rejection = WsHandshakeRejected::new(response);
+                *self.rejection.lock().expect(OTHER_THREAD_ERR) = Some(rejection);
+                PendingStatus::Disconnected
+            }
+        }
+    }
+
+    /// Connects to the location of the redirection `response`, if the connection
+    /// follows redirections and it has hops left.
+    /// Returns the request and the stream of the new handshake, or the `stream` back if
+    /// it is not redirected.
+    /// The redirected stream is kept until [`Remote::replace_source()`] replaces it.
+    fn follow_redirection(
+        &self,
+        response: &HttpResponse,
+        stream: ArcTcpStream,
+    ) -> Result<(Request, ArcTcpStream), ArcTcpStream> {
+        let mut redirection = self.redirection.lock().expect(OTHER_THREAD_ERR);
+        let redirection = match redirection.as_mut() {
+            Some(redirection) if redirection.hops_left > 0 => redirection,
+            _ => return Err(stream),
+        };
+        if !response.status().is_redirection() {
+            return Err(stream)
+        }
+
+        let location = response.headers().get(header::LOCATION);
+        let location = location.and_then(|location| location.to_str().ok());
+        let redirected = location.ok_or_else(|| io::Error::from(ErrorKind::InvalidData)).and_then(
+            |location| {
+                let mut url = redirection
+                    .url
+                    .join(location)
+                    .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+                match url.scheme() {
+                    "ws" => (),
+                    "http" => url.set_scheme("ws").expect("A valid scheme"),
+                    _ => return Err(io::Error::new(ErrorKind::Unsupported, "TLS not supported")),
+                }
+
+                let mut request = Self::request(url.clone())?;
+                if let Some(token) = &redirection.resume_token {
+                    request.headers_mut().insert(RESUME_TOKEN_HEADER, Self::header_value(token)?);
+                }
+                let new_stream = TcpStream::connect(Self::location_addr(&url)?)?;
+                Ok((url, request, new_stream))
+            },
+        );
+
+        match redirected {
+            Ok((url, request, new_stream)) => {
+                log::trace!("WS handshake redirected to {}", url);
+                redirection.hops_left -= 1;
+                redirection.url = url;
+                redirection.previous = Some(stream);
+                Ok((request, new_stream.into()))
+            }
+            Err(err) => {
+                log::trace!("WS handshake redirection could not be followed: {}", err);
+                Err(stream)
+            }
+        }
+    }
+
+    fn send_message(&self, message: Message) -> SendStatus {
+        let mut state = self.state.lock().expect(OTHER_THREAD_ERR);
+        match state.deref_mut() {
+            RemoteState::WebSocket(web_socket) => {
+                let mut result = web_socket.send(message);
+                loop {
+                    match result {
+                        Ok(_) => break SendStatus::Sent,
+                        Err(Error::Io(ref err)) if err.kind() == ErrorKind::WouldBlock => {
+                            result = web_socket.flush();
+                        }
+                        Err(Error::Capacity(_)) => break SendStatus::MaxPacketSizeExceeded,
+                        Err(Error::Io(ref err)) if super::tcp::is_disconnection(err) => {
+                            log::trace!("WS send to a closed connection: {}", err);
+                            break SendStatus::ResourceNotFound
+                        }
+                        Err(
+                            err @ (Error::ConnectionClosed
+                            | Error::AlreadyClosed
+                            | Error::Protocol(ProtocolError::SendAfterClosing)),
+                        ) => {
+                            log::trace!("WS send to a closed connection: {}", err);
+                            break SendStatus::ResourceNotFound
+                        }
+                        Err(err) => {
+                            log::error!("WS send error: {}", err);
+                            break SendStatus::ResourceNotFound // should not happen
+                        }
+                    }
+                }
+            }
+            RemoteState::Handshake(_) => unreachable!(),
+            RemoteState::Error(_) => unreachable!(),
+        }
+    }
+
+    fn adopt(upgraded: WsUpgraded) -> io::Result<ConnectionInfo<Self>> {
+        let WsUpgraded { stream, role, read_buffer, control_frames } = upgraded;
+        stream.set_nonblocking(true)?;
+        let local_addr = stream.local_addr()?;
+        let peer_addr = stream.peer_addr()?;
+
+        let stream = ArcTcpStream::from(TcpStream::from_std(stream));
+        let web_socket = WebSocket::from_partially_read(stream, read_buffer, role, None);
+
+        Ok(ConnectionInfo {
+            remote: RemoteResource {
+                state: Mutex::new(RemoteState::WebSocket(web_socket)),
+                control_frames,
+                resume_token: Arc::default(),
+                redirection: Mutex::new(None),
+                rejection: Mutex::new(None),
+                closing: AtomicBool::new(false),
+            },
+            local_addr,
+            peer_addr,
+        })
+    }
+
     fn io_error_to_read_status(err: &io::Error) -> ReadStatus {
         if err.kind() == io::ErrorKind::WouldBlock {
             ReadStatus::WaitNextEvent
@@ -320,40 +842,85 @@ impl RemoteResource {
 }
 
 pub(crate) struct LocalResource {
-    listener: TcpListener,
+    listener: Mutex<TcpListener>,
+    control_frames: bool,
+    path: Option<String>,
 }
 
 impl Resource for LocalResource {
     fn source(&mut self) -> &mut dyn Source {
-        &mut self.listener
+        self.listener.get_mut().expect(OTHER_THREAD_ERR)
     }
 }
 
 impl Local for LocalResource {
     type Remote = RemoteResource;
 
-    fn listen_with(_: TransportListen, addr: SocketAddr) -> io::Result<ListeningInfo<Self>> {
+    fn listen_with(config: TransportListen, addr: SocketAddr) -> io::Result<ListeningInfo<Self>> {
+        let config = match config {
+            TransportListen::Ws(config) => config,
+            _ => panic!("Internal error: Got wrong config"),
+        };
+        check_path(config.path.as_deref())?;
+
         let listener = TcpListener::bind(addr)?;
         let local_addr = listener.local_addr().unwrap();
-        Ok(ListeningInfo { local: LocalResource { listener }, local_addr })
+        Ok(ListeningInfo {
+            local: LocalResource {
+                listener: Mutex::new(listener),
+                control_frames: config.control_frames,
+                path: config.path,
+            },
+            local_addr,
+        })
     }
 
-    fn accept(&self, mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
-        loop {
-            match self.listener.accept() {
+    fn accept(&self, accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>)) {
+        self.accept_limited(usize::MAX, accept_remote);
+    }
+
+    fn accept_limited(
+        &self,
+        limit: usize,
+        mut accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>),
+    ) -> bool {
+        let mut accepted = 0;
+        while accepted < limit {
+            let result = self.listener.lock().expect(OTHER_THREAD_ERR).accept();
+            match result {
                 Ok((stream, addr)) => {
+                    accepted += 1;
+                    let resume_token = Arc::default();
+                    let filter = RequestFilter {
+                        path: self.path.clone(),
+                        resume_token: Arc::clone(&resume_token),
+                    };
                     let remote = RemoteResource {
                         state: Mutex::new(RemoteState::Handshake(Some(PendingHandshake::Accept(
                             stream.into(),
+                            filter,
                         )))),
+                        control_frames: self.control_frames,
+                        resume_token,
+                        redirection: Mutex::new(None),
+                        rejection: Mutex::new(None),
+                        closing: AtomicBool::new(false),
                     };
                     accept_remote(AcceptedType::Remote(addr, remote));
                 }
-                Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => return false,
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
-                Err(err) => break log::error!("WS accept error: {}", err), // Should not happen
+                Err(err) => {
+                    log::error!("WS accept error: {}", err); // Should not happen
+                    return false
+                }
             }
         }
+        true
+    }
+
+    fn update_source(&self, update: &mut dyn FnMut(&mut dyn Source)) {
+        update(&mut *self.listener.lock().expect(OTHER_THREAD_ERR));
     }
 }
 
@@ -214,6 +214,13 @@ where E: Send + 'static
     pub fn cancel_timer(&self, timer_id: TimerId) {
         self.timer_sender.send((timer_id.0, TimerCommand::Cancel)).ok();
     }
+
+    /// Returns the number of events sent that are waiting to be received.
+    /// Timed events are not counted while they are waiting for their time.
+    /// This call is cheap, it can be used to check how busy the receiver is.
+    pub fn pending_len(&self) -> usize {
+        self.sender.len() + self.priority_sender.len()
+    }
 }
 
 impl<E> Clone for EventSender<E>
@@ -269,6 +276,20 @@ mod tests {
         assert_eq!(queue.receive_timeout(*ZERO_MS).unwrap(), "standard");
     }
 
+    #[test]
+    fn pending_len() {
+        let mut queue = EventReceiver::default();
+        for _ in 0..100 {
+            queue.sender().send("standard");
+        }
+        queue.sender().send_with_priority("priority");
+        queue.sender().send_with_timer("timed", *TIMEOUT);
+        assert_eq!(queue.sender().pending_len(), 101);
+
+        while queue.receive_timeout(*ZERO_MS).is_some() {}
+        assert_eq!(queue.sender().pending_len(), 0);
+    }
+
     #[test]
     fn timer_events_order() {
         let mut queue = EventReceiver::default();
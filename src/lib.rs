@@ -21,3 +21,8 @@ pub mod events;
 
 /// General purpose utilities.
 pub mod util;
+
+/// Helpers to write tests of applications that use the library.
+/// Available with the `testing` feature.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
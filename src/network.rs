@@ -6,6 +6,15 @@ mod driver;
 mod remote_addr;
 mod transport;
 mod loader;
+mod accept_rate;
+mod throughput;
+mod framing;
+mod group;
+mod watermarks;
+mod ticket;
+mod error;
+#[cfg(feature = "network-monitor")]
+mod monitor;
 
 /// Module that specify the pattern to follow to create adapters.
 /// This module is not part of the public API itself,
@@ -13,32 +22,130 @@ mod loader;
 pub mod adapter;
 
 // Reexports
-pub use adapter::{SendStatus};
+pub use adapter::{SendStatus, ControlFrame, Direction};
 pub use resource_id::{ResourceId, ResourceType};
 pub use endpoint::{Endpoint};
-pub use remote_addr::{RemoteAddr, ToRemoteAddr};
-pub use transport::{Transport, TransportConnect, TransportListen};
+pub use remote_addr::{RemoteAddr, ToRemoteAddr, ResolvePreference};
+pub use transport::{Transport, TransportConnect, TransportListen, TransportDefaults};
 pub use driver::{NetEvent};
-pub use poll::{Readiness};
+pub use poll::{Readiness, ReadinessNotifier};
+pub use accept_rate::{AcceptRate};
+pub use throughput::{ThroughputSample};
+pub use framing::{FramingStats};
+pub use group::{GroupId};
+pub use watermarks::{Watermarks, Watermark};
+pub use ticket::{SendTicket, TicketStatus, SendCompletion};
+pub use error::{NetworkError};
+
+use crate::util::thread::{self, OTHER_THREAD_ERR};
 
 use loader::{DriverLoader, ActionControllerList, EventProcessorList};
-use poll::{Poll, PollEvent};
+use driver::{ConnectOptions};
+use poll::{Poll, PollEvent, PollWaker};
+#[cfg(feature = "network-monitor")]
+use monitor::{NetworkMonitor};
 
 use strum::{IntoEnumIterator};
 
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::time::{Duration, Instant};
 use std::io::{self};
+use std::sync::{
+    Arc, RwLock,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
 
 /// Create a network instance giving its controller and processor.
 pub fn split() -> (NetworkController, NetworkProcessor) {
-    let mut drivers = DriverLoader::default();
+    split_with(TransportDefaults::default())
+}
+
+/// Similar to [`split()`] but the network uses `defaults` as the options of the transports
+/// used without explicit options. See [`TransportDefaults`].
+pub fn split_with(defaults: TransportDefaults) -> (NetworkController, NetworkProcessor) {
+    split_on(Poll::default(), defaults)
+}
+
+/// Similar to [`split_with()`] but the network resources are registered in the `registry`
+/// of an external [`mio::Poll`], so the network can share the event loop with other
+/// `mio` sources without an additional poll or thread.
+/// The events of that poll must be given to [`NetworkProcessor::process_ready()`].
+///
+/// The tokens of the poll are shared, so they must be coordinated:
+/// the network uses the token `0` for its waker and the odd tokens for its resources.
+/// The rest of sources must use even tokens greater than `0`, whose events are ignored
+/// by the network.
+/// Note that `mio` only allows one waker per poll, so only one network can be registered
+/// in a poll, and the rest of sources can not create a waker.
+///
+/// The `mio` version used by the poll must be the same as the one of this crate.
+///
+/// Example
+/// ```
+/// use message_io::network::{self, Transport, TransportDefaults, NetEvent};
+/// use mio::{Poll, Events, Token, Interest};
+/// use mio::net::{UdpSocket};
+/// use std::time::{Duration};
+///
+/// let mut poll = Poll::new().unwrap();
+/// let mut events = Events::with_capacity(1024);
+/// let (controller, mut processor) =
+///     network::split_with_registry(poll.registry(), TransportDefaults::default());
+///
+/// // Other sources must use even tokens greater than 0.
+/// const OTHER_SOURCE: Token = Token(2);
+/// let mut other_socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+/// poll.registry().register(&mut other_socket, OTHER_SOURCE, Interest::READABLE).unwrap();
+///
+/// let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+/// let (server, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+///
+/// let mut received = false;
+/// while !received {
+///     // Let the network limit the wait time, it could have work to do without events.
+///     let timeout = processor.poll_timeout().unwrap_or(Duration::from_secs(1));
+///     poll.poll(&mut events, Some(timeout)).unwrap();
+///     for event in &events {
+///         if event.token() == OTHER_SOURCE {
+///             // Process the other socket...
+///         }
+///     }
+///     processor.process_ready(&events, |net_event| match net_event {
+///         NetEvent::Connected(endpoint, _) => {
+///             controller.send(endpoint, b"hello");
+///         }
+///         NetEvent::Message(_, data) => {
+///             assert_eq!(data, b"hello");
+///             received = true;
+///         }
+///         _ => (),
+///     });
+/// }
+/// ```
+pub fn split_with_registry(
+    registry: &mio::Registry,
+    defaults: TransportDefaults,
+) -> (NetworkController, NetworkProcessor) {
+    split_on(Poll::external(registry), defaults)
+}
+
+fn split_on(poll: Poll, defaults: TransportDefaults) -> (NetworkController, NetworkProcessor) {
+    let mut drivers = DriverLoader::new(poll);
     Transport::iter().for_each(|transport| transport.mount_adapter(&mut drivers));
 
-    let (poll, controllers, processors) = drivers.take();
+    let (mut poll, controllers, processors) = drivers.take();
 
-    let network_controller = NetworkController::new(controllers);
-    let network_processor = NetworkProcessor::new(poll, processors);
+    let sampling = Arc::new(RwLock::new(None));
+    let network_changed = Arc::new(AtomicBool::new(false));
+    let waker = poll.create_waker();
+    let network_controller = NetworkController::new(
+        controllers,
+        defaults,
+        sampling.clone(),
+        network_changed.clone(),
+        waker,
+    );
+    let network_processor = NetworkProcessor::new(poll, processors, sampling, network_changed);
 
     (network_controller, network_processor)
 }
@@ -46,16 +153,82 @@ pub fn split() -> (NetworkController, NetworkProcessor) {
 /// Shareable instance in charge of control all the connections.
 pub struct NetworkController {
     controllers: ActionControllerList,
+    resolve_preference: RwLock<ResolvePreference>,
+    defaults: TransportDefaults,
+    sampling: Arc<RwLock<Option<Duration>>>, // Interval of the throughput samples.
+    connecting_events: AtomicBool,
+    connect_failed_events: AtomicBool,
+    next_group: AtomicU64,
+    network_changed: Arc<AtomicBool>, // A NetEvent::NetworkChanged must be generated.
+    #[cfg(feature = "network-monitor")]
+    network_monitor: std::sync::Mutex<Option<NetworkMonitor>>,
+    waker: PollWaker,
 }
 
 impl NetworkController {
-    fn new(controllers: ActionControllerList) -> NetworkController {
-        Self { controllers }
+    fn new(
+        controllers: ActionControllerList,
+        defaults: TransportDefaults,
+        sampling: Arc<RwLock<Option<Duration>>>,
+        network_changed: Arc<AtomicBool>,
+        waker: PollWaker,
+    ) -> NetworkController {
+        Self {
+            controllers,
+            resolve_preference: RwLock::new(ResolvePreference::default()),
+            defaults,
+            sampling,
+            connecting_events: AtomicBool::new(false),
+            connect_failed_events: AtomicBool::new(false),
+            next_group: AtomicU64::new(0),
+            network_changed,
+            #[cfg(feature = "network-monitor")]
+            network_monitor: std::sync::Mutex::new(None),
+            waker,
+        }
+    }
+
+    /// Sets the IP family preference used to choose the address to connect
+    /// when a `'host:port'` string resolves to several addresses.
+    /// It applies to the next calls to [`NetworkController::connect()`] and its variants.
+    /// With [`ResolvePreference::V4Only`] or [`ResolvePreference::V6Only`], connecting to a host
+    /// (or an ip) without addresses of that family returns an `AddrNotAvailable` error.
+    /// By default, [`ResolvePreference::System`] is used.
+    ///
+    /// Note that urls (e.g. `ws://domain:1234/path`) are resolved by its adapter,
+    /// so they are not affected by this preference.
+    pub fn set_resolve_preference(&self, preference: ResolvePreference) {
+        *self.resolve_preference.write().expect(OTHER_THREAD_ERR) = preference;
+    }
+
+    /// Returns the preference set by [`NetworkController::set_resolve_preference()`].
+    pub fn resolve_preference(&self) -> ResolvePreference {
+        *self.resolve_preference.read().expect(OTHER_THREAD_ERR)
+    }
+
+    /// Enables or disables the [`NetEvent::Connecting`] event for the next connections.
+    /// When enabled, each call to [`NetworkController::connect()`] or its variants generates
+    /// a `Connecting` event as soon as the connection is initiated,
+    /// before its [`NetEvent::Connected`] event.
+    /// It is disabled by default.
+    pub fn set_connecting_events(&self, enabled: bool) {
+        self.connecting_events.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Enables or disables the [`NetEvent::ConnectFailed`] event for the next connections.
+    /// When enabled, each connection created by [`NetworkController::connect()`] or its
+    /// variants that can not be established generates a `ConnectFailed` event
+    /// just after its [`NetEvent::Connected`] event with the `false` result.
+    /// It is disabled by default.
+    pub fn set_connect_failed_events(&self, enabled: bool) {
+        self.connect_failed_events.store(enabled, Ordering::Relaxed);
     }
 
     /// Creates a connection to the specified address.
     /// The endpoint, an identifier of the new connection, will be returned.
     /// This function will generate a [`NetEvent::Connected`] event with the result of the connection.
+    /// If the connection fails, it can be followed by a [`NetEvent::ConnectFailed`] event
+    /// with `addr`, see [`NetworkController::set_connect_failed_events()`].
     /// This call will **NOT** block to perform the connection.
     ///
     /// Note that this function can return an error in the case the internal socket
@@ -64,6 +237,9 @@ impl NetworkController {
     /// If you want to check if the connection has been established or not you have to read the
     /// boolean indicator in the [`NetEvent::Connected`] event.
     ///
+    /// The connection uses the default options of the network for the transport,
+    /// see [`TransportDefaults`].
+    ///
     /// Example
     /// ```
     /// use message_io::node::{self, NodeEvent};
@@ -101,8 +277,8 @@ impl NetworkController {
         &self,
         transport: Transport,
         addr: impl ToRemoteAddr,
-    ) -> io::Result<(Endpoint, SocketAddr)> {
-        self.connect_with(transport.into(), addr)
+    ) -> Result<(Endpoint, SocketAddr), NetworkError> {
+        self.connect_with(self.defaults.connect(transport), addr)
     }
 
     /// Creates a connection to the specified address with custom transport options for transports
@@ -152,20 +328,40 @@ impl NetworkController {
         &self,
         transport_connect: TransportConnect,
         addr: impl ToRemoteAddr,
-    ) -> io::Result<(Endpoint, SocketAddr)> {
-        let addr = addr.to_remote_addr().unwrap();
-        self.controllers[transport_connect.id() as usize].connect_with(transport_connect, addr).map(
-            |(endpoint, addr)| {
+    ) -> Result<(Endpoint, SocketAddr), NetworkError> {
+        self.connect_by(transport_connect, addr, false)
+    }
+
+    /// Creates the connection, `sync` if the caller waits for it to be established.
+    fn connect_by(
+        &self,
+        transport_connect: TransportConnect,
+        addr: impl ToRemoteAddr,
+        sync: bool,
+    ) -> Result<(Endpoint, SocketAddr), NetworkError> {
+        let target = addr.to_remote_addr().unwrap();
+        let addr = target.clone().resolve(self.resolve_preference())?;
+        let options = ConnectOptions {
+            announce: self.connecting_events.load(Ordering::Relaxed),
+            announce_failure: self.connect_failed_events.load(Ordering::Relaxed),
+            sync,
+        };
+        self.controllers[transport_connect.id() as usize]
+            .connect_with(transport_connect, addr, target, options)
+            .map(|(endpoint, addr)| {
                 log::trace!("Connect to {}", endpoint);
                 (endpoint, addr)
-            },
-        )
+            })
+            .map_err(NetworkError::from)
     }
 
     /// Creates a connection to the specified address.
     /// This function is similar to [`NetworkController::connect()`] but will block
     /// until for the connection is ready.
-    /// If the connection can not be established, a `ConnectionRefused` error will be returned.
+    /// If the connection can not be established, a [`NetworkError::ConnectionRefused`] error
+    /// will be returned, or a [`NetworkError::HandshakeFailed`] one if the peer rejected the
+    /// handshake of the protocol, as the response of a rejected *WebSocket* handshake,
+    /// see [`crate::adapters::ws::WsHandshakeRejected`].
     ///
     /// Note that the `Connect` event will be also generated.
     ///
@@ -178,7 +374,7 @@ impl NetworkController {
     /// Example
     /// ```
     /// use message_io::node::{self, NodeEvent};
-    /// use message_io::network::{Transport, NetEvent};
+    /// use message_io::network::{Transport, NetEvent, NetworkError};
     ///
     /// let (handler, listener) = node::split();
     /// handler.signals().send_with_timer((), std::time::Duration::from_secs(1));
@@ -189,9 +385,7 @@ impl NetworkController {
     ///         println!("Connected!");
     ///         handler.network().send(endpoint, &[42]);
     ///     }
-    ///     Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
-    ///         println!("Could not connect");
-    ///     }
+    ///     Err(NetworkError::ConnectionRefused(_)) => println!("Could not connect"),
     ///     Err(err) => println!("An OS error creating the socket"),
     /// }
     /// ```
@@ -199,15 +393,18 @@ impl NetworkController {
         &self,
         transport: Transport,
         addr: impl ToRemoteAddr,
-    ) -> io::Result<(Endpoint, SocketAddr)> {
-        self.connect_sync_with(transport.into(), addr)
+    ) -> Result<(Endpoint, SocketAddr), NetworkError> {
+        self.connect_sync_with(self.defaults.connect(transport), addr)
     }
 
     /// Creates a connection to the specified address with custom transport options for transports
     /// that support it.
     /// This function is similar to [`NetworkController::connect_with()`] but will block
     /// until for the connection is ready.
-    /// If the connection can not be established, a `ConnectionRefused` error will be returned.
+    /// If the connection can not be established, a [`NetworkError::ConnectionRefused`] error
+    /// will be returned, or a [`NetworkError::HandshakeFailed`] one if the peer rejected the
+    /// handshake of the protocol, as the response of a rejected *WebSocket* handshake,
+    /// see [`crate::adapters::ws::WsHandshakeRejected`].
     ///
     /// Note that the `Connect` event will be also generated.
     ///
@@ -220,7 +417,7 @@ impl NetworkController {
     /// Example
     /// ```
     /// use message_io::node::{self, NodeEvent};
-    /// use message_io::network::{TransportConnect, NetEvent};
+    /// use message_io::network::{TransportConnect, NetEvent, NetworkError};
     /// use message_io::adapters::udp::{UdpConnectConfig};
     ///
     /// let (handler, listener) = node::split();
@@ -233,9 +430,7 @@ impl NetworkController {
     ///         println!("Connected!");
     ///         handler.network().send(endpoint, &[42]);
     ///     }
-    ///     Err(err) if err.kind() == std::io::ErrorKind::ConnectionRefused => {
-    ///         println!("Could not connect");
-    ///     }
+    ///     Err(NetworkError::ConnectionRefused(_)) => println!("Could not connect"),
     ///     Err(err) => println!("An OS error creating the socket"),
     /// }
     /// ```
@@ -243,18 +438,20 @@ impl NetworkController {
         &self,
         transport_connect: TransportConnect,
         addr: impl ToRemoteAddr,
-    ) -> io::Result<(Endpoint, SocketAddr)> {
-        let (endpoint, addr) = self.connect_with(transport_connect, addr)?;
+    ) -> Result<(Endpoint, SocketAddr), NetworkError> {
+        let (endpoint, addr) = self.connect_by(transport_connect, addr, true)?;
+        let resource_id = endpoint.resource_id();
         loop {
             std::thread::sleep(Duration::from_millis(1));
-            match self.is_ready(endpoint.resource_id()) {
+            match self.is_ready(resource_id) {
                 Some(true) => return Ok((endpoint, addr)),
                 Some(false) => continue,
                 None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::ConnectionRefused,
-                        "Connection refused",
-                    ))
+                    let controller = &self.controllers[resource_id.adapter_id() as usize];
+                    let err = controller.take_connect_error(resource_id).unwrap_or_else(|| {
+                        io::Error::new(io::ErrorKind::ConnectionRefused, "Connection refused")
+                    });
+                    return Err(err.into())
                 }
             }
         }
@@ -263,36 +460,131 @@ impl NetworkController {
     /// Listen messages from specified transport.
     /// The given address will be used as interface and listening port.
     /// If the port can be opened, a [ResourceId] identifying the listener is returned
-    /// along with the local address, or an error if not.
+    /// along with the local address, or an error if not,
+    /// that is [`NetworkError::AddrInUse`] if other socket is using the address.
     /// The address is returned despite you passed as parameter because
     /// when a `0` port is specified, the OS will give choose the value.
+    ///
+    /// The listener uses the default options of the network for the transport,
+    /// see [`TransportDefaults`].
     pub fn listen(
         &self,
         transport: Transport,
         addr: impl ToSocketAddrs,
-    ) -> io::Result<(ResourceId, SocketAddr)> {
-        self.listen_with(transport.into(), addr)
+    ) -> Result<(ResourceId, SocketAddr), NetworkError> {
+        self.listen_with(self.defaults.listen(transport), addr)
     }
 
     /// Listen messages from specified transport with custom transport options for transports that
     /// support it.
     /// The given address will be used as interface and listening port.
     /// If the port can be opened, a [ResourceId] identifying the listener is returned
-    /// along with the local address, or an error if not.
+    /// along with the local address, or an error if not,
+    /// that is [`NetworkError::AddrInUse`] if other socket is using the address.
     /// The address is returned despite you passed as parameter because
     /// when a `0` port is specified, the OS will give choose the value.
     pub fn listen_with(
         &self,
         transport_listen: TransportListen,
         addr: impl ToSocketAddrs,
-    ) -> io::Result<(ResourceId, SocketAddr)> {
+    ) -> Result<(ResourceId, SocketAddr), NetworkError> {
         let addr = addr.to_socket_addrs().unwrap().next().unwrap();
-        self.controllers[transport_listen.id() as usize].listen_with(transport_listen, addr).map(
-            |(resource_id, addr)| {
+        self.listen_in_group(transport_listen, addr, None)
+    }
+
+    /// Listen messages from specified transport at several addresses,
+    /// as an IPv4 and an IPv6 address, or the addresses of several interfaces.
+    /// The listeners work as a group: the id of the first listener created represents the group,
+    /// so the connections accepted by any of them generate the [`NetEvent::Accepted`] event
+    /// with that id, and [`NetworkController::listener_of()`] returns it.
+    /// Removing it or setting its accept rate is applied to the whole group.
+    ///
+    /// A result is returned for each address, in the same order,
+    /// so an address that can not be listened does not prevent listening the rest.
+    /// If all the addresses are required, use [`NetworkController::listen_multi_all()`].
+    ///
+    /// The listeners use the default options of the network for the transport,
+    /// see [`TransportDefaults`].
+    ///
+    /// Note that the messages of non connection-oriented transports as UDP are received
+    /// with the id of the listener that received them, since it is the one used to answer.
+    pub fn listen_multi(
+        &self,
+        transport: Transport,
+        addrs: &[SocketAddr],
+    ) -> Vec<Result<(ResourceId, SocketAddr), NetworkError>> {
+        self.listen_multi_with(self.defaults.listen(transport), addrs)
+    }
+
+    /// Similar to [`NetworkController::listen_multi()`] with custom transport options
+    /// for transports that support it, that are used by all the listeners.
+    pub fn listen_multi_with(
+        &self,
+        transport_listen: TransportListen,
+        addrs: &[SocketAddr],
+    ) -> Vec<Result<(ResourceId, SocketAddr), NetworkError>> {
+        let mut group = None;
+        addrs
+            .iter()
+            .map(|&addr| {
+                let result = self.listen_in_group(transport_listen.clone(), addr, group);
+                if let Ok((resource_id, _)) = result {
+                    group.get_or_insert(resource_id);
+                }
+                result
+            })
+            .collect()
+    }
+
+    /// Similar to [`NetworkController::listen_multi()`] but all the addresses must be listened:
+    /// if any of them fails, the listeners already created are removed and its error
+    /// is returned. Otherwise, the ids and addresses of the listeners are returned
+    /// in the order of `addrs`.
+    pub fn listen_multi_all(
+        &self,
+        transport: Transport,
+        addrs: &[SocketAddr],
+    ) -> Result<Vec<(ResourceId, SocketAddr)>, NetworkError> {
+        self.listen_multi_all_with(self.defaults.listen(transport), addrs)
+    }
+
+    /// Similar to [`NetworkController::listen_multi_all()`] with custom transport options
+    /// for transports that support it, that are used by all the listeners.
+    pub fn listen_multi_all_with(
+        &self,
+        transport_listen: TransportListen,
+        addrs: &[SocketAddr],
+    ) -> Result<Vec<(ResourceId, SocketAddr)>, NetworkError> {
+        let mut listeners: Vec<(ResourceId, SocketAddr)> = Vec::with_capacity(addrs.len());
+        for &addr in addrs {
+            let group = listeners.first().map(|&(group_id, _)| group_id);
+            match self.listen_in_group(transport_listen.clone(), addr, group) {
+                Ok(listener) => listeners.push(listener),
+                Err(err) => {
+                    // Removing the group removes all its listeners.
+                    if let Some(group_id) = group {
+                        self.remove(group_id);
+                    }
+                    return Err(err)
+                }
+            }
+        }
+        Ok(listeners)
+    }
+
+    fn listen_in_group(
+        &self,
+        transport_listen: TransportListen,
+        addr: SocketAddr,
+        group: Option<ResourceId>,
+    ) -> Result<(ResourceId, SocketAddr), NetworkError> {
+        self.controllers[transport_listen.id() as usize]
+            .listen_with(transport_listen, addr, group)
+            .map(|(resource_id, addr)| {
                 log::trace!("Listening at {} by {}", addr, resource_id);
                 (resource_id, addr)
-            },
-        )
+            })
+            .map_err(NetworkError::from)
     }
 
     /// Send the data message thought the connection represented by the given endpoint.
@@ -310,6 +602,82 @@ impl NetworkController {
         status
     }
 
+    /// Similar to [`NetworkController::send()`] but taking the ownership of the data.
+    /// Any buffer convertible into a `Vec<u8>` can be used, as a
+    /// [`Bytes`](https://docs.rs/bytes/latest/bytes/struct.Bytes.html) that is not shared.
+    ///
+    /// Transports that queue the data before writing it, as a [`Transport::FramedTcp`]
+    /// configured with a write chunk size, move the buffer into the queue instead of copying it.
+    /// In that case, the size header of the frame is queued apart and written before the data.
+    /// The rest of transports write the data on the call, as [`NetworkController::send()`] does.
+    pub fn send_owned(&self, endpoint: Endpoint, data: impl Into<Vec<u8>>) -> SendStatus {
+        let data = data.into();
+        log::trace!("Sending {} owned bytes to {}...", data.len(), endpoint);
+        let status = self.controllers[endpoint.resource_id().adapter_id() as usize]
+            .send_owned(endpoint, data);
+        log::trace!("Send status: {:?}", status);
+        status
+    }
+
+    /// Similar to [`NetworkController::send_owned()`] but returning a [`SendTicket`]
+    /// to know when the message is completely written.
+    /// The status of the send is given by [`SendTicket::send_status()`].
+    ///
+    /// The ticket becomes [`TicketStatus::Written`] when the last byte of the message is
+    /// accepted by the OS: for transports that write the data on the call, as [`Transport::Udp`]
+    /// or [`Transport::Tcp`], once the call returns.
+    /// For transports that queue the data, as a [`Transport::FramedTcp`] configured with
+    /// a write chunk size, once the network thread writes it, that can take a while
+    /// if the peer reads slowly.
+    /// It becomes [`TicketStatus::Failed`] if the message was not sent, if it was dropped from
+    /// the queue by its limit, or if the connection is removed before writing it.
+    pub fn send_tracked(&self, endpoint: Endpoint, data: impl Into<Vec<u8>>) -> SendTicket {
+        let data = data.into();
+        log::trace!("Sending {} tracked bytes to {}...", data.len(), endpoint);
+        let (ticket, completion) = SendTicket::new();
+        let status = self.controllers[endpoint.resource_id().adapter_id() as usize]
+            .send_tracked(endpoint, data, completion);
+        log::trace!("Send status: {:?}", status);
+        ticket.with_send_status(status)
+    }
+
+    /// Sends a WebSocket ping frame with `payload` through the connection of the endpoint.
+    /// The peer answers it with a pong frame with the same payload, that can be received
+    /// as a [`NetEvent::Control`] enabling the control frames in the WebSocket configuration.
+    /// See [`crate::adapters::ws::WsConnectConfig::with_control_frames()`].
+    ///
+    /// If the payload exceeds [`crate::adapters::ws::MAX_CONTROL_PAYLOAD_LEN`],
+    /// [`SendStatus::MaxPacketSizeExceeded`] is returned.
+    /// If the endpoint is not a WebSocket endpoint, [`SendStatus::ResourceNotFound`] is returned.
+    #[cfg(feature = "websocket")]
+    pub fn send_ws_ping(&self, endpoint: Endpoint, payload: &[u8]) -> SendStatus {
+        let adapter_id = endpoint.resource_id().adapter_id();
+        if adapter_id != Transport::Ws.id() {
+            return SendStatus::ResourceNotFound
+        }
+        log::trace!("Sending ping of {} bytes to {}...", payload.len(), endpoint);
+        let status = self.controllers[adapter_id as usize].send_ping(endpoint, payload);
+        log::trace!("Send status: {:?}", status);
+        status
+    }
+
+    /// Returns the resume token presented in the handshake by the client of a WebSocket
+    /// connection accepted by a listener, available since its [`NetEvent::Accepted`] event.
+    /// It allows to restore the session of a client that reconnects,
+    /// distinguishing it from a new client.
+    /// See [`crate::adapters::ws::WsConnectConfig::with_resume_token()`].
+    ///
+    /// It returns `None` if the client presented no token, the resource has been removed
+    /// or disconnected, or the endpoint is not a WebSocket endpoint.
+    #[cfg(feature = "websocket")]
+    pub fn ws_resume_token(&self, endpoint: Endpoint) -> Option<String> {
+        let adapter_id = endpoint.resource_id().adapter_id();
+        if adapter_id != Transport::Ws.id() {
+            return None
+        }
+        self.controllers[adapter_id as usize].resume_token(endpoint.resource_id())
+    }
+
     /// Remove a network resource.
     /// Returns `false` if the resource id doesn't exists.
     /// This is used to remove resources as connection or listeners.
@@ -329,6 +697,177 @@ impl NetworkController {
         value
     }
 
+    /// Removes a connection gracefully.
+    /// New sends by the resource are not allowed, returning [`SendStatus::ResourceNotAvailable`].
+    /// The data queued but not yet written by the resource (e.g. with a
+    /// [`Transport::FramedTcp`] configured with a write chunk size) is written before closing,
+    /// waiting at most `timeout`.
+    /// Then, the connection is closed as its protocol does (a *FIN* in TCP,
+    /// a close frame in WebSocket), the resource is removed and a [`NetEvent::Disconnected`]
+    /// is generated.
+    ///
+    /// Returns `false` if the resource id doesn't exists.
+    /// Listeners have not connection to close, they are removed as [`NetworkController::remove()`]
+    /// does.
+    pub fn remove_graceful(&self, resource_id: ResourceId, timeout: Duration) -> bool {
+        log::trace!("Remove gracefully {}", resource_id);
+        let value = self.controllers[resource_id.adapter_id() as usize]
+            .remove_graceful(resource_id, timeout);
+        log::trace!("Removing gracefully: {}", value);
+        value
+    }
+
+    /// Rejects the connection of the `endpoint`, intended to be called while handling its
+    /// [`NetEvent::Accepted`] or [`NetEvent::Connected`] event, for example, when the peer
+    /// is not authorized.
+    /// The connection is closed as its protocol does (a *FIN* in TCP, a close frame in
+    /// WebSocket) and the resource is removed, without waiting for its queued data.
+    /// Unlike a [`NetworkController::remove()`] performed later, no event of the endpoint
+    /// is generated after the one being handled, even if the peer had sent data by then.
+    /// A [`NetEvent::Disconnected`] is not generated either.
+    ///
+    /// Returns `false` if the connection doesn't exists. Listeners are not connections,
+    /// `false` is returned for them without removing them.
+    pub fn reject(&self, endpoint: Endpoint) -> bool {
+        log::trace!("Reject {}", endpoint);
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].reject(resource_id)
+    }
+
+    /// Removes all the resources in a deterministic order,
+    /// in order to shut down the network before stopping the node.
+    ///
+    /// 1. The listeners are removed first, so no more connections are accepted meanwhile.
+    /// 2. Then, the connections (including the accepted ones) are removed gracefully,
+    ///    as [`NetworkController::remove_graceful()`] does, waiting at most `timeout`
+    ///    for their queued data.
+    ///
+    /// In both steps, the transports are processed in the order of [`Transport`],
+    /// and the resources of each transport in the order they were created.
+    /// The [`NetEvent::Disconnected`] of the connections are generated in that order
+    /// as they are closed: the connections with data still queued are closed after the rest.
+    ///
+    /// Returns the number of connections removed, so the caller knows how many
+    /// [`NetEvent::Disconnected`] to wait for.
+    /// [`crate::node::NodeHandler::stop_graceful()`] waits for them before stopping the node.
+    pub fn remove_all(&self, timeout: Duration) -> usize {
+        log::trace!("Remove all");
+        let controllers: Vec<_> =
+            Transport::iter().map(|transport| &self.controllers[transport.id() as usize]).collect();
+        for controller in &controllers {
+            for listener_id in controller.resource_ids(ResourceType::Local) {
+                controller.remove(listener_id);
+            }
+        }
+
+        let mut removed = 0;
+        for controller in &controllers {
+            for resource_id in controller.resource_ids(ResourceType::Remote) {
+                if controller.remove_graceful(resource_id, timeout) {
+                    removed += 1;
+                }
+            }
+        }
+        log::trace!("Removing {} connections", removed);
+        removed
+    }
+
+    /// Returns the endpoints of the connections of `transport`, both the connections created by
+    /// [`NetworkController::connect()`] and the accepted ones, in the order they were created.
+    /// The connections not established yet are included.
+    ///
+    /// Non connection-oriented transports, as UDP, only have the connections created by
+    /// [`NetworkController::connect()`].
+    pub fn connections_by_transport(&self, transport: Transport) -> Vec<Endpoint> {
+        self.controllers[transport.id() as usize].connections()
+    }
+
+    /// Closes gracefully all the connections of `transport`
+    /// (see [`NetworkController::connections_by_transport()`]),
+    /// as [`NetworkController::remove_graceful()`] does, waiting at most `timeout`
+    /// for their queued data.
+    /// The listeners are kept, so the transport can accept new connections,
+    /// and the resources of other transports are not affected.
+    ///
+    /// Returns the number of connections removed, so the caller knows how many
+    /// [`NetEvent::Disconnected`] to wait for.
+    pub fn close_all(&self, transport: Transport, timeout: Duration) -> usize {
+        log::trace!("Close all {:?} connections", transport);
+        let controller = &self.controllers[transport.id() as usize];
+        let removed = controller
+            .connections()
+            .into_iter()
+            .filter(|endpoint| controller.remove_graceful(endpoint.resource_id(), timeout))
+            .count();
+        log::trace!("Closing {} connections", removed);
+        removed
+    }
+
+    /// Creates a new group of endpoints, without members.
+    /// The endpoints are added by [`NetworkController::join_group()`],
+    /// and the group is used to send to all of them by [`NetworkController::send_group()`].
+    pub fn create_group(&self) -> GroupId {
+        GroupId::new(self.next_group.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Adds the connection of `endpoint` to `group`.
+    /// An endpoint can be a member of several groups.
+    /// It leaves all its groups when it is removed or disconnected,
+    /// so there is no need to call [`NetworkController::leave_group()`] on
+    /// [`NetEvent::Disconnected`].
+    ///
+    /// Returns `false` if the connection doesn't exists.
+    /// Only connections can be members, as the endpoints of the listeners of non
+    /// connection-oriented transports (e.g. UDP) share the resource of the listener,
+    /// `false` is returned for them.
+    pub fn join_group(&self, group: GroupId, endpoint: Endpoint) -> bool {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].join_group(resource_id, group)
+    }
+
+    /// Removes the connection of `endpoint` from `group`.
+    /// Returns `false` if the endpoint was not a member of the group.
+    pub fn leave_group(&self, group: GroupId, endpoint: Endpoint) -> bool {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].leave_group(resource_id, group)
+    }
+
+    /// Returns the current members of `group`, in the order of [`Transport`],
+    /// and the members of each transport in the order they were created.
+    pub fn group_members(&self, group: GroupId) -> Vec<Endpoint> {
+        Transport::iter()
+            .flat_map(|transport| self.controllers[transport.id() as usize].group_members(group))
+            .collect()
+    }
+
+    /// Sends `data` to all the current members of `group`
+    /// (see [`NetworkController::group_members()`]),
+    /// as [`NetworkController::send()`] does for each one.
+    /// Returns the status of the send of each member.
+    pub fn send_group(&self, group: GroupId, data: &[u8]) -> Vec<(Endpoint, SendStatus)> {
+        self.group_members(group)
+            .into_iter()
+            .map(|endpoint| (endpoint, self.send(endpoint, data)))
+            .collect()
+    }
+
+    /// Limits the rate at which the listener accepts new connections,
+    /// in order to smooth bursts of connections.
+    /// Beyond the rate, the listener stops accepting until the rate allows it again,
+    /// the connections are not dropped, they wait in the OS listener backlog
+    /// (that could be full by a too long wait).
+    /// `None` removes the limit.
+    ///
+    /// The paused listeners are resumed by the [`NetworkProcessor`], so the pace is as precise
+    /// as often the processor polls (each few milliseconds when it is used by a
+    /// [`crate::node::NodeListener`]).
+    ///
+    /// Returns `false` if the resource id is not an existing listener of a connection oriented
+    /// transport.
+    pub fn set_accept_rate(&self, listener_id: ResourceId, rate: Option<AcceptRate>) -> bool {
+        self.controllers[listener_id.adapter_id() as usize].set_accept_rate(listener_id, rate)
+    }
+
     /// Check a resource specified by `resource_id` is ready.
     /// If the status is `true` means that the resource is ready to use.
     /// In connection oriented transports, it implies the resource is connected.
@@ -338,6 +877,164 @@ impl NetworkController {
     pub fn is_ready(&self, resource_id: ResourceId) -> Option<bool> {
         self.controllers[resource_id.adapter_id() as usize].is_ready(resource_id)
     }
+
+    /// Returns the id of the listener from which the `endpoint` was accepted.
+    /// This is the same id offered by the [`NetEvent::Accepted`] event of that endpoint.
+    /// Endpoints created by [`NetworkController::connect()`] have no listener, so `None` is
+    /// returned. It is also returned if the resource has been removed or disconnected.
+    ///
+    /// For endpoints of non connection-oriented transports as UDP, the resource of the endpoint
+    /// is already the listener, so its own id is returned.
+    /// For listeners created by [`NetworkController::listen_multi()`], the id of the group
+    /// is returned.
+    pub fn listener_of(&self, endpoint: Endpoint) -> Option<ResourceId> {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].listener_of(resource_id)
+    }
+
+    /// Returns the time when the connection of the `endpoint` was established,
+    /// that is, when its [`NetEvent::Connected`] or [`NetEvent::Accepted`] was generated.
+    /// It returns `None` if the connection is not established yet or the resource
+    /// has been removed or disconnected.
+    ///
+    /// The endpoints of non connection-oriented listeners, as UDP listeners, have no
+    /// connection, so `None` is returned for them.
+    pub fn connected_at(&self, endpoint: Endpoint) -> Option<Instant> {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].connected_at(resource_id)
+    }
+
+    /// Returns the last time that data was received or successfully sent by the connection
+    /// of the `endpoint`. Until then, it is the time when the connection was established.
+    /// It returns `None` in the same cases as [`NetworkController::connected_at()`].
+    pub fn last_activity(&self, endpoint: Endpoint) -> Option<Instant> {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].last_activity(resource_id)
+    }
+
+    /// Returns the state of the framing of the data received by the connection of the
+    /// `endpoint`, useful to diagnose peers that send incomplete or oversized frames.
+    /// See [`FramingStats`].
+    ///
+    /// It returns `None` if the transport of the endpoint does not assemble frames,
+    /// or the resource has been removed or disconnected.
+    pub fn framing_stats(&self, endpoint: Endpoint) -> Option<FramingStats> {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].framing_stats(resource_id)
+    }
+
+    /// Sets a `deadline` to receive the next message by the connection of the `endpoint`.
+    /// If no message is received before the deadline, the connection is closed,
+    /// generating a [`NetEvent::Error`] with [`std::io::ErrorKind::TimedOut`]
+    /// followed by a [`NetEvent::Disconnected`].
+    /// Once a message is received, the deadline is met and removed.
+    /// `None` removes the current deadline, if any.
+    ///
+    /// It returns `false` if the connection is not established or the resource
+    /// has been removed or disconnected.
+    /// The endpoints of non connection-oriented listeners, as UDP listeners, have no
+    /// connection, so `false` is returned for them.
+    pub fn set_read_deadline(&self, endpoint: Endpoint, deadline: Option<Instant>) -> bool {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].set_read_deadline(resource_id, deadline)
+    }
+
+    /// Sets a `deadline` to write the data sent by the connection of the `endpoint`
+    /// that is still queued, because the peer does not read it fast enough.
+    /// If the queued data is not written before the deadline, the connection is closed
+    /// as it happens with [`NetworkController::set_read_deadline()`].
+    /// Once there is no queued data, the deadline is met and removed.
+    /// `None` removes the current deadline, if any.
+    ///
+    /// It returns `false` in the same cases as [`NetworkController::set_read_deadline()`].
+    pub fn set_write_deadline(&self, endpoint: Endpoint, deadline: Option<Instant>) -> bool {
+        let resource_id = endpoint.resource_id();
+        let controller = &self.controllers[resource_id.adapter_id() as usize];
+        controller.set_write_deadline(resource_id, deadline)
+    }
+
+    /// Sets the `watermarks` of the data queued by the connection of the `endpoint`,
+    /// to pause and resume the source of the data sent without checking the queue.
+    /// A [`NetEvent::Watermark`] is generated with [`Watermark::High`] when the queued data
+    /// reaches the high watermark, and with [`Watermark::Low`] when it drops to the low one.
+    /// `None` removes the current watermarks, if any.
+    ///
+    /// The events are generated by the processor as the rest of events,
+    /// not inside the `send()` call that crossed the watermark,
+    /// so no lock of the network is held and it can be used from the event.
+    ///
+    /// It returns `false` if the connection is not established, the resource has been removed
+    /// or disconnected, or the connection does not queue the data, as the *FramedTcp*
+    /// connections without a queue, see
+    /// [`crate::adapters::framed_tcp::FramedTcpConnectConfig::with_queue_capacity()`].
+    pub fn set_watermarks(&self, endpoint: Endpoint, watermarks: Option<Watermarks>) -> bool {
+        let resource_id = endpoint.resource_id();
+        self.controllers[resource_id.adapter_id() as usize].set_watermarks(resource_id, watermarks)
+    }
+
+    /// Enables the generation of a [`NetEvent::Throughput`] for each established connection
+    /// every `interval`, with the bytes of the messages that the connection has sent
+    /// and received since the previous sample.
+    /// The samples of all the connections are generated together, in the order the connections
+    /// were created. `None` disables the sampling, that is disabled by default.
+    ///
+    /// The bytes are always counted by the connections, so the sampling only reads
+    /// and resets the counters, it does not add cost to the messages.
+    /// The samples are generated by the thread that processes the network events,
+    /// so they are delayed while that thread is busy processing other events.
+    /// Since they are events, [`NetworkProcessor::process_poll_events_until_timeout()`]
+    /// does not finish while the sampling is enabled with a shorter interval than its timeout.
+    ///
+    /// The data exchanged by a connection in the interval when it is closed is not sampled.
+    /// The endpoints of non connection-oriented listeners, as UDP listeners,
+    /// have no connection, so their data is not sampled either.
+    ///
+    /// It panics if `interval` is zero.
+    pub fn set_throughput_sampling(&self, interval: Option<Duration>) {
+        assert!(interval != Some(Duration::ZERO), "The sampling interval must be positive");
+        *self.sampling.write().expect(OTHER_THREAD_ERR) = interval;
+        self.waker.wake(); // The processor could be waiting for events without a timeout.
+    }
+
+    /// Notifies that the network of the host has changed,
+    /// generating a [`NetEvent::NetworkChanged`] event.
+    /// The notifications of the OS are given by [`NetworkController::monitor_network_changes()`],
+    /// this function allows the application to notify the changes known by other means,
+    /// as in the platforms that the monitor does not support.
+    /// Several notifications given before the event is processed generate only one event.
+    pub fn notify_network_change(&self) {
+        Self::notify_change(&self.network_changed, &self.waker);
+    }
+
+    fn notify_change(network_changed: &AtomicBool, waker: &PollWaker) {
+        network_changed.store(true, Ordering::Relaxed);
+        waker.wake();
+    }
+
+    /// Starts monitoring the interfaces and addresses of the host,
+    /// generating a [`NetEvent::NetworkChanged`] event when they change,
+    /// as when switching to other Wi-Fi network.
+    /// The connections are usually broken silently by these changes,
+    /// so the event allows to re-establish them without waiting for their timeouts.
+    /// The monitor runs in its own thread until the controller is dropped,
+    /// calling this function again has no effect.
+    ///
+    /// Currently the changes are only monitored in Linux, by a netlink socket.
+    /// In the rest of platforms this function does nothing,
+    /// see [`NetworkController::notify_network_change()`].
+    #[cfg(feature = "network-monitor")]
+    pub fn monitor_network_changes(&self) -> io::Result<()> {
+        let mut network_monitor = self.network_monitor.lock().expect(OTHER_THREAD_ERR);
+        if network_monitor.is_none() {
+            let network_changed = self.network_changed.clone();
+            let waker = self.waker.clone();
+            *network_monitor = Some(NetworkMonitor::start(move || {
+                log::trace!("Network change notified by the OS");
+                Self::notify_change(&network_changed, &waker);
+            })?);
+        }
+        Ok(())
+    }
 }
 
 /// Instance in charge of process input network events.
@@ -345,11 +1042,20 @@ impl NetworkController {
 pub struct NetworkProcessor {
     poll: Poll,
     processors: EventProcessorList,
+    deferred: bool,
+    sampling: Arc<RwLock<Option<Duration>>>,
+    last_sample: Option<Instant>,
+    network_changed: Arc<AtomicBool>,
 }
 
 impl NetworkProcessor {
-    fn new(poll: Poll, processors: EventProcessorList) -> Self {
-        Self { poll, processors }
+    fn new(
+        poll: Poll,
+        processors: EventProcessorList,
+        sampling: Arc<RwLock<Option<Duration>>>,
+        network_changed: Arc<AtomicBool>,
+    ) -> Self {
+        Self { poll, processors, deferred: false, sampling, last_sample: None, network_changed }
     }
 
     /// Process the next poll event.
@@ -358,26 +1064,141 @@ impl NetworkProcessor {
     /// Note that there is no 1-1 relation between an internal poll event and a [`NetEvent`].
     /// You need to assume that process an internal poll event could call 0 or N times to
     /// the callback with diferents `NetEvent`s.
+    ///
+    /// It panics if the network was created by [`split_with_registry()`],
+    /// use [`NetworkProcessor::process_ready()`] instead.
     pub fn process_poll_event(
         &mut self,
         timeout: Option<Duration>,
         mut event_callback: impl FnMut(NetEvent<'_>),
     ) {
-        let processors = &mut self.processors;
-        self.poll.process_event(timeout, |poll_event| {
-            match poll_event {
-                PollEvent::Network(resource_id, interest) => {
-                    let processor = &processors[resource_id.adapter_id() as usize];
-                    processor.process(resource_id, interest, &mut |net_event| {
-                        log::trace!("Processed {:?}", net_event);
-                        event_callback(net_event);
-                    });
-                }
+        let timeout = match (timeout, self.poll_timeout()) {
+            (Some(timeout), Some(poll_timeout)) => Some(timeout.min(poll_timeout)),
+            (timeout, poll_timeout) => timeout.or(poll_timeout),
+        };
+
+        thread::process_network(|| {
+            let processors = &self.processors;
+            self.poll.process_event(timeout, |poll_event| {
+                Self::process_network_event(processors, poll_event, &mut event_callback)
+            });
+            self.process_pending_work(event_callback);
+        })
+    }
+
+    /// Processes the events of an external poll, for a network created by
+    /// [`split_with_registry()`].
+    /// The events of the sources that do not belong to the network are ignored,
+    /// so all the events of the poll can be given.
+    /// It must be called after each poll, even without events,
+    /// to process the work of the network that is not triggered by events.
+    /// See [`NetworkProcessor::poll_timeout()`].
+    pub fn process_ready(
+        &mut self,
+        events: &mio::Events,
+        mut event_callback: impl FnMut(NetEvent<'_>),
+    ) {
+        thread::process_network(|| {
+            let processors = &self.processors;
+            self.poll.process_ready(events, |poll_event| {
+                Self::process_network_event(processors, poll_event, &mut event_callback)
+            });
+            self.process_pending_work(event_callback);
+        })
+    }
+
+    /// Maximum time the poll can wait for events before processing the network,
+    /// because it has work to do that is not triggered by events,
+    /// as the pending writes, the throughput samples or the deadlines of the connections.
+    /// `None` means that the poll can wait indefinitely.
+    /// It is only needed by the external polls of [`split_with_registry()`],
+    /// [`NetworkProcessor::process_poll_event()`] already takes it into account.
+    pub fn poll_timeout(&mut self) -> Option<Duration> {
+        // If there is deferred work, the poll is only used to check the current events.
+        // Otherwise, it waits at most until the next throughput sample or deadline.
+        if self.deferred {
+            return Some(Duration::ZERO)
+        }
+        let next_deadline = self.processors.iter().filter_map(|p| p.next_deadline()).min();
+        match (self.next_sample(), next_deadline) {
+            (Some(next_sample), Some(next_deadline)) => Some(next_sample.min(next_deadline)),
+            (next_sample, next_deadline) => next_sample.or(next_deadline),
+        }
+        .map(|next_time| next_time.saturating_duration_since(Instant::now()))
+    }
 
-                #[allow(dead_code)] //TODO: remove it with native event support
-                PollEvent::Waker => todo!(),
+    fn process_network_event(
+        processors: &EventProcessorList,
+        poll_event: PollEvent,
+        event_callback: &mut impl FnMut(NetEvent<'_>),
+    ) {
+        match poll_event {
+            PollEvent::Network(resource_id, interest) => {
+                let processor = &processors[resource_id.adapter_id() as usize];
+                processor.process(resource_id, interest, &mut |net_event| {
+                    log::trace!("Processed {:?}", net_event);
+                    event_callback(net_event);
+                });
             }
-        });
+
+            // The poll was woken up to process the deferred work.
+            PollEvent::Waker => (),
+        }
+    }
+
+    /// Processes the network changes, the deferred work and the throughput samples.
+    fn process_pending_work(&mut self, mut event_callback: impl FnMut(NetEvent<'_>)) {
+        if self.network_changed.swap(false, Ordering::Relaxed) {
+            log::trace!("Processed {:?}", NetEvent::NetworkChanged);
+            event_callback(NetEvent::NetworkChanged);
+        }
+
+        let next_sample = self.next_sample();
+        let processors = &self.processors;
+        let mut deferred = false;
+        for processor in processors.iter() {
+            deferred |= processor.process_deferred(&mut |net_event| {
+                log::trace!("Processed deferred {:?}", net_event);
+                event_callback(net_event);
+            });
+        }
+        self.deferred = deferred;
+
+        let now = Instant::now();
+        if matches!(next_sample, Some(next_sample) if now >= next_sample) {
+            let interval = now.saturating_duration_since(self.last_sample.unwrap_or(now));
+            for processor in processors.iter() {
+                processor.sample_throughput(interval, &mut |net_event| {
+                    log::trace!("Sampled {:?}", net_event);
+                    event_callback(net_event);
+                });
+            }
+            self.last_sample = Some(now);
+        }
+    }
+
+    /// Returns the time of the next throughput sample, if the sampling is enabled.
+    fn next_sample(&mut self) -> Option<Instant> {
+        let interval = *self.sampling.read().expect(OTHER_THREAD_ERR);
+        match interval {
+            Some(interval) => {
+                let last_sample = match self.last_sample {
+                    Some(last_sample) => last_sample,
+                    None => {
+                        // Just enabled, the counters start from now.
+                        for processor in self.processors.iter() {
+                            processor.sample_throughput(Duration::ZERO, &mut |_| ());
+                        }
+                        *self.last_sample.insert(Instant::now())
+                    }
+                };
+                Some(last_sample + interval)
+            }
+            None => {
+                self.last_sample = None;
+                None
+            }
+        }
     }
 
     /// Process poll events until there is no more events during a `timeout` duration.
@@ -402,6 +1223,8 @@ mod tests {
     use super::*;
     use std::time::{Duration};
     use crate::util::thread::{NamespacedThread};
+    #[cfg(feature = "tcp")]
+    use crate::adapters::framed_tcp::{DropPolicy};
 
     use test_case::test_case;
 
@@ -413,6 +1236,7 @@ mod tests {
     #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
     #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
     #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
     fn successful_connection(transport: Transport) {
         let (controller, mut processor) = self::split();
         let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
@@ -436,77 +1260,1701 @@ mod tests {
         assert_eq!(was_connected, 1);
     }
 
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn connecting_before_connected() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+        controller.set_connecting_events(true);
+        let (endpoint, _) = controller.connect(Transport::Tcp, addr).unwrap();
+        controller.set_connecting_events(false);
+        controller.connect(Transport::Tcp, addr).unwrap(); // Without Connecting event
+
+        let mut events = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connecting(net_endpoint, target) => {
+                assert_eq!(*target, RemoteAddr::Socket(addr));
+                events.push(("connecting", net_endpoint));
+            }
+            NetEvent::Connected(net_endpoint, status) => {
+                assert!(status);
+                events.push(("connected", net_endpoint));
+            }
+            NetEvent::Accepted(..) => (),
+            _ => unreachable!(),
+        });
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], ("connecting", endpoint));
+        assert!(events[1..].contains(&("connected", endpoint)));
+    }
+
     #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
     #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
     #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
-    fn successful_connection_sync(transport: Transport) {
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn listener_of_accepted(transport: Transport) {
         let (controller, mut processor) = self::split();
-        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        let (listener_id_1, addr_1) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        let (listener_id_2, addr_2) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        let (_, local_addr_1) = controller.connect(transport, addr_1).unwrap();
+        let (_, local_addr_2) = controller.connect(transport, addr_2).unwrap();
 
-        let mut thread = NamespacedThread::spawn("test", move || {
-            let (endpoint, _) = controller.connect_sync(transport, addr).unwrap();
-            assert!(controller.is_ready(endpoint.resource_id()).unwrap());
+        let mut accepted = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                assert_eq!(controller.listener_of(endpoint), None);
+            }
+            NetEvent::Accepted(endpoint, listener_id) => {
+                assert_eq!(controller.listener_of(endpoint), Some(listener_id));
+                if endpoint.addr() == local_addr_1 {
+                    assert_eq!(listener_id, listener_id_1);
+                }
+                else {
+                    assert_eq!(endpoint.addr(), local_addr_2);
+                    assert_eq!(listener_id, listener_id_2);
+                }
+                accepted += 1;
+            }
+            _ => unreachable!(),
         });
-
-        processor.process_poll_events_until_timeout(*TIMEOUT, |_| ());
-
-        thread.join();
+        assert_eq!(accepted, 2);
     }
 
     #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
     #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
     #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
-    fn unreachable_connection(transport: Transport) {
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn send_owned(transport: Transport) {
         let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        controller.connect(transport, addr).unwrap();
 
-        // Ensure that addr is not using by other process
-        // because it takes some secs to be reusable.
-        let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
-        controller.remove(listener_id);
-
-        let (endpoint, _) = controller.connect(transport, addr).unwrap();
-        assert_eq!(controller.send(endpoint, &[42]), SendStatus::ResourceNotAvailable);
-        assert!(!controller.is_ready(endpoint.resource_id()).unwrap());
-
-        let mut was_disconnected = false;
-        processor.process_poll_events_until_timeout(*LOCALHOST_CONN_TIMEOUT, |net_event| {
-            match net_event {
-                NetEvent::Connected(net_endpoint, status) => {
-                    assert!(!status);
-                    assert_eq!(endpoint, net_endpoint);
-                    was_disconnected = true;
-                }
-                _ => unreachable!(),
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                assert_eq!(controller.send_owned(endpoint, vec![42; 100]), SendStatus::Sent);
             }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Message(_, data) => received.extend_from_slice(data),
+            _ => unreachable!(),
         });
-        assert!(was_disconnected);
+        assert_eq!(received, vec![42; 100]);
     }
 
     #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
     #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "udp", test_case(Transport::Udp))]
     #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
-    fn unreachable_connection_sync(transport: Transport) {
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn send_to_removed_endpoint(transport: Transport) {
         let (controller, mut processor) = self::split();
-
-        // Ensure that addr is not using by other process
-        // because it takes some secs to be reusable.
         let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
-        controller.remove(listener_id);
+        let (_, local_addr) = controller.connect(transport, addr).unwrap();
 
-        let mut thread = NamespacedThread::spawn("test", move || {
-            let err = controller.connect_sync(transport, addr).unwrap_err();
-            assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        let mut was_connected = false;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                assert!(controller.remove(endpoint.resource_id()));
+                assert_eq!(controller.send(endpoint, b"data"), SendStatus::ResourceNotFound);
+                let status = controller.send_owned(endpoint, b"data".to_vec());
+                assert_eq!(status, SendStatus::ResourceNotFound);
+                was_connected = true;
+            }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Disconnected(..) => (),
+            _ => unreachable!(),
         });
+        assert!(was_connected);
 
-        processor.process_poll_events_until_timeout(*LOCALHOST_CONN_TIMEOUT, |_| ());
-
-        thread.join();
+        assert!(controller.remove(listener_id));
+        if !transport.is_connection_oriented() {
+            let endpoint = Endpoint::from_listener(listener_id, local_addr);
+            assert_eq!(controller.send(endpoint, b"data"), SendStatus::ResourceNotFound);
+        }
     }
 
-    #[test]
-    fn create_remove_listener() {
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn send_to_closed_by_peer(transport: Transport) {
         let (controller, mut processor) = self::split();
-        let (listener_id, _) = controller.listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(transport, addr).unwrap();
+
+        let (mut was_connected, mut accepted) = (false, None);
+        let deadline = Instant::now() + *TIMEOUT;
+        while !(was_connected && accepted.is_some()) && Instant::now() < deadline {
+            processor.process_poll_event(Some(*TIMEOUT), |net_event| match net_event {
+                NetEvent::Connected(_, status) => was_connected = status,
+                NetEvent::Accepted(endpoint, _) => accepted = Some(endpoint),
+                _ => unreachable!(),
+            });
+        }
+        assert!(was_connected);
+        assert!(controller.remove(accepted.unwrap().resource_id()));
+
+        // The disconnection is not processed, only the OS knows that the peer closed.
+        let deadline = Instant::now() + *TIMEOUT;
+        let status = loop {
+            match controller.send(endpoint, b"data") {
+                SendStatus::Sent if Instant::now() < deadline => continue,
+                status => break status,
+            }
+        };
+        assert_eq!(status, SendStatus::ResourceNotFound);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test_case(Transport::Tcp)]
+    #[test_case(Transport::FramedTcp)]
+    fn user_timeout_connection(transport: Transport) {
+        use crate::adapters::tcp::{TcpConnectConfig, TcpListenConfig};
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, FramedTcpListenConfig};
+
+        let timeout = Duration::from_millis(500);
+        let (transport_listen, transport_connect) = match transport {
+            Transport::Tcp => (
+                TransportListen::Tcp(TcpListenConfig::default().with_user_timeout(timeout)),
+                TransportConnect::Tcp(TcpConnectConfig::default().with_user_timeout(timeout)),
+            ),
+            _ => (
+                TransportListen::FramedTcp(
+                    FramedTcpListenConfig::default().with_user_timeout(timeout),
+                ),
+                TransportConnect::FramedTcp(
+                    FramedTcpConnectConfig::default().with_user_timeout(timeout),
+                ),
+            ),
+        };
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen_with(transport_listen, "127.0.0.1:0").unwrap();
+        controller.connect_with(transport_connect, addr).unwrap();
+
+        let mut connected = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                connected += 1;
+            }
+            NetEvent::Accepted(..) => connected += 1,
+            _ => unreachable!(),
+        });
+        assert_eq!(connected, 2);
+    }
+
+    #[cfg(all(feature = "tcp", target_os = "linux"))]
+    #[test_case(Transport::Tcp)]
+    #[test_case(Transport::FramedTcp)]
+    fn bind_device_connection(transport: Transport) {
+        use crate::adapters::tcp::{TcpConnectConfig, TcpListenConfig};
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, FramedTcpListenConfig};
+
+        let device = String::from("lo");
+        let (transport_listen, transport_connect) = match transport {
+            Transport::Tcp => (
+                TransportListen::Tcp(TcpListenConfig::default().with_bind_device(device.clone())),
+                TransportConnect::Tcp(TcpConnectConfig::default().with_bind_device(device)),
+            ),
+            _ => (
+                TransportListen::FramedTcp(
+                    FramedTcpListenConfig::default().with_bind_device(device.clone()),
+                ),
+                TransportConnect::FramedTcp(
+                    FramedTcpConnectConfig::default().with_bind_device(device),
+                ),
+            ),
+        };
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen_with(transport_listen, "127.0.0.1:0").unwrap();
+        controller.connect_with(transport_connect, addr).unwrap();
+
+        let mut connected = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                connected += 1;
+            }
+            NetEvent::Accepted(..) => connected += 1,
+            _ => unreachable!(),
+        });
+        assert_eq!(connected, 2);
+    }
+
+    /// Address of the socket `fd` of this process, if it is bound to an IPv4 address.
+    #[cfg(all(feature = "tcp", feature = "udp", target_os = "linux"))]
+    fn bound_addr(fd: std::os::fd::RawFd) -> Option<SocketAddr> {
+        use nix::sys::socket::{getsockname, SockaddrIn};
+        let addr = getsockname::<SockaddrIn>(fd).ok()?;
+        Some(SocketAddr::V4(addr.into()))
+    }
+
+    /// Sockets of this process that are bound to `local_addr`.
+    #[cfg(all(feature = "tcp", feature = "udp", target_os = "linux"))]
+    fn sockets_bound_to(local_addr: SocketAddr) -> Vec<std::os::fd::RawFd> {
+        (0..1024).filter(|&fd| bound_addr(fd) == Some(local_addr)).collect()
+    }
+
+    #[cfg(all(feature = "tcp", feature = "udp", target_os = "linux"))]
+    #[test_case(Transport::Tcp)]
+    #[test_case(Transport::FramedTcp)]
+    #[test_case(Transport::Udp)]
+    fn tos_connection(transport: Transport) {
+        use crate::adapters::tcp::{TcpConnectConfig, TcpListenConfig};
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, FramedTcpListenConfig};
+        use crate::adapters::udp::{UdpConnectConfig, UdpListenConfig};
+        use nix::sys::socket::{getsockopt, sockopt};
+
+        // Reads the option of the sockets of this process that are bound to `local_addr`.
+        fn tos_of(local_addr: SocketAddr) -> Vec<i32> {
+            sockets_bound_to(local_addr)
+                .into_iter()
+                .filter_map(|fd| getsockopt(fd, sockopt::IpTos).ok())
+                .collect()
+        }
+
+        const EXPEDITED_FORWARDING: u8 = 46 << 2;
+        let (transport_listen, transport_connect) = match transport {
+            Transport::Tcp => (
+                TransportListen::Tcp(TcpListenConfig::default().with_tos(EXPEDITED_FORWARDING)),
+                TransportConnect::Tcp(TcpConnectConfig::default().with_tos(EXPEDITED_FORWARDING)),
+            ),
+            Transport::FramedTcp => (
+                TransportListen::FramedTcp(
+                    FramedTcpListenConfig::default().with_tos(EXPEDITED_FORWARDING),
+                ),
+                TransportConnect::FramedTcp(
+                    FramedTcpConnectConfig::default().with_tos(EXPEDITED_FORWARDING),
+                ),
+            ),
+            _ => (
+                TransportListen::Udp(UdpListenConfig::default().with_tos(EXPEDITED_FORWARDING)),
+                TransportConnect::Udp(UdpConnectConfig::default().with_tos(EXPEDITED_FORWARDING)),
+            ),
+        };
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen_with(transport_listen, "127.0.0.1:0").unwrap();
+        let (_, local_addr) = controller.connect_with(transport_connect, addr).unwrap();
+        processor.process_poll_events_until_timeout(Duration::from_millis(100), |_| ());
+
+        // The accepted connections share the address of the listener and inherit its option.
+        let listen_sockets = tos_of(addr);
+        let expected = EXPEDITED_FORWARDING as i32;
+        assert_eq!(listen_sockets.len(), if transport.is_connection_oriented() { 2 } else { 1 });
+        assert!(listen_sockets.iter().all(|&tos| tos == expected));
+        assert_eq!(tos_of(local_addr), vec![expected]);
+    }
+
+    #[cfg(all(feature = "tcp", feature = "udp", target_os = "linux"))]
+    #[test_case(Transport::Tcp)]
+    #[test_case(Transport::FramedTcp)]
+    #[test_case(Transport::Udp)]
+    fn socket_hook(transport: Transport) {
+        use crate::adapters::tcp::{TcpConnectConfig, TcpListenConfig, Socket};
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, FramedTcpListenConfig};
+        use crate::adapters::udp::{UdpConnectConfig, UdpListenConfig};
+        use nix::sys::socket::{getsockopt, sockopt};
+        use std::os::fd::{AsRawFd, RawFd};
+        use std::sync::{Arc, Mutex};
+
+        const SIZE: usize = 12345;
+        let hooked_fds: Arc<Mutex<Vec<RawFd>>> = Default::default();
+        let hook = {
+            let hooked_fds = hooked_fds.clone();
+            move |socket: &Socket| {
+                hooked_fds.lock().unwrap().push(socket.as_raw_fd());
+                socket.set_recv_buffer_size(SIZE)
+            }
+        };
+        let failing_hook = |_: &Socket| Err(io::ErrorKind::Other.into());
+        let (transport_listen, transport_connect, failing_connect) = match transport {
+            Transport::Tcp => (
+                TransportListen::Tcp(TcpListenConfig::default()),
+                TransportConnect::Tcp(TcpConnectConfig::default().with_socket(hook)),
+                TransportConnect::Tcp(TcpConnectConfig::default().with_socket(failing_hook)),
+            ),
+            Transport::FramedTcp => (
+                TransportListen::FramedTcp(FramedTcpListenConfig::default().with_socket(hook)),
+                TransportConnect::FramedTcp(FramedTcpConnectConfig::default()),
+                TransportConnect::FramedTcp(
+                    FramedTcpConnectConfig::default().with_socket(failing_hook),
+                ),
+            ),
+            _ => (
+                TransportListen::Udp(UdpListenConfig::default().with_socket(hook.clone())),
+                TransportConnect::Udp(UdpConnectConfig::default().with_socket(hook)),
+                TransportConnect::Udp(UdpConnectConfig::default().with_socket(failing_hook)),
+            ),
+        };
+
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen_with(transport_listen, "127.0.0.1:0").unwrap();
+        let (_, local_addr) = controller.connect_with(transport_connect, addr).unwrap();
+        assert!(controller.connect_with(failing_connect, addr).is_err());
+
+        // The hooked sockets are the ones the resources keep once bound.
+        let hooked_fds = hooked_fds.lock().unwrap().clone();
+        let configured = match transport {
+            Transport::Tcp => vec![local_addr],
+            Transport::FramedTcp => vec![addr],
+            _ => vec![addr, local_addr],
+        };
+        let hooked_addrs: Vec<_> = hooked_fds.iter().filter_map(|&fd| bound_addr(fd)).collect();
+        assert_eq!(hooked_addrs, configured);
+
+        // The kernel doubles the value to make room for its bookkeeping.
+        for fd in hooked_fds {
+            assert_eq!(getsockopt(fd, sockopt::RcvBuf).unwrap(), SIZE * 2);
+        }
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn connection_activity_times() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (server_endpoint, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        assert_eq!(controller.connected_at(server_endpoint), None);
+
+        let mut client_endpoint = None;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(..) => (),
+            NetEvent::Accepted(endpoint, _) => client_endpoint = Some(endpoint),
+            _ => unreachable!(),
+        });
+        let client_endpoint = client_endpoint.unwrap();
+
+        let accepted_at = controller.connected_at(client_endpoint).unwrap();
+        assert_eq!(controller.last_activity(client_endpoint), Some(accepted_at));
+        let connected_at = controller.connected_at(server_endpoint).unwrap();
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(controller.send(server_endpoint, b"data"), SendStatus::Sent);
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(endpoint, _) => assert_eq!(endpoint, client_endpoint),
+            _ => unreachable!(),
+        });
+
+        // Both sides have activity: one has sent the data and the other has received it.
+        assert_eq!(controller.connected_at(client_endpoint), Some(accepted_at));
+        let last_activity = controller.last_activity(client_endpoint).unwrap();
+        assert!(last_activity >= accepted_at + Duration::from_millis(50));
+        assert_eq!(controller.connected_at(server_endpoint), Some(connected_at));
+        let last_activity = controller.last_activity(server_endpoint).unwrap();
+        assert!(last_activity >= connected_at + Duration::from_millis(50));
+
+        assert!(controller.remove(server_endpoint.resource_id()));
+        assert_eq!(controller.connected_at(server_endpoint), None);
+        assert_eq!(controller.last_activity(server_endpoint), None);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn listen_multi_group() {
+        if std::net::TcpListener::bind("[::1]:0").is_err() {
+            return // The environment has no IPv6 loopback to test with.
+        }
+        let (controller, mut processor) = self::split();
+        let busy_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addrs = [
+            "127.0.0.1:0".parse().unwrap(),
+            busy_listener.local_addr().unwrap(),
+            "[::1]:0".parse().unwrap(),
+        ];
+        let results = controller.listen_multi(Transport::FramedTcp, &addrs);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1].as_ref().unwrap_err().kind(), io::ErrorKind::AddrInUse);
+        let (group_id, addr_v4) = *results[0].as_ref().unwrap();
+        let (listener_id_v6, addr_v6) = *results[2].as_ref().unwrap();
+        assert!(addr_v4.is_ipv4() && addr_v6.is_ipv6());
+
+        controller.connect(Transport::FramedTcp, addr_v4).unwrap();
+        controller.connect(Transport::FramedTcp, addr_v6).unwrap();
+
+        let mut accepted = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Accepted(endpoint, listener_id) => {
+                assert_eq!(listener_id, group_id);
+                assert_eq!(controller.listener_of(endpoint), Some(group_id));
+                accepted.push(endpoint.addr().is_ipv4());
+            }
+            _ => unreachable!(),
+        });
+        accepted.sort();
+        assert_eq!(accepted, vec![false, true]);
+
+        // The group is removed as one listener.
+        assert!(controller.remove(group_id));
+        assert_eq!(controller.is_ready(listener_id_v6), None);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn listen_multi_all_rollback() {
+        let (controller, _processor) = self::split();
+        let busy_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addrs = ["127.0.0.1:0".parse().unwrap(), busy_listener.local_addr().unwrap()];
+        let err = controller.listen_multi_all(Transport::FramedTcp, &addrs).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+
+        // The listener of the first address is removed.
+        let framed_tcp = &controller.controllers[Transport::FramedTcp.id() as usize];
+        assert!(framed_tcp.resource_ids(ResourceType::Local).is_empty());
+
+        let addrs = ["127.0.0.1:0".parse().unwrap(), "127.0.0.1:0".parse().unwrap()];
+        let listeners = controller.listen_multi_all(Transport::FramedTcp, &addrs).unwrap();
+        assert_eq!(listeners.len(), 2);
+        assert!(controller.remove(listeners[0].0));
+        assert_eq!(controller.is_ready(listeners[1].0), None);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn read_deadline_expiration() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (silent_endpoint, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        let (talking_endpoint, talking_addr) =
+            controller.connect(Transport::FramedTcp, addr).unwrap();
+
+        let mut received = Vec::new();
+        let mut timed_out = Vec::new();
+        let mut disconnected = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                let deadline = Instant::now() + Duration::from_millis(100);
+                assert!(controller.set_read_deadline(endpoint, Some(deadline)));
+            }
+            NetEvent::Accepted(endpoint, _) => {
+                if endpoint.addr() == talking_addr {
+                    controller.send(endpoint, b"hello");
+                }
+            }
+            NetEvent::Message(endpoint, data) => received.push((endpoint, data.to_vec())),
+            NetEvent::Error(endpoint, kind) => {
+                assert_eq!(kind, io::ErrorKind::TimedOut);
+                timed_out.push(endpoint);
+            }
+            NetEvent::Disconnected(_) => disconnected += 1, // Both sides of the silent one
+            _ => unreachable!(),
+        });
+        assert_eq!(received, vec![(talking_endpoint, b"hello".to_vec())]);
+        assert_eq!(timed_out, vec![silent_endpoint]);
+        assert_eq!(disconnected, 2);
+        assert_eq!(controller.is_ready(silent_endpoint.resource_id()), None);
+        assert_eq!(controller.is_ready(talking_endpoint.resource_id()), Some(true));
+        assert!(!controller.set_read_deadline(silent_endpoint, None));
+    }
+
+    #[cfg(all(feature = "tcp", feature = "udp"))]
+    #[test]
+    fn external_poll() {
+        use mio::{Events, Interest, Token};
+
+        let mut poll = mio::Poll::new().unwrap();
+        let mut events = Events::with_capacity(64);
+        let (controller, mut processor) =
+            self::split_with_registry(poll.registry(), TransportDefaults::default());
+
+        // A source of the host loop sharing the poll.
+        const HOST_TOKEN: Token = Token(2);
+        let mut host_socket = mio::net::UdpSocket::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        poll.registry().register(&mut host_socket, HOST_TOKEN, Interest::READABLE).unwrap();
+        let sender = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.send_to(b"host", host_socket.local_addr().unwrap()).unwrap();
+
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (server_endpoint, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+
+        let mut host_events = 0;
+        let mut received = Vec::new();
+        let deadline = Instant::now() + *TIMEOUT;
+        while received.len() < 2 && Instant::now() < deadline {
+            let timeout = processor.poll_timeout().unwrap_or(*TIMEOUT);
+            poll.poll(&mut events, Some(timeout)).unwrap();
+            host_events += events.iter().filter(|event| event.token() == HOST_TOKEN).count();
+            processor.process_ready(&events, |net_event| match net_event {
+                NetEvent::Connected(endpoint, status) => {
+                    assert!(status);
+                    controller.send(endpoint, b"ping");
+                }
+                NetEvent::Accepted(..) => (),
+                NetEvent::Message(endpoint, data) => {
+                    if endpoint != server_endpoint {
+                        controller.send(endpoint, b"pong");
+                    }
+                    received.push(data.to_vec());
+                }
+                _ => unreachable!(),
+            });
+        }
+        assert_eq!(received, vec![b"ping".to_vec(), b"pong".to_vec()]);
+        assert_eq!(host_events, 1);
+
+        let mut buffer = [0; 16];
+        let (size, _) = host_socket.recv_from(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], b"host");
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn throughput_sampling() {
+        const MESSAGES: usize = 10;
+        const MESSAGE_SIZE: usize = 1000;
+        let interval = Duration::from_millis(50);
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (server_endpoint, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        controller.set_throughput_sampling(Some(interval));
+
+        let mut client_endpoint = None;
+        let mut samples = Vec::new();
+        let deadline = Instant::now() + interval * 5;
+        while Instant::now() < deadline {
+            processor.process_poll_event(Some(interval), |net_event| match net_event {
+                NetEvent::Connected(..) => {
+                    for _ in 0..MESSAGES {
+                        let status = controller.send(server_endpoint, &[0; MESSAGE_SIZE]);
+                        assert_eq!(status, SendStatus::Sent);
+                    }
+                }
+                NetEvent::Accepted(endpoint, _) => client_endpoint = Some(endpoint),
+                NetEvent::Message(..) => (),
+                NetEvent::Throughput(sample) => samples.push(sample),
+                _ => unreachable!(),
+            });
+        }
+        let client_endpoint = client_endpoint.unwrap();
+
+        let total = |endpoint, bytes: fn(&ThroughputSample) -> u64| -> u64 {
+            samples.iter().filter(|sample| sample.endpoint == endpoint).map(bytes).sum()
+        };
+        let transferred = (MESSAGES * MESSAGE_SIZE) as u64;
+        assert_eq!(total(server_endpoint, |sample| sample.bytes_out), transferred);
+        assert_eq!(total(server_endpoint, |sample| sample.bytes_in), 0);
+        assert_eq!(total(client_endpoint, |sample| sample.bytes_in), transferred);
+        assert_eq!(total(client_endpoint, |sample| sample.bytes_out), 0);
+        assert!(samples.len() >= 2 * 3); // Both endpoints sampled in several intervals.
+        assert!(samples.iter().all(|sample| sample.interval >= interval));
+
+        // Once disabled, no more samples are generated.
+        controller.set_throughput_sampling(None);
+        processor.process_poll_events_until_timeout(interval * 2, |net_event| {
+            assert!(!matches!(net_event, NetEvent::Throughput(_)), "Sampling disabled");
+        });
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn tcp_read_boundaries() {
+        use std::io::{Write};
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+        let mut peer = std::net::TcpStream::connect(addr).unwrap();
+
+        // Each write is performed once the previous one has been read.
+        let mut sizes = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Accepted(..) => peer.write_all(&[1; 5]).unwrap(),
+            NetEvent::Message(_, data) => {
+                sizes.push(data.len());
+                if sizes.len() == 1 {
+                    peer.write_all(&[2; 3]).unwrap();
+                }
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(sizes, vec![5, 3]);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn reject_on_accepted() {
+        use std::io::{Read, Write};
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let mut peer = std::net::TcpStream::connect(addr).unwrap();
+        peer.write_all(&[5, b'h', b'e', b'l', b'l', b'o']).unwrap(); // Before being accepted
+
+        let mut rejected = None;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Accepted(endpoint, _) => {
+                assert!(controller.reject(endpoint));
+                rejected = Some(endpoint);
+            }
+            _ => unreachable!(),
+        });
+        let rejected = rejected.unwrap();
+        assert_eq!(controller.is_ready(rejected.resource_id()), None);
+        assert!(!controller.reject(rejected));
+
+        // The peer reads the end of the stream.
+        let mut data = Vec::new();
+        peer.read_to_end(&mut data).unwrap();
+        assert!(data.is_empty());
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn framed_tcp_partial_frame_stats() {
+        use std::io::{Write};
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let mut peer = std::net::TcpStream::connect(addr).unwrap();
+
+        // The header declares 100 bytes, but only 30 of them are sent.
+        peer.write_all(&[100]).unwrap();
+        peer.write_all(&[1; 30]).unwrap();
+        let mut client_endpoint = None;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Accepted(endpoint, _) => client_endpoint = Some(endpoint),
+            _ => unreachable!(),
+        });
+        let client_endpoint = client_endpoint.unwrap();
+        let stats = controller.framing_stats(client_endpoint).unwrap();
+        assert_eq!(stats.buffered_bytes, 31);
+        assert_eq!(stats.frame_size, Some(100));
+        assert_eq!(stats.frames_decoded, 0);
+
+        peer.write_all(&[1; 70]).unwrap();
+        let mut sizes = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(_, data) => sizes.push(data.len()),
+            _ => unreachable!(),
+        });
+        assert_eq!(sizes, vec![100]);
+        let stats = controller.framing_stats(client_endpoint).unwrap();
+        assert_eq!(stats, FramingStats { buffered_bytes: 0, frame_size: None, frames_decoded: 1 });
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn ws_control_frames() {
+        use crate::adapters::ws::{WsConnectConfig, WsListenConfig, MAX_CONTROL_PAYLOAD_LEN};
+
+        let (controller, mut processor) = self::split();
+        let config = TransportListen::Ws(WsListenConfig::default().with_control_frames());
+        let (_, addr) = controller.listen_with(config, "127.0.0.1:0").unwrap();
+        let config = TransportConnect::Ws(WsConnectConfig::default().with_control_frames());
+        let (server_endpoint, _) = controller.connect_with(config, addr).unwrap();
+
+        let mut frames = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                let status = controller.send_ws_ping(endpoint, &[0; MAX_CONTROL_PAYLOAD_LEN + 1]);
+                assert_eq!(status, SendStatus::MaxPacketSizeExceeded);
+                assert_eq!(controller.send_ws_ping(endpoint, b"app data"), SendStatus::Sent);
+            }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Control(endpoint, frame, data) => {
+                frames.push((endpoint == server_endpoint, frame, data.to_vec()))
+            }
+            _ => unreachable!(),
+        });
+
+        // The ping is received by the server, that still answers it with a pong.
+        assert_eq!(
+            frames,
+            vec![
+                (false, ControlFrame::Ping, b"app data".to_vec()),
+                (true, ControlFrame::Pong, b"app data".to_vec())
+            ]
+        );
+    }
+
+    #[cfg(all(feature = "websocket", feature = "udp"))]
+    #[test]
+    fn ws_ping_other_transport() {
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Udp, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(Transport::Udp, addr).unwrap();
+        assert_eq!(controller.send_ws_ping(endpoint, b"app data"), SendStatus::ResourceNotFound);
+    }
+
+    #[cfg(all(feature = "websocket", feature = "udp"))]
+    #[test]
+    fn ws_resume_token_other_transport() {
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Udp, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(Transport::Udp, addr).unwrap();
+        assert_eq!(controller.ws_resume_token(endpoint), None);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn ws_upgraded_connection() {
+        use crate::adapters::ws::{WsUpgraded};
+        use std::net::{TcpListener, TcpStream};
+
+        // The handshakes are performed outside, as an HTTP server would do.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client_thread = NamespacedThread::spawn("test-ws-client", move || {
+            let stream = TcpStream::connect(addr).unwrap();
+            tungstenite::client(format!("ws://{addr}/upgraded"), &stream).unwrap();
+            stream
+        });
+        let (server_stream, client_addr) = listener.accept().unwrap();
+        tungstenite::accept(&server_stream).unwrap();
+        let client_stream = client_thread.join();
+
+        let (controller, mut processor) = self::split();
+        let server_side = TransportConnect::WsUpgraded(WsUpgraded::server(server_stream));
+        let (client_endpoint, _) = controller.connect_with(server_side, client_addr).unwrap();
+        let client_side = TransportConnect::WsUpgraded(WsUpgraded::client(client_stream));
+        let (server_endpoint, _) = controller.connect_with(client_side, addr).unwrap();
+        assert_eq!(client_endpoint.addr(), client_addr);
+        assert_eq!(server_endpoint.addr(), addr);
+
+        let mut connected = 0;
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                connected += 1;
+                if endpoint == server_endpoint {
+                    assert_eq!(controller.send(server_endpoint, b"ping"), SendStatus::Sent);
+                }
+            }
+            NetEvent::Message(endpoint, data) => {
+                received.push((endpoint, data.to_vec()));
+                if endpoint == client_endpoint {
+                    assert_eq!(controller.send(client_endpoint, b"pong"), SendStatus::Sent);
+                }
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(connected, 2);
+        assert_eq!(
+            received,
+            vec![(client_endpoint, b"ping".to_vec()), (server_endpoint, b"pong".to_vec())]
+        );
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn ws_listener_path() {
+        use crate::adapters::ws::{WsConnectConfig, WsListenConfig};
+
+        let (controller, mut processor) = self::split();
+        let config = TransportListen::Ws(WsListenConfig::default().with_path("/chat".into()));
+        let (_, addr) = controller.listen_with(config, "127.0.0.1:0").unwrap();
+        let config = TransportConnect::Ws(WsConnectConfig::default().with_path("/chat".into()));
+        let (chat_endpoint, _) = controller.connect_with(config, addr).unwrap();
+        let (default_endpoint, _) = controller.connect(Transport::Ws, addr).unwrap();
+
+        let mut connected = Vec::new();
+        let mut accepted = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => connected.push((endpoint, status)),
+            NetEvent::Accepted(..) => accepted += 1,
+            _ => unreachable!(),
+        });
+        assert_eq!(connected.len(), 2);
+        assert!(connected.contains(&(chat_endpoint, true)));
+        assert!(connected.contains(&(default_endpoint, false)));
+        assert_eq!(accepted, 1);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn ws_relative_path() {
+        use crate::adapters::ws::{WsConnectConfig, WsListenConfig};
+
+        let (controller, _processor) = self::split();
+        let config = TransportListen::Ws(WsListenConfig::default().with_path("chat".into()));
+        let err = controller.listen_with(config, "127.0.0.1:0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let (_, addr) = controller.listen(Transport::Ws, "127.0.0.1:0").unwrap();
+        let config = TransportConnect::Ws(WsConnectConfig::default().with_path("chat".into()));
+        let err = controller.connect_with(config, addr).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    #[allow(clippy::result_large_err)] // The handshake callback signature
+    fn ws_connect_path_and_host() {
+        use crate::adapters::ws::{WsConnectConfig};
+        use tungstenite::handshake::server::{Request, Response};
+        use std::net::{TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server_thread = NamespacedThread::spawn("test-ws-server", move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut requested = None;
+            tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+                let host = request.headers()["host"].to_str().unwrap().to_string();
+                requested = Some((request.uri().path().to_string(), host));
+                Ok(response)
+            })
+            .unwrap();
+            requested.unwrap()
+        });
+
+        let (controller, mut processor) = self::split();
+        let config = WsConnectConfig::default()
+            .with_path("/custom/path".into())
+            .with_host("example.com".into());
+        controller.connect_with(TransportConnect::Ws(config), addr).unwrap();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Disconnected(_) => (), // The server closes after the handshake
+            _ => unreachable!(),
+        });
+
+        let (path, host) = server_thread.join();
+        assert_eq!(path, "/custom/path");
+        assert_eq!(host, "example.com");
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test_case(true, "http://{addr}/redirected", true ; "followed")]
+    #[test_case(false, "http://{addr}/redirected", false ; "not followed")]
+    #[test_case(true, "https://{addr}/redirected", false ; "to tls")]
+    #[test_case(true, "http://localhost:{port}/redirected", false ; "to domain")]
+    fn ws_handshake_redirection(follow: bool, location: &str, followed: bool) {
+        use crate::adapters::ws::{WsConnectConfig, WsHandshakeRejected};
+        use std::net::{TcpListener};
+        use std::io::{Read, Write};
+
+        let (controller, mut processor) = self::split();
+        let (_, ws_addr) = controller.listen(Transport::Ws, "127.0.0.1:0").unwrap();
+        let location = location
+            .replace("{addr}", &ws_addr.to_string())
+            .replace("{port}", &ws_addr.port().to_string());
+
+        let redirector = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = redirector.local_addr().unwrap();
+        let redirect_response =
+            format!("HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\n\r\n");
+        let mut redirector_thread = NamespacedThread::spawn("test-ws-redirector", move || {
+            let (mut stream, _) = redirector.accept().unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut byte = [0];
+                stream.read_exact(&mut byte).unwrap();
+                request.push(byte[0]);
+            }
+            stream.write_all(redirect_response.as_bytes()).unwrap();
+        });
+
+        let mut client_thread = NamespacedThread::spawn("test-ws-client", move || {
+            let config = match follow {
+                true => WsConnectConfig::default().with_redirects(1),
+                false => WsConnectConfig::default(),
+            };
+            let result = controller.connect_sync_with(TransportConnect::Ws(config), addr);
+            (controller, result)
+        });
+
+        let mut accepted = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => assert_eq!(status, followed),
+            NetEvent::Accepted(..) => accepted += 1,
+            _ => unreachable!(),
+        });
+        redirector_thread.join();
+        let (controller, result) = client_thread.join();
+
+        match followed {
+            true => {
+                let (endpoint, _) = result.unwrap();
+                assert_eq!(endpoint.addr(), addr); // The address of the first connection
+                assert_eq!(accepted, 1);
+                assert_eq!(controller.send(endpoint, b"data"), SendStatus::Sent);
+            }
+            false => {
+                let err = match result.unwrap_err() {
+                    NetworkError::HandshakeFailed(err) => err,
+                    err => panic!("Unexpected error: {}", err),
+                };
+                assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+                let rejection = err.get_ref().unwrap().downcast_ref::<WsHandshakeRejected>();
+                let rejection = rejection.unwrap();
+                assert_eq!(rejection.status, 302);
+                assert!(rejection.headers.contains(&("location".into(), location)));
+                assert_eq!(accepted, 0);
+            }
+        }
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn ws_resume_token_on_reconnection() {
+        use crate::adapters::ws::{WsConnectConfig};
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Ws, "127.0.0.1:0").unwrap();
+        let config = WsConnectConfig::default().with_resume_token("session-1234".into());
+
+        // The first connection, the reconnection after dropping it, and one without token.
+        let mut tokens = Vec::new();
+        for connect_config in [Some(config.clone()), Some(config), None] {
+            let (endpoint, _) = match connect_config {
+                Some(config) => controller.connect_with(TransportConnect::Ws(config), addr),
+                None => controller.connect(Transport::Ws, addr),
+            }
+            .unwrap();
+
+            let mut accepted = None;
+            let deadline = Instant::now() + *TIMEOUT;
+            while accepted.is_none() && Instant::now() < deadline {
+                processor.process_poll_event(Some(*TIMEOUT), |net_event| match net_event {
+                    NetEvent::Connected(_, status) => assert!(status),
+                    NetEvent::Accepted(endpoint, _) => accepted = Some(endpoint),
+                    NetEvent::Disconnected(_) => (), // The previous connection
+                    _ => unreachable!(),
+                });
+            }
+            let accepted = accepted.expect("Connection not accepted");
+            tokens.push(controller.ws_resume_token(accepted));
+            assert!(controller.remove(endpoint.resource_id()));
+        }
+
+        let token = Some(String::from("session-1234"));
+        assert_eq!(tokens, vec![token.clone(), token, None]);
+    }
+
+    #[cfg(all(feature = "udp", target_os = "linux"))]
+    #[test]
+    fn bind_device_udp() {
+        use crate::adapters::udp::{UdpConnectConfig, UdpListenConfig};
+
+        // The traffic sent to a local address goes through the loopback interface.
+        let other_device = match nix::ifaddrs::getifaddrs()
+            .unwrap()
+            .map(|ifaddr| ifaddr.interface_name)
+            .find(|name| name != "lo")
+        {
+            Some(device) => device,
+            None => return, // Only the loopback interface, nothing to compare with.
+        };
+
+        let (controller, mut processor) = self::split();
+        let lo_config = UdpListenConfig::default().with_bind_device("lo".into());
+        let (lo_listener_id, lo_addr) =
+            controller.listen_with(TransportListen::Udp(lo_config), "0.0.0.0:0").unwrap();
+        let other_config = UdpListenConfig::default().with_bind_device(other_device);
+        let (_, other_addr) =
+            controller.listen_with(TransportListen::Udp(other_config), "0.0.0.0:0").unwrap();
+
+        for port in [lo_addr.port(), other_addr.port()] {
+            let config = UdpConnectConfig::default().with_bind_device("lo".into());
+            let addr = SocketAddr::from(([127, 0, 0, 1], port));
+            controller.connect_with(TransportConnect::Udp(config), addr).unwrap();
+        }
+
+        let (mut received, mut refused) = (0, 0);
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                assert_eq!(controller.send(endpoint, &[42]), SendStatus::Sent);
+            }
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(endpoint.resource_id(), lo_listener_id);
+                assert_eq!(data, [42]);
+                received += 1;
+            }
+            // No socket of the loopback interface is listening at the other port.
+            NetEvent::Error(_, kind) => {
+                assert_eq!(kind, io::ErrorKind::ConnectionRefused);
+                refused += 1;
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(received, 1);
+        assert_eq!(refused, 1);
+    }
+
+    #[cfg(all(feature = "tcp", feature = "udp"))]
+    #[test]
+    fn remove_all_order() {
+        let (controller, mut processor) = self::split();
+        let mut listeners = Vec::new();
+        let mut endpoints = Vec::new();
+        for (transport, connections) in
+            [(Transport::FramedTcp, 2), (Transport::Tcp, 1), (Transport::Udp, 1)]
+        {
+            let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+            listeners.push(listener_id);
+            for _ in 0..connections {
+                let (endpoint, _) = controller.connect(transport, addr).unwrap();
+                if !transport.is_connection_oriented() {
+                    endpoints.push(endpoint);
+                }
+            }
+        }
+
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                if !endpoints.contains(&endpoint) {
+                    endpoints.push(endpoint);
+                }
+            }
+            NetEvent::Accepted(endpoint, _) => endpoints.push(endpoint),
+            _ => unreachable!(),
+        });
+        assert_eq!(endpoints.len(), 7);
+
+        assert_eq!(controller.remove_all(*TIMEOUT), endpoints.len());
+        for listener_id in listeners {
+            assert_eq!(controller.is_ready(listener_id), None);
+        }
+
+        let mut disconnected = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Disconnected(endpoint) => disconnected.push(endpoint),
+            _ => unreachable!(),
+        });
+
+        // By transport and then in the order they were created.
+        endpoints.sort_by_key(|endpoint| {
+            (endpoint.resource_id().adapter_id(), endpoint.resource_id().raw())
+        });
+        assert_eq!(disconnected, endpoints);
+    }
+
+    #[cfg(feature = "websocket")]
+    #[test]
+    fn close_all_by_transport() {
+        let (controller, mut processor) = self::split();
+        let mut listeners = Vec::new();
+        for transport in [Transport::FramedTcp, Transport::Ws] {
+            let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+            listeners.push(listener_id);
+            controller.connect(transport, addr).unwrap();
+        }
+
+        let mut ws_endpoints = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, _) | NetEvent::Accepted(endpoint, _) => {
+                if Transport::from(endpoint.resource_id().adapter_id()) == Transport::Ws {
+                    ws_endpoints.push(endpoint);
+                }
+            }
+            _ => unreachable!(),
+        });
+        ws_endpoints.sort_by_key(|endpoint| endpoint.resource_id().raw());
+        assert_eq!(controller.connections_by_transport(Transport::Ws), ws_endpoints);
+        let tcp_endpoints = controller.connections_by_transport(Transport::FramedTcp);
+        assert_eq!(tcp_endpoints.len(), 2);
+
+        assert_eq!(controller.close_all(Transport::Ws, *TIMEOUT), 2);
+        let mut disconnected = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Disconnected(endpoint) => disconnected.push(endpoint),
+            _ => unreachable!(),
+        });
+        assert_eq!(disconnected, ws_endpoints);
+        assert!(controller.connections_by_transport(Transport::Ws).is_empty());
+
+        // The rest of the resources are kept.
+        assert_eq!(controller.connections_by_transport(Transport::FramedTcp), tcp_endpoints);
+        for listener_id in listeners {
+            assert_eq!(controller.is_ready(listener_id), Some(true));
+        }
+        assert_eq!(controller.send(tcp_endpoints[0], b"data"), SendStatus::Sent);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn send_group_and_leave_on_disconnection() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (client_1, local_addr_1) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        let (client_2, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        let group = controller.create_group();
+
+        let mut members = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(..) => (),
+            NetEvent::Accepted(endpoint, _) => {
+                assert!(controller.join_group(group, endpoint));
+                members.push(endpoint);
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(controller.group_members(group), members);
+        assert!(controller.group_members(controller.create_group()).is_empty());
+
+        let statuses = controller.send_group(group, b"data");
+        assert_eq!(statuses, members.iter().map(|&e| (e, SendStatus::Sent)).collect::<Vec<_>>());
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(data, b"data");
+                received.push(endpoint);
+            }
+            _ => unreachable!(),
+        });
+        received.sort_by_key(|endpoint| endpoint.resource_id().raw());
+        assert_eq!(received, vec![client_1, client_2]);
+
+        // The peer of the first member is removed.
+        let peer = match members[0].addr() == local_addr_1 {
+            true => client_1,
+            false => client_2,
+        };
+        assert!(controller.remove(peer.resource_id()));
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Disconnected(endpoint) => assert_eq!(endpoint, members[0]),
+            _ => unreachable!(),
+        });
+        assert_eq!(controller.group_members(group), vec![members[1]]);
+        assert_eq!(controller.send_group(group, b"data"), vec![(members[1], SendStatus::Sent)]);
+
+        assert!(controller.leave_group(group, members[1]));
+        assert!(!controller.leave_group(group, members[1]));
+        assert!(controller.group_members(group).is_empty());
+    }
+
+    #[test]
+    fn network_change_notification() {
+        let (controller, mut processor) = self::split();
+        #[cfg(feature = "network-monitor")]
+        for _ in 0..2 {
+            controller.monitor_network_changes().unwrap();
+        }
+
+        // The notifications given before processing them are merged.
+        controller.notify_network_change();
+        controller.notify_network_change();
+        let mut changes = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::NetworkChanged => changes += 1,
+            _ => unreachable!(),
+        });
+        assert_eq!(changes, 1);
+
+        // The notification wakes the processor waiting without timeout.
+        let controller = Arc::new(controller);
+        let notifier = controller.clone();
+        let mut thread = NamespacedThread::spawn("test", move || {
+            std::thread::sleep(Duration::from_millis(100));
+            notifier.notify_network_change();
+        });
+        processor.process_poll_event(None, |net_event| match net_event {
+            NetEvent::NetworkChanged => changes += 1,
+            _ => unreachable!(),
+        });
+        thread.join();
+        assert_eq!(changes, 2);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test_case(DropPolicy::DropNewest, &[1, 2], SendStatus::Dropped)]
+    #[test_case(DropPolicy::DropOldest, &[2, 3], SendStatus::SentDroppingOldest)]
+    #[test_case(DropPolicy::Block, &[1, 2, 3], SendStatus::Sent)]
+    fn queue_capacity_congestion(policy: DropPolicy, expected: &[u8], last_status: SendStatus) {
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, QueueCapacity};
+        use std::collections::{HashMap};
+
+        let (controller, mut processor) = self::split();
+        let (congested_id, congested_addr) =
+            controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (_, other_addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let config = FramedTcpConnectConfig::default()
+            .with_queue_capacity(QueueCapacity::Messages(2), policy);
+        let connect = |addr| {
+            let config = TransportConnect::FramedTcp(config.clone());
+            controller.connect_with(config, addr).unwrap().0
+        };
+        let congested = connect(congested_addr);
+        let other = connect(other_addr);
+
+        let mut listener_of = HashMap::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(..) => (),
+            NetEvent::Accepted(endpoint, id) => drop(listener_of.insert(endpoint, id)),
+            _ => unreachable!(),
+        });
+
+        // Nothing is written until the network thread processes the queues.
+        assert_eq!(controller.send(congested, &[1]), SendStatus::Sent);
+        assert_eq!(controller.send(congested, &[2]), SendStatus::Sent);
+        assert_eq!(controller.send_owned(congested, vec![3]), last_status);
+        assert_eq!(controller.send(other, &[1]), SendStatus::Sent);
+
+        let mut received = Vec::new();
+        let mut other_received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Message(endpoint, data) => match listener_of[&endpoint] == congested_id {
+                true => received.extend_from_slice(data),
+                false => other_received.extend_from_slice(data),
+            },
+            _ => unreachable!(),
+        });
+        assert_eq!(received, expected);
+        assert_eq!(other_received, [1]);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn queue_capacity_block_from_network_thread() {
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig, QueueCapacity};
+
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let config = FramedTcpConnectConfig::default()
+            .with_queue_capacity(QueueCapacity::Messages(1), DropPolicy::Block);
+        controller.connect_with(TransportConnect::FramedTcp(config), addr).unwrap();
+
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(endpoint, _) => {
+                // The queue is full until the network thread writes it.
+                assert_eq!(controller.send(endpoint, &[1]), SendStatus::Sent);
+                assert_eq!(controller.send(endpoint, &[2]), SendStatus::Dropped);
+            }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Message(_, data) => received.extend_from_slice(data),
+            _ => unreachable!(),
+        });
+        assert_eq!(received, [1]);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn watermark_crossings() {
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig};
+
+        const MESSAGE_SIZE: usize = 1024 * 1024;
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (unqueued, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+        let config = FramedTcpConnectConfig::default().with_write_chunk_size(16 * 1024);
+        let connect = TransportConnect::FramedTcp(config);
+        let (endpoint, _) = controller.connect_with(connect, addr).unwrap();
+
+        let watermarks = Watermarks::new(1024, 64 * 1024);
+        let mut crossings = Vec::new();
+        let mut received = 0;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(connected, status) => {
+                assert!(status);
+                if connected == unqueued {
+                    // Without a queue there is nothing to watch.
+                    assert!(!controller.set_watermarks(unqueued, Some(watermarks)));
+                    return
+                }
+                assert!(controller.set_watermarks(endpoint, Some(watermarks)));
+                let status = controller.send_owned(endpoint, vec![42; MESSAGE_SIZE]);
+                assert_eq!(status, SendStatus::Sent);
+            }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Message(_, data) => received += data.len(),
+            NetEvent::Watermark(watermark_endpoint, watermark) => {
+                assert_eq!(watermark_endpoint, endpoint);
+                // Not generated inside a lock of the network, so it can be used from here.
+                if watermark == Watermark::Low {
+                    assert_eq!(controller.send(endpoint, &[42]), SendStatus::Sent);
+                }
+                crossings.push(watermark);
+            }
+            _ => unreachable!(),
+        });
+        assert_eq!(crossings, [Watermark::High, Watermark::Low]);
+        assert_eq!(received, MESSAGE_SIZE + 1);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn send_tracked_to_slow_reader() {
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig};
+
+        // Bigger than the socket buffers of both sides, to keep data queued.
+        const MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+        let (reader_controller, mut reader_processor) = self::split();
+        let (_, addr) = reader_controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+
+        let (controller, mut processor) = self::split();
+        let config = FramedTcpConnectConfig::default().with_write_chunk_size(64 * 1024);
+        let connect = TransportConnect::FramedTcp(config);
+        let (endpoint, _) = controller.connect_with(connect, addr).unwrap();
+
+        let mut ticket = None;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                ticket = Some(controller.send_tracked(endpoint, vec![42; MESSAGE_SIZE]));
+            }
+            _ => unreachable!(),
+        });
+        let ticket = ticket.unwrap();
+        assert_eq!(ticket.send_status(), SendStatus::Sent);
+        // The reader has not read anything yet.
+        assert_eq!(ticket.status(), TicketStatus::Pending);
+
+        let mut reader_thread = NamespacedThread::spawn("test-slow-reader", move || {
+            let mut received = 0;
+            reader_processor.process_poll_events_until_timeout(*TIMEOUT * 3, |net_event| {
+                match net_event {
+                    NetEvent::Accepted(..) => (),
+                    NetEvent::Message(_, data) => received += data.len(),
+                    _ => unreachable!(),
+                }
+            });
+            received
+        });
+        processor.process_poll_events_until_timeout(*TIMEOUT * 3, |_| unreachable!());
+        assert_eq!(ticket.wait(Duration::ZERO), TicketStatus::Written);
+        assert_eq!(reader_thread.join(), MESSAGE_SIZE);
+
+        assert!(controller.remove(endpoint.resource_id()));
+        let ticket = controller.send_tracked(endpoint, vec![42]);
+        assert_eq!(ticket.send_status(), SendStatus::ResourceNotFound);
+        assert_eq!(ticket.status(), TicketStatus::Failed);
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn remove_graceful(transport: Transport) {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(transport, addr).unwrap();
+
+        let mut disconnected = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                assert!(controller.remove_graceful(endpoint.resource_id(), *TIMEOUT));
+                assert_eq!(controller.send(endpoint, &[42]), SendStatus::ResourceNotAvailable);
+            }
+            NetEvent::Accepted(..) => (),
+            NetEvent::Disconnected(endpoint) => disconnected.push(endpoint),
+            _ => unreachable!(),
+        });
+
+        // Both sides of the connection are disconnected.
+        assert_eq!(disconnected.len(), 2);
+        assert!(disconnected.contains(&endpoint));
+        assert_eq!(controller.is_ready(endpoint.resource_id()), None);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn remove_graceful_timeout_without_events() {
+        use crate::adapters::framed_tcp::{FramedTcpConnectConfig};
+
+        const GRACEFUL_TIMEOUT: Duration = Duration::from_millis(200);
+        // A peer that never reads, the queued data could never be written.
+        let peer_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let (controller, mut processor) = self::split();
+        let config = FramedTcpConnectConfig::default().with_write_chunk_size(64 * 1024);
+        let connect = TransportConnect::FramedTcp(config);
+        let addr = peer_listener.local_addr().unwrap();
+        let (endpoint, _) = controller.connect_with(connect, addr).unwrap();
+        let _peer = peer_listener.accept().unwrap();
+
+        let mut removed_at = None;
+        while removed_at.is_none() {
+            processor.process_poll_event(Some(*TIMEOUT), |net_event| match net_event {
+                NetEvent::Connected(_, status) => {
+                    assert!(status);
+                    controller.send_owned(endpoint, vec![42; 32 * 1024 * 1024]);
+                    assert!(controller.remove_graceful(endpoint.resource_id(), GRACEFUL_TIMEOUT));
+                    removed_at = Some(Instant::now());
+                }
+                _ => unreachable!(),
+            });
+        }
+
+        // Without more events, the poll only wakes up by the timeout of the removal.
+        assert!(processor.poll_timeout().unwrap() <= GRACEFUL_TIMEOUT);
+        let mut disconnected = false;
+        while !disconnected {
+            processor.process_poll_event(None, |net_event| match net_event {
+                NetEvent::Disconnected(disconnected_endpoint) => {
+                    assert_eq!(disconnected_endpoint, endpoint);
+                    disconnected = true;
+                }
+                _ => unreachable!(),
+            });
+        }
+        assert!(removed_at.unwrap().elapsed() >= GRACEFUL_TIMEOUT);
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn accept_rate(transport: Transport) {
+        const CONNECTIONS: usize = 20;
+        let (controller, mut processor) = self::split();
+        let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        assert!(controller.set_accept_rate(listener_id, Some(AcceptRate::new(20.0, 5))));
+
+        for _ in 0..CONNECTIONS {
+            controller.connect(transport, addr).unwrap();
+        }
+
+        let start = Instant::now();
+        let mut accepted_times = Vec::new();
+        while accepted_times.len() < CONNECTIONS && start.elapsed() < *LOCALHOST_CONN_TIMEOUT {
+            processor.process_poll_event(Some(Duration::from_millis(10)), |net_event| {
+                if let NetEvent::Accepted(..) = net_event {
+                    accepted_times.push(start.elapsed());
+                }
+            });
+        }
+
+        // All the connections are accepted, but paced: 5 at once and 20 per second.
+        assert_eq!(accepted_times.len(), CONNECTIONS);
+        let paced = accepted_times.iter().filter(|time| **time > Duration::from_millis(400));
+        assert!(paced.count() >= 5);
+        assert!(accepted_times[CONNECTIONS - 1] > Duration::from_millis(600));
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    fn accept_rate_resume_without_events(transport: Transport) {
+        const CONNECTIONS: usize = 3;
+        let (controller, mut processor) = self::split();
+        let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        assert!(controller.set_accept_rate(listener_id, Some(AcceptRate::new(10.0, 1))));
+
+        for _ in 0..CONNECTIONS {
+            controller.connect(transport, addr).unwrap();
+        }
+
+        // The paused listener is out of the poll, it is resumed by the poll timeout.
+        let start = Instant::now();
+        let mut accepted = 0;
+        while accepted < CONNECTIONS {
+            processor.process_poll_event(None, |net_event| {
+                if let NetEvent::Accepted(..) = net_event {
+                    accepted += 1;
+                }
+            });
+        }
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // Paused again by the last accepted connection.
+        assert!(controller.remove(listener_id));
+    }
+
+    #[cfg(feature = "udp")]
+    #[test]
+    fn accept_rate_udp() {
+        let (controller, _processor) = self::split();
+        let (listener_id, _) = controller.listen(Transport::Udp, "127.0.0.1:0").unwrap();
+        assert!(!controller.set_accept_rate(listener_id, Some(AcceptRate::new(1.0, 1))));
+    }
+
+    #[cfg(feature = "udp")]
+    #[test]
+    fn listener_of_udp() {
+        let (controller, _processor) = self::split();
+        let (listener_id, addr) = controller.listen(Transport::Udp, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(Transport::Udp, addr).unwrap();
+
+        assert_eq!(controller.listener_of(endpoint), None);
+        let listener_endpoint = Endpoint::from_listener(listener_id, addr);
+        assert_eq!(controller.listener_of(listener_endpoint), Some(listener_id));
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn resolve_localhost_v4_only() {
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+
+        controller.set_resolve_preference(ResolvePreference::V4Only);
+        let host = format!("localhost:{}", addr.port());
+        let (endpoint, _) = controller.connect(Transport::FramedTcp, host).unwrap();
+        assert_eq!(endpoint.addr(), addr);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn resolve_v6_only_v4_host() {
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+
+        controller.set_resolve_preference(ResolvePreference::V6Only);
+        let err = controller.connect(Transport::FramedTcp, addr).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrNotAvailable);
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn successful_connection_sync(transport: Transport) {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+
+        let mut thread = NamespacedThread::spawn("test", move || {
+            let (endpoint, _) = controller.connect_sync(transport, addr).unwrap();
+            assert!(controller.is_ready(endpoint.resource_id()).unwrap());
+        });
+
+        processor.process_poll_events_until_timeout(*TIMEOUT, |_| ());
+
+        thread.join();
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn unreachable_connection(transport: Transport) {
+        let (controller, mut processor) = self::split();
+
+        // Ensure that addr is not using by other process
+        // because it takes some secs to be reusable.
+        let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        controller.remove(listener_id);
+
+        let (endpoint, _) = controller.connect(transport, addr).unwrap();
+        assert_eq!(controller.send(endpoint, &[42]), SendStatus::ResourceNotAvailable);
+        assert!(!controller.is_ready(endpoint.resource_id()).unwrap());
+
+        let mut was_disconnected = false;
+        processor.process_poll_events_until_timeout(*LOCALHOST_CONN_TIMEOUT, |net_event| {
+            match net_event {
+                NetEvent::Connected(net_endpoint, status) => {
+                    assert!(!status);
+                    assert_eq!(endpoint, net_endpoint);
+                    was_disconnected = true;
+                }
+                _ => unreachable!(),
+            }
+        });
+        assert!(was_disconnected);
+    }
+
+    #[cfg(all(feature = "tcp", feature = "websocket"))]
+    #[test]
+    fn connect_failed_targets() {
+        use std::collections::{HashMap, HashSet};
+
+        let (controller, mut processor) = self::split();
+        controller.set_connect_failed_events(true);
+
+        // Ensure that the ports are not used by other process.
+        let closed_port = || {
+            let (listener_id, addr) = controller.listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+            controller.remove(listener_id);
+            addr.port()
+        };
+        let host_name = format!("localhost:{}", closed_port());
+        let url = format!("ws://127.0.0.1:{}/path", closed_port());
+        let targets = vec![
+            (Transport::Tcp, RemoteAddr::Socket(([127, 0, 0, 1], closed_port()).into())),
+            (Transport::FramedTcp, RemoteAddr::Socket(([127, 0, 0, 1], closed_port()).into())),
+            (Transport::FramedTcp, RemoteAddr::Str(host_name)),
+            (Transport::Ws, RemoteAddr::Str(url)),
+        ];
+
+        let mut expected = HashMap::new();
+        for (transport, target) in targets {
+            let (endpoint, _) = controller.connect(transport, target.clone()).unwrap();
+            expected.insert(endpoint, target);
+        }
+
+        let mut refused = HashSet::new();
+        let mut failed = HashMap::new();
+        let deadline = Instant::now() + *LOCALHOST_CONN_TIMEOUT;
+        while failed.len() < expected.len() && Instant::now() < deadline {
+            processor.process_poll_event(Some(*TIMEOUT), |net_event| match net_event {
+                NetEvent::Connected(endpoint, status) => {
+                    assert!(!status);
+                    assert!(refused.insert(endpoint));
+                }
+                NetEvent::ConnectFailed(endpoint, target) => {
+                    assert!(refused.contains(&endpoint)); // Generated after Connected
+                    assert!(failed.insert(endpoint, target.clone()).is_none());
+                }
+                _ => unreachable!(),
+            });
+        }
+        assert_eq!(failed, expected);
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn unreachable_connection_sync(transport: Transport) {
+        let (controller, mut processor) = self::split();
+
+        // Ensure that addr is not using by other process
+        // because it takes some secs to be reusable.
+        let (listener_id, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        controller.remove(listener_id);
+
+        let mut thread = NamespacedThread::spawn("test", move || {
+            let err = controller.connect_sync(transport, addr).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        });
+
+        processor.process_poll_events_until_timeout(*LOCALHOST_CONN_TIMEOUT, |_| ());
+
+        thread.join();
+    }
+
+    #[cfg(feature = "in-process")]
+    #[test]
+    fn in_process_named_listener() {
+        use crate::adapters::in_process::{InProcessListenConfig};
+
+        let (controller, mut processor) = self::split();
+        let config = InProcessListenConfig::default().with_name("named-listener".into());
+        let listen = TransportListen::InProcess(config.clone());
+        let (listener_id, addr) = controller.listen_with(listen, "127.0.0.1:0").unwrap();
+
+        let listen = TransportListen::InProcess(config.clone());
+        let err = controller.listen_with(listen, "127.0.0.1:0").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        let err = controller.connect(Transport::InProcess, "inproc://other-name").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let name_addr = "inproc://named-listener";
+        let (endpoint, _) = controller.connect(Transport::InProcess, name_addr).unwrap();
+        assert_eq!(endpoint.addr(), addr);
+
+        let mut received = Vec::new();
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                assert_eq!(controller.send(endpoint, b"data"), SendStatus::Sent);
+            }
+            NetEvent::Accepted(_, id) => assert_eq!(id, listener_id),
+            NetEvent::Message(_, data) => received.push(data.to_vec()),
+            _ => unreachable!(),
+        });
+        assert_eq!(received, vec![b"data".to_vec()]);
+
+        // The name is released with the listener.
+        assert!(controller.remove(listener_id));
+        controller.listen_with(TransportListen::InProcess(config), "127.0.0.1:0").unwrap();
+    }
+
+    #[test]
+    fn create_remove_listener() {
+        let (controller, mut processor) = self::split();
+        let (listener_id, _) = controller.listen(Transport::Tcp, "127.0.0.1:0").unwrap();
         assert!(controller.remove(listener_id)); // Do not generate an event
         assert!(!controller.remove(listener_id));
 
@@ -531,4 +2979,78 @@ mod tests {
         });
         assert!(was_accepted);
     }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn ids_not_reused_on_reconnection() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+
+        let mut previous: Option<(ResourceId, ResourceId)> = None;
+        for _ in 0..3 {
+            let (endpoint, _) = controller.connect(Transport::FramedTcp, addr).unwrap();
+            let mut accepted = None;
+            processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+                NetEvent::Connected(_, status) => assert!(status),
+                NetEvent::Accepted(endpoint, _) => {
+                    assert!(controller.remove(endpoint.resource_id()));
+                    accepted = Some(endpoint.resource_id());
+                }
+                NetEvent::Disconnected(disconnected) => assert_eq!(disconnected, endpoint),
+                _ => unreachable!(),
+            });
+            let ids = (endpoint.resource_id(), accepted.unwrap());
+            assert_eq!(controller.is_ready(ids.0), None);
+
+            // The new connection gets fresh ids, higher than the ones of the previous one.
+            if let Some((connected_id, accepted_id)) = previous {
+                assert!(ids.0.base_value() > connected_id.base_value());
+                assert!(ids.1.base_value() > accepted_id.base_value());
+            }
+            previous = Some(ids);
+        }
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    #[cfg_attr(feature = "in-process", test_case(Transport::InProcess))]
+    fn listen_addr_in_use_error(transport: Transport) {
+        let (controller, _processor) = self::split();
+        let (_, addr) = controller.listen(transport, "127.0.0.1:0").unwrap();
+        match controller.listen(transport, addr).unwrap_err() {
+            NetworkError::AddrInUse(err) => assert_eq!(err.kind(), io::ErrorKind::AddrInUse),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[cfg(feature = "udp")]
+    #[test]
+    fn send_message_too_large_error() {
+        let (controller, mut processor) = self::split();
+        let (_, addr) = controller.listen(Transport::Udp, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = controller.connect(Transport::Udp, addr).unwrap();
+
+        let mut sent = false;
+        processor.process_poll_events_until_timeout(*TIMEOUT, |net_event| match net_event {
+            NetEvent::Connected(_, status) => {
+                assert!(status);
+                let data = vec![0; Transport::Udp.max_message_size() + 1];
+                let result = controller.send(endpoint, &data).into_result();
+                assert!(matches!(result, Err(NetworkError::MessageTooLarge)));
+                let err = io::Error::from(result.unwrap_err());
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+                assert!(controller.send(endpoint, &[42]).into_result().is_ok());
+                sent = true;
+            }
+            NetEvent::Message(..) => (),
+            _ => unreachable!(),
+        });
+        assert!(sent);
+
+        assert!(controller.remove(endpoint.resource_id()));
+        let result = controller.send(endpoint, &[42]).into_result();
+        assert!(matches!(result, Err(NetworkError::ResourceNotFound)));
+    }
 }
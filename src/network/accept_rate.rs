@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+/// Maximum rate at which a listener accepts new connections.
+/// It works as a token bucket: up to `burst` connections can be accepted at once,
+/// and then `per_second` connections are accepted each second.
+/// The connections beyond the rate are not dropped, they wait in the OS listener backlog
+/// until they can be accepted.
+/// It is set by [`crate::network::NetworkController::set_accept_rate()`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AcceptRate {
+    per_second: f64,
+    burst: u32,
+}
+
+impl AcceptRate {
+    /// Creates an accept rate of `per_second` connections that allows bursts
+    /// of `burst` connections.
+    /// It panics if `per_second` is not a positive value or `burst` is 0.
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        assert!(per_second > 0.0, "The accept rate must be positive");
+        assert!(burst > 0, "The accept burst must be greater than 0");
+        Self { per_second, burst }
+    }
+
+    /// Connections accepted each second.
+    pub fn per_second(&self) -> f64 {
+        self.per_second
+    }
+
+    /// Connections that can be accepted at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+pub(crate) struct AcceptLimiter {
+    rate: AcceptRate,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl AcceptLimiter {
+    pub fn new(rate: AcceptRate) -> Self {
+        Self { rate, tokens: rate.burst as f64, last_refill: Instant::now() }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate.per_second).min(self.rate.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Number of connections that can be accepted now.
+    pub fn available(&mut self, now: Instant) -> usize {
+        self.refill(now);
+        self.tokens.floor() as usize
+    }
+
+    pub fn consume(&mut self, accepted: usize) {
+        self.tokens -= accepted as f64;
+    }
+
+    /// Time when the next connection can be accepted.
+    pub fn next_available(&self) -> Instant {
+        let missing = (1.0 - self.tokens).max(0.0);
+        self.last_refill + Duration::from_secs_f64(missing / self.rate.per_second)
+    }
+}
@@ -1,7 +1,9 @@
 use crate::network::transport::{TransportConnect, TransportListen};
 
 use super::remote_addr::{RemoteAddr};
-use super::poll::{Readiness};
+use super::poll::{Readiness, ReadinessNotifier};
+use super::framing::{FramingStats};
+use super::ticket::{SendCompletion};
 
 use mio::event::{Source};
 
@@ -31,6 +33,14 @@ pub trait Resource: Send + Sync {
     /// one of them as a base for your non-blocking transport.
     /// See [`Source`].
     fn source(&mut self) -> &mut dyn Source;
+
+    /// Called once the resource is registered, with the notifier of its readiness events.
+    /// It is used by the resources whose source is not an OS resource, as a channel in memory,
+    /// that must report by themselves when they can be read or written.
+    ///
+    /// The **implementator** only needs to implement this function if the `Source` of the
+    /// resource is never made ready by the poll. By default, the notifier is discarded.
+    fn set_notifier(&mut self, _notifier: ReadinessNotifier) {}
 }
 
 /// Plain struct used as a returned value of [`Remote::connect_with()`]
@@ -58,7 +68,9 @@ pub struct ListeningInfo<L: Local> {
 /// call can return.
 /// The library do not encourage to perform the check of this status for each `send()` call,
 /// only in that cases where you need extra information about how the sending method was.
+/// New variants can be added in future versions.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub enum SendStatus {
     /// This status is received when the entire data has been sent.
     /// It does not guarantees that the packet have been successfully received by the endpoint.
@@ -72,11 +84,83 @@ pub enum SendStatus {
     /// It means that the message could not be sent by the specified `ResourceId`.
     /// This implies that a [`crate::network::NetEvent::Disconnected`] has happened or that
     /// the resource never existed.
+    /// It is also returned if the connection was closed by the other side and the
+    /// [`crate::network::NetEvent::Disconnected`] has not been processed yet.
+    /// Any transport returns it in these cases instead of panicking.
     ResourceNotFound,
 
     /// The resource can not perform the required send operation.
     /// Usually this is due because it is performing the handshake.
     ResourceNotAvailable,
+
+    /// The resource was created as [`Direction::ReadOnly`], it can not send data.
+    NotWritable,
+
+    /// The outbound queue of the resource is full and the message was discarded.
+    /// See [`crate::adapters::framed_tcp::FramedTcpConnectConfig::with_queue_capacity()`].
+    Dropped,
+
+    /// The message was queued after discarding the oldest queued messages,
+    /// because the outbound queue of the resource was full.
+    /// See [`crate::adapters::framed_tcp::DropPolicy::DropOldest`].
+    SentDroppingOldest,
+}
+
+/// Directions in which a resource transfers data.
+/// It decides the readiness events that the resource is registered for,
+/// so a resource that only sends or only receives does not waste the resources
+/// on watching the other direction.
+/// It is set by the transport configurations that support it,
+/// as [`crate::adapters::udp::UdpConnectConfig::with_direction()`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Direction {
+    /// The resource sends and receives data.
+    #[default]
+    ReadWrite,
+
+    /// The resource only receives data.
+    /// Sending by it returns [`SendStatus::NotWritable`].
+    ReadOnly,
+
+    /// The resource only sends data.
+    /// It is not registered to read, so no [`crate::network::NetEvent::Message`] is generated
+    /// by it, even if data arrives to its socket.
+    WriteOnly,
+}
+
+impl Direction {
+    /// Returns `true` if the resource receives data.
+    pub fn can_read(self) -> bool {
+        self != Direction::WriteOnly
+    }
+
+    /// Returns `true` if the resource sends data.
+    pub fn can_write(self) -> bool {
+        self != Direction::ReadOnly
+    }
+}
+
+/// Kind of a control frame received by protocols that have them, as the WebSocket
+/// ping and pong frames.
+/// See [`crate::network::NetEvent::Control`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlFrame {
+    /// A ping frame, that the other side must answer with a pong frame.
+    Ping,
+
+    /// A pong frame, received as the answer to a ping frame.
+    Pong,
+}
+
+/// Used as a parameter callback in [`Remote::receive_frames()`]
+pub enum ReceivedFrame<'a> {
+    /// The data of a message.
+    /// It will produce a [`crate::network::NetEvent::Message`] event.
+    Message(&'a [u8]),
+
+    /// A control frame with its payload.
+    /// It will produce a [`crate::network::NetEvent::Control`] event.
+    Control(ControlFrame, &'a [u8]),
 }
 
 /// Returned as a result of [`Remote::receive()`]
@@ -92,6 +176,12 @@ pub enum ReadStatus {
     /// Usually, this status is returned if the resource receives
     /// a [`std::io::ErrorKind::WouldBlock`].
     WaitNextEvent,
+
+    /// This status must be returned when the resource found an error that does not imply
+    /// a disconnection, as an ICMP error reported in a connected UDP socket.
+    /// A [`crate::network::NetEvent::Error`] will be generated and the resource will be kept.
+    /// Like [`ReadStatus::WaitNextEvent`], the resource must be read until it would block.
+    Error(io::ErrorKind),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -143,12 +233,71 @@ pub trait Remote: Resource + Sized {
     /// For most of the cases it means read until the network resource returns `WouldBlock`.
     fn receive(&self, process_data: impl FnMut(&[u8])) -> ReadStatus;
 
+    /// Similar to [`Remote::receive()`] but the resource can also process control frames,
+    /// calling `process_frame` with a [`ReceivedFrame::Control`].
+    /// This is the function called by the upper level when the resource has data to read.
+    ///
+    /// The **implementator** only needs to implement this function if the protocol
+    /// has control frames to deliver. By default, it calls [`Remote::receive()`]
+    /// and processes its data as messages.
+    fn receive_frames(&self, mut process_frame: impl FnMut(ReceivedFrame<'_>)) -> ReadStatus {
+        self.receive(|data| process_frame(ReceivedFrame::Message(data)))
+    }
+
     /// Sends raw data from a resource.
     /// The resource must be *ready* to receive this call.
     /// The **implementator** is in charge to send the entire `data`.
     /// The [`SendStatus`] will contain the status of this attempt.
     fn send(&self, data: &[u8]) -> SendStatus;
 
+    /// Similar to [`Remote::send()`] but taking the ownership of the data.
+    /// Resources that queue the data before writing it can store the buffer
+    /// without copying it.
+    /// By default, it sends the data as [`Remote::send()`].
+    fn send_owned(&self, data: Vec<u8>) -> SendStatus {
+        self.send(&data)
+    }
+
+    /// Similar to [`Remote::send_owned()`] but calling [`SendCompletion::complete()`]
+    /// once the last byte of the data is written.
+    /// Dropping the `completion` without completing it marks the message as failed.
+    ///
+    /// The **implementator** only needs to implement this function if the resource
+    /// queues the data before writing it. By default, it sends the data as
+    /// [`Remote::send_owned()`] and completes if it was sent.
+    fn send_tracked(&self, data: Vec<u8>, completion: SendCompletion) -> SendStatus {
+        let status = self.send_owned(data);
+        if matches!(status, SendStatus::Sent | SendStatus::SentDroppingOldest) {
+            completion.complete();
+        }
+        status
+    }
+
+    /// Sends a ping control frame with `data` as payload.
+    ///
+    /// The **implementator** must **only** implement this function if the protocol
+    /// has ping frames.
+    fn send_ping(&self, _data: &[u8]) -> SendStatus {
+        panic!("Adapter not configured to send ping frames")
+    }
+
+    /// Returns the resume token presented by the peer when the connection was established,
+    /// if any.
+    ///
+    /// The **implementator** only needs to implement this function if the protocol
+    /// has resume tokens. By default, it returns `None`.
+    fn resume_token(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns the state of the framing of the received data. It is called from any thread.
+    ///
+    /// The **implementator** only needs to implement this function if the adapter
+    /// assembles the frames of the protocol. By default, it returns `None`.
+    fn framing_stats(&self) -> Option<FramingStats> {
+        None
+    }
+
     /// Called when a `Remote` is created (explicity of by a listener)
     /// and it is not consider ready yet.
     /// A remote resource **is considered ready** when it is totally connected
@@ -162,6 +311,26 @@ pub trait Remote: Resource + Sized {
     /// returns a `PendingStatus::Ready` or `PendingStatus::Disconnected`.
     fn pending(&self, readiness: Readiness) -> PendingStatus;
 
+    /// Called after [`Remote::pending()`] returns [`PendingStatus::Incomplete`].
+    /// If the resource has replaced its source meanwhile, as when it connects to other address
+    /// to follow a redirection of its handshake, it must call `replace` with the previous
+    /// source and the new one, so the previous one is removed from the poll
+    /// and the new one is registered in its place.
+    ///
+    /// The **implementator** only needs to implement this function if the resource can
+    /// replace its source. By default, it does nothing.
+    fn replace_source(&self, _replace: &mut dyn FnMut(&mut dyn Source, &mut dyn Source)) {}
+
+    /// Returns the reason why the connection could not be established,
+    /// once [`Remote::pending()`] has returned [`PendingStatus::Disconnected`].
+    /// It is the error returned by [`crate::network::NetworkController::connect_sync()`].
+    ///
+    /// The **implementator** only needs to implement this function if the protocol can
+    /// give more details than a refused connection. By default, it returns `None`.
+    fn connect_error(&self) -> Option<io::Error> {
+        None
+    }
+
     /// The resource is available to write.
     /// It must be *ready* to receive this call.
     /// Here the **implementator** optionally can try to write any pending data.
@@ -171,6 +340,51 @@ pub trait Remote: Resource + Sized {
     fn ready_to_write(&self) -> bool {
         true
     }
+
+    /// Used by resources that defer the writing of their data to the poll thread.
+    /// If it returns `true`, the resource still has data to write and it is able to write it
+    /// without waiting a new write readiness. In that case, [`Remote::ready_to_write()`]
+    /// will be called again in the next poll iteration, interleaved with the processing
+    /// of the rest of resources.
+    /// It is checked after each [`Remote::send()`] and [`Remote::ready_to_write()`] call.
+    fn wants_write(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if the resource has data queued that has not been written yet.
+    /// It is used to wait for that data before closing the resource in a graceful removal.
+    fn has_queued_data(&self) -> bool {
+        false
+    }
+
+    /// Returns the bytes of the data queued that has not been written yet,
+    /// or `None` if the resource does not queue the data.
+    /// It is checked after each [`Remote::send()`] and [`Remote::ready_to_write()`] call
+    /// to generate the events of [`crate::network::NetworkController::set_watermarks()`].
+    ///
+    /// The **implementator** only needs to implement this function if the resource
+    /// queues the data before writing it. By default, it returns `None`.
+    fn queued_bytes(&self) -> Option<usize> {
+        None
+    }
+
+    /// Called before removing the resource in a graceful removal, once its queued data
+    /// has been written.
+    /// Here the **implementator** can perform the close procedure of the protocol,
+    /// as sending a *FIN* in TCP or a close frame in WebSocket.
+    fn close(&self) {}
+
+    /// Directions in which the resource transfers data.
+    /// A [`Direction::WriteOnly`] remote is only registered for write readiness,
+    /// and [`Remote::receive()`] is never called for it.
+    /// The rest are registered for both readiness, since the write readiness
+    /// is used to establish the connection.
+    ///
+    /// The **implementator** only needs to implement this function if the transport
+    /// allows to configure it. By default, it is [`Direction::ReadWrite`].
+    fn direction(&self) -> Direction {
+        Direction::ReadWrite
+    }
 }
 
 /// Used as a parameter callback in [`Local::accept()`]
@@ -217,6 +431,32 @@ pub trait Local: Resource + Sized {
     /// resource returns `WouldBlock`.
     fn accept(&self, accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>));
 
+    /// Similar to [`Local::accept()`] but accepting at most `limit` remotes.
+    /// It is used to limit the accept rate of the listener.
+    /// It must return `true` if the limit was reached, since it could still be pending
+    /// connections to accept. In that case, the next call will be performed
+    /// when the rate allows it, without waiting a new event.
+    ///
+    /// The **implementator** only needs to implement this function if the local resource
+    /// accepts remotes. By default, the limit is ignored.
+    fn accept_limited(
+        &self,
+        _limit: usize,
+        accept_remote: impl FnMut(AcceptedType<'_, Self::Remote>),
+    ) -> bool {
+        self.accept(accept_remote);
+        false
+    }
+
+    /// Calls `update` with the source of the local resource.
+    /// It is used to remove the source from the poll while the listener is paused by its
+    /// accept rate, and to register it again when the listener is resumed.
+    ///
+    /// The **implementator** only needs to implement this function if the local resource
+    /// accepts remotes. By default, `update` is not called, so a paused listener keeps
+    /// receiving the events of the poll, that are ignored until it is resumed.
+    fn update_source(&self, _update: &mut dyn FnMut(&mut dyn Source)) {}
+
     /// Sends a raw data from a resource.
     /// Similar to [`Remote::send()`] but the resource that sends the data is a `Local`.
     /// This behaviour usually happens when the transport to implement is not connection oriented.
@@ -226,4 +466,14 @@ pub trait Local: Resource + Sized {
     fn send_to(&self, _addr: SocketAddr, _data: &[u8]) -> SendStatus {
         panic!("Adapter not configured to send messages directly from the local resource")
     }
+
+    /// Directions in which the local resource transfers data.
+    /// A [`Direction::WriteOnly`] local is not registered for read readiness,
+    /// so [`Local::accept()`] is never called for it.
+    ///
+    /// The **implementator** only needs to implement this function if the transport
+    /// allows to configure it. By default, it is [`Direction::ReadWrite`].
+    fn direction(&self) -> Direction {
+        Direction::ReadWrite
+    }
 }
@@ -1,23 +1,45 @@
 use super::endpoint::{Endpoint};
 use super::resource_id::{ResourceId, ResourceType};
-use super::poll::{Poll, Readiness};
+use super::poll::{Poll, PollWaker, Readiness};
 use super::registry::{ResourceRegistry, Register};
 use super::remote_addr::{RemoteAddr};
 use super::adapter::{Adapter, Remote, Local, SendStatus, AcceptedType, ReadStatus, PendingStatus};
-use super::transport::{TransportConnect, TransportListen};
+use super::adapter::{ControlFrame, ReceivedFrame, Direction};
+use super::transport::{Transport, TransportConnect, TransportListen};
+use super::accept_rate::{AcceptRate, AcceptLimiter};
+use super::throughput::{ThroughputSample};
+use super::framing::{FramingStats};
+use super::group::{GroupId};
+use super::watermarks::{Watermarks, Watermark, WatermarkTracker};
+use super::ticket::{SendCompletion};
+
+use crate::util::thread::{OTHER_THREAD_ERR};
+
+use mio::{Interest};
 
 use std::net::{SocketAddr};
 use std::sync::{
-    Arc,
-    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
 };
+use std::collections::{HashMap, HashSet};
 use std::io::{self};
-
-#[cfg(doctest)]
-use super::transport::{Transport};
+use std::time::{Duration, Instant};
 
 /// Enum used to describe a network event that an internal transport adapter has produced.
+/// New variants can be added in future versions, so a `match` over it needs a wildcard arm.
+#[non_exhaustive]
 pub enum NetEvent<'a> {
+    /// A connection has been initiated.
+    /// This event is only generated after a [`crate::network::NetworkController::connect()`]
+    /// call, when it is enabled by
+    /// [`crate::network::NetworkController::set_connecting_events()`].
+    /// The event contains the endpoint of the connection and the address given to `connect()`.
+    /// It always precedes the rest of events of the endpoint,
+    /// so the connection goes from `Connecting` to [`NetEvent::Connected`], whatever its result.
+    /// If the endpoint is removed before the event is processed, it is not generated.
+    Connecting(Endpoint, &'a RemoteAddr),
+
     /// Connection result.
     /// This event is only generated after a [`crate::network::NetworkController::connect()`]
     /// call.
@@ -31,6 +53,16 @@ pub enum NetEvent<'a> {
     /// Since this handshake could fail, the boolean could be `false`.
     Connected(Endpoint, bool),
 
+    /// A connection could not be established.
+    /// This event is only generated when it is enabled by
+    /// [`crate::network::NetworkController::set_connect_failed_events()`].
+    /// It is generated just after the [`NetEvent::Connected`] event with the `false` result,
+    /// with the address given to [`crate::network::NetworkController::connect()`],
+    /// as it was given, before being resolved.
+    /// It identifies the target of the connection when the address of the endpoint
+    /// is not enough, as when connecting by a host name or by the url of a *WebSocket*.
+    ConnectFailed(Endpoint, &'a RemoteAddr),
+
     /// New endpoint has been accepted by a listener and considered ready to use.
     /// The event contains the resource id of the listener that accepted this connection.
     ///
@@ -54,49 +86,203 @@ pub enum NetEvent<'a> {
     /// Note that this event will only be generated by connection-oriented transports as *TCP*.
     /// *UDP*, for example, is NOT connection-oriented, and the event can no be detected.
     Disconnected(Endpoint),
+
+    /// The OS has reported an error for this endpoint that does not close the resource.
+    /// Currently, it is only generated by *UDP* endpoints created by
+    /// [`crate::network::NetworkController::connect()`], when the peer answers with an ICMP error.
+    /// If the peer port is unreachable, the error kind is
+    /// [`std::io::ErrorKind::ConnectionRefused`].
+    ///
+    /// The resource is not removed, you can keep sending data or remove it explicitly.
+    ///
+    /// It is also generated with [`std::io::ErrorKind::TimedOut`] when a deadline of the
+    /// endpoint expires, see [`crate::network::NetworkController::set_read_deadline()`].
+    /// In this case, the resource is closed, followed by a [`NetEvent::Disconnected`] event.
+    Error(Endpoint, io::ErrorKind),
+
+    /// A control frame has been received with the payload of this event.
+    /// It is only generated by *WebSocket* endpoints when the control frames are enabled
+    /// in their configuration.
+    /// See [`crate::adapters::ws::WsConnectConfig::with_control_frames()`].
+    Control(Endpoint, ControlFrame, &'a [u8]),
+
+    /// Data exchanged by a connection since the previous sample.
+    /// It is only generated, periodically for each connection, when the sampling is enabled.
+    /// See [`crate::network::NetworkController::set_throughput_sampling()`].
+    Throughput(ThroughputSample),
+
+    /// The network of the host has changed, as its interfaces or addresses.
+    /// It is not related to any endpoint: the connections could have been silently broken
+    /// by the change, and it can be used to re-establish them.
+    /// This event is only generated when the changes are monitored by
+    /// [`crate::network::NetworkController::monitor_network_changes()`]
+    /// or notified by [`crate::network::NetworkController::notify_network_change()`].
+    NetworkChanged,
+
+    /// The data queued by a connection to be written has crossed one of its watermarks:
+    /// the source of the data should be paused by a [`Watermark::High`],
+    /// and can be resumed by a [`Watermark::Low`].
+    /// It is only generated for the connections with watermarks, see
+    /// [`crate::network::NetworkController::set_watermarks()`].
+    /// A `High` is always followed by a `Low` before other `High`,
+    /// unless the connection is removed or disconnected.
+    Watermark(Endpoint, Watermark),
 }
 
 impl std::fmt::Debug for NetEvent<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let string = match self {
+            Self::Connecting(endpoint, target) => format!("Connecting({endpoint}, {target})"),
             Self::Connected(endpoint, status) => format!("Connected({endpoint}, {status})"),
+            Self::ConnectFailed(endpoint, target) => format!("ConnectFailed({endpoint}, {target})"),
             Self::Accepted(endpoint, id) => format!("Accepted({endpoint}, {id})"),
             Self::Message(endpoint, data) => format!("Message({}, {})", endpoint, data.len()),
             Self::Disconnected(endpoint) => format!("Disconnected({endpoint})"),
+            Self::Error(endpoint, kind) => format!("Error({endpoint}, {kind:?})"),
+            Self::Control(endpoint, frame, data) => {
+                format!("Control({}, {:?}, {})", endpoint, frame, data.len())
+            }
+            Self::Throughput(sample) => format!("Throughput({sample:?})"),
+            Self::NetworkChanged => "NetworkChanged".to_string(),
+            Self::Watermark(endpoint, watermark) => format!("Watermark({endpoint}, {watermark:?})"),
         };
         write!(f, "NetEvent::{string}")
     }
 }
 
+/// How a connection created by [`ActionController::connect_with()`] is reported.
+#[derive(Clone, Copy)]
+pub struct ConnectOptions {
+    /// A [`NetEvent::Connecting`] is generated before any other event of the connection.
+    pub announce: bool,
+
+    /// A [`NetEvent::ConnectFailed`] is generated if the connection fails.
+    pub announce_failure: bool,
+
+    /// The caller waits for the connection, keeping its error, as `connect_sync()` does.
+    pub sync: bool,
+}
+
 pub trait ActionController: Send + Sync {
     fn connect_with(
         &self,
         config: TransportConnect,
         addr: RemoteAddr,
+        target: RemoteAddr,
+        options: ConnectOptions,
     ) -> io::Result<(Endpoint, SocketAddr)>;
     fn listen_with(
         &self,
         config: TransportListen,
         addr: SocketAddr,
+        group: Option<ResourceId>,
     ) -> io::Result<(ResourceId, SocketAddr)>;
     fn send(&self, endpoint: Endpoint, data: &[u8]) -> SendStatus;
+    fn send_owned(&self, endpoint: Endpoint, data: Vec<u8>) -> SendStatus;
+    fn send_tracked(
+        &self,
+        endpoint: Endpoint,
+        data: Vec<u8>,
+        completion: SendCompletion,
+    ) -> SendStatus;
+    fn send_ping(&self, endpoint: Endpoint, data: &[u8]) -> SendStatus;
     fn remove(&self, id: ResourceId) -> bool;
+    fn remove_graceful(&self, id: ResourceId, timeout: Duration) -> bool;
+    fn reject(&self, id: ResourceId) -> bool;
+    fn resource_ids(&self, resource_type: ResourceType) -> Vec<ResourceId>;
+    fn connections(&self) -> Vec<Endpoint>;
+    fn take_connect_error(&self, id: ResourceId) -> Option<io::Error>;
+    fn join_group(&self, id: ResourceId, group: GroupId) -> bool;
+    fn leave_group(&self, id: ResourceId, group: GroupId) -> bool;
+    fn group_members(&self, group: GroupId) -> Vec<Endpoint>;
     fn is_ready(&self, id: ResourceId) -> Option<bool>;
+    fn listener_of(&self, id: ResourceId) -> Option<ResourceId>;
+    fn connected_at(&self, id: ResourceId) -> Option<Instant>;
+    fn last_activity(&self, id: ResourceId) -> Option<Instant>;
+    fn resume_token(&self, id: ResourceId) -> Option<String>;
+    fn framing_stats(&self, id: ResourceId) -> Option<FramingStats>;
+    fn set_read_deadline(&self, id: ResourceId, deadline: Option<Instant>) -> bool;
+    fn set_write_deadline(&self, id: ResourceId, deadline: Option<Instant>) -> bool;
+    fn set_accept_rate(&self, id: ResourceId, rate: Option<AcceptRate>) -> bool;
+    fn set_watermarks(&self, id: ResourceId, watermarks: Option<Watermarks>) -> bool;
 }
 
 pub trait EventProcessor: Send + Sync {
     fn process(&self, id: ResourceId, readiness: Readiness, callback: &mut dyn FnMut(NetEvent<'_>));
+
+    /// Process the work that the resources deferred to the poll thread.
+    /// Returns `true` if there is still deferred work to process.
+    fn process_deferred(&self, callback: &mut dyn FnMut(NetEvent<'_>)) -> bool;
+
+    /// Generates a [`NetEvent::Throughput`] for each established connection,
+    /// resetting its counters. `interval` is the time elapsed since the previous sample.
+    fn sample_throughput(&self, interval: Duration, callback: &mut dyn FnMut(NetEvent<'_>));
+
+    /// Returns the earliest deadline of the resources, if any: a read or write deadline,
+    /// the timeout of a graceful removal, or the resume time of a listener paused by its
+    /// accept rate.
+    /// The expired deadlines are processed by [`EventProcessor::process_deferred()`].
+    fn next_deadline(&self) -> Option<Instant>;
+}
+
+/// Encodes an optional [`Instant`] in an [`AtomicU64`], so it can be updated without a lock,
+/// as the nanoseconds elapsed since a base instant.
+struct TimeOffset;
+
+impl TimeOffset {
+    const NONE: u64 = 0;
+
+    fn encode(base: Instant, instant: Instant) -> u64 {
+        // Shifted by one to keep the zero for NONE.
+        instant.saturating_duration_since(base).as_nanos() as u64 + 1
+    }
+
+    fn decode(base: Instant, offset: u64) -> Option<Instant> {
+        offset.checked_sub(1).map(|nanos| base + Duration::from_nanos(nanos))
+    }
 }
 
 struct RemoteProperties {
     peer_addr: SocketAddr,
     local: Option<ResourceId>,
+    target: Option<RemoteAddr>, // Address requested to connect, if it was not accepted.
+    sync: bool, // Connected by connect_sync(), that waits for the connection error.
+    announce_failure: bool, // A NetEvent::ConnectFailed is generated if the connection fails.
     ready: AtomicBool,
+    closing: AtomicBool,
+    connecting: AtomicBool, // The NetEvent::Connecting has not been generated yet.
+    groups: Mutex<HashSet<GroupId>>,
+    created_at: Instant,
+    connected_at: AtomicU64, // TimeOffset from created_at.
+    last_activity: AtomicU64, // TimeOffset from created_at.
+    read_deadline: Mutex<Option<Instant>>,
+    write_deadline: Mutex<Option<Instant>>,
+    watermarks: Mutex<Option<WatermarkTracker>>,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
 }
 
 impl RemoteProperties {
-    fn new(peer_addr: SocketAddr, local: Option<ResourceId>) -> Self {
-        Self { peer_addr, local, ready: AtomicBool::new(false) }
+    fn new(peer_addr: SocketAddr, local: Option<ResourceId>, target: Option<RemoteAddr>) -> Self {
+        Self {
+            peer_addr,
+            local,
+            target,
+            sync: false,
+            announce_failure: false,
+            ready: AtomicBool::new(false),
+            closing: AtomicBool::new(false),
+            connecting: AtomicBool::new(false),
+            groups: Mutex::new(HashSet::new()),
+            created_at: Instant::now(),
+            connected_at: AtomicU64::new(TimeOffset::NONE),
+            last_activity: AtomicU64::new(TimeOffset::NONE),
+            read_deadline: Mutex::new(None),
+            write_deadline: Mutex::new(None),
+            watermarks: Mutex::new(None),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+        }
     }
 
     pub fn is_ready(&self) -> bool {
@@ -104,15 +290,90 @@ impl RemoteProperties {
     }
 
     pub fn mark_as_ready(&self) {
+        let now = TimeOffset::encode(self.created_at, Instant::now());
+        self.connected_at.store(now, Ordering::Relaxed);
+        self.last_activity.store(now, Ordering::Relaxed);
         self.ready.store(true, Ordering::Relaxed);
     }
+
+    /// Registers that data has been sent or received by the resource.
+    pub fn mark_activity(&self) {
+        let now = TimeOffset::encode(self.created_at, Instant::now());
+        self.last_activity.store(now, Ordering::Relaxed);
+    }
+
+    pub fn count_received(&self, bytes: usize) {
+        self.bytes_in.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn count_sent(&self, bytes: usize) {
+        self.bytes_out.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the bytes received and sent since the previous call.
+    pub fn take_throughput(&self) -> (u64, u64) {
+        (self.bytes_in.swap(0, Ordering::Relaxed), self.bytes_out.swap(0, Ordering::Relaxed))
+    }
+
+    pub fn connected_at(&self) -> Option<Instant> {
+        TimeOffset::decode(self.created_at, self.connected_at.load(Ordering::Relaxed))
+    }
+
+    pub fn last_activity(&self) -> Option<Instant> {
+        TimeOffset::decode(self.created_at, self.last_activity.load(Ordering::Relaxed))
+    }
+
+    /// Returns the earliest of the read and write deadlines.
+    pub fn deadline(&self) -> Option<Instant> {
+        let read_deadline = *self.read_deadline.lock().expect(OTHER_THREAD_ERR);
+        let write_deadline = *self.write_deadline.lock().expect(OTHER_THREAD_ERR);
+        match (read_deadline, write_deadline) {
+            (Some(read_deadline), Some(write_deadline)) => Some(read_deadline.min(write_deadline)),
+            (read_deadline, write_deadline) => read_deadline.or(write_deadline),
+        }
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::Relaxed)
+    }
+
+    pub fn mark_as_closing(&self) {
+        self.closing.store(true, Ordering::Relaxed);
+    }
+
+    pub fn mark_as_connecting(&self) {
+        self.connecting.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` only once if the remote was marked as connecting.
+    pub fn take_connecting(&self) -> bool {
+        self.connecting.swap(false, Ordering::Relaxed)
+    }
 }
 
-struct LocalProperties;
+struct LocalProperties {
+    accept_limiter: Mutex<Option<AcceptLimiter>>,
+    group: Option<ResourceId>, // Listener that represents the group, if it is not this one.
+}
+
+impl LocalProperties {
+    fn new(group: Option<ResourceId>) -> Self {
+        Self { accept_limiter: Mutex::new(None), group }
+    }
+}
 
 pub struct Driver<R: Remote, L: Local> {
     remote_registry: Arc<ResourceRegistry<R, RemoteProperties>>,
     local_registry: Arc<ResourceRegistry<L, LocalProperties>>,
+    deferred_writes: Arc<Mutex<HashSet<ResourceId>>>,
+    // Graceful removals, with its deadlines and if the resources were already closed.
+    closing: Arc<Mutex<HashMap<ResourceId, (Instant, bool)>>>,
+    paused_listeners: Arc<Mutex<HashMap<ResourceId, Instant>>>, // Listeners and its resume time.
+    with_deadlines: Arc<Mutex<HashSet<ResourceId>>>, // Remotes with a read or write deadline.
+    connecting: Arc<Mutex<Vec<ResourceId>>>, // Remotes to generate its NetEvent::Connecting.
+    connect_errors: Arc<Mutex<HashMap<ResourceId, io::Error>>>, // Failed connect_sync() calls.
+    watermark_events: Arc<Mutex<Vec<(ResourceId, Watermark)>>>, // In the order they were crossed.
+    waker: PollWaker,
 }
 
 impl<R: Remote, L: Local> Driver<R, L> {
@@ -131,6 +392,119 @@ impl<R: Remote, L: Local> Driver<R, L> {
             local_registry: Arc::new(ResourceRegistry::<L, LocalProperties>::new(
                 local_poll_registry,
             )),
+            deferred_writes: Arc::new(Mutex::new(HashSet::new())),
+            closing: Arc::new(Mutex::new(HashMap::new())),
+            paused_listeners: Arc::new(Mutex::new(HashMap::new())),
+            with_deadlines: Arc::new(Mutex::new(HashSet::new())),
+            connecting: Arc::new(Mutex::new(Vec::new())),
+            connect_errors: Arc::new(Mutex::new(HashMap::new())),
+            watermark_events: Arc::new(Mutex::new(Vec::new())),
+            waker: poll.create_waker(),
+        }
+    }
+
+    /// Schedules a write for the remote in the next poll iteration.
+    /// The poll is woken up because it could be waiting for events in other thread.
+    fn defer_write(&self, id: ResourceId) {
+        if self.deferred_writes.lock().expect(OTHER_THREAD_ERR).insert(id) {
+            self.waker.wake();
+        }
+    }
+
+    /// Sends by the remote, `bytes` is the size of the message sent.
+    fn send_by_remote(
+        &self,
+        endpoint: Endpoint,
+        bytes: usize,
+        send: impl FnOnce(&R) -> SendStatus,
+    ) -> SendStatus {
+        match self.remote_registry.get(endpoint.resource_id()) {
+            Some(remote) if !remote.resource.direction().can_write() => SendStatus::NotWritable,
+            Some(remote) => match remote.properties.is_ready() && !remote.properties.is_closing() {
+                true => {
+                    let status = send(&remote.resource);
+                    if matches!(status, SendStatus::Sent | SendStatus::SentDroppingOldest) {
+                        remote.properties.mark_activity();
+                        remote.properties.count_sent(bytes);
+                    }
+                    if remote.resource.wants_write() {
+                        self.defer_write(endpoint.resource_id());
+                    }
+                    self.check_watermarks(&remote, endpoint.resource_id());
+                    status
+                }
+                false => SendStatus::ResourceNotAvailable,
+            },
+            None => SendStatus::ResourceNotFound,
+        }
+    }
+
+    /// Returns the listeners of the group represented by the listener `id`, besides itself.
+    fn group_members(&self, id: ResourceId) -> Vec<ResourceId> {
+        self.local_registry
+            .ids()
+            .into_iter()
+            .filter(|&member| {
+                let local = self.local_registry.get(member);
+                matches!(local, Some(local) if local.properties.group == Some(id))
+            })
+            .collect()
+    }
+
+    /// Removes the listener `id` along with the rest of listeners of its group, if any.
+    fn remove_listener(&self, id: ResourceId) -> bool {
+        let members = self.group_members(id);
+        let removed = self.local_registry.deregister(id);
+        if removed {
+            for member in members {
+                self.local_registry.deregister(member);
+            }
+        }
+        removed
+    }
+
+    /// Sets a deadline of the established connection `id`, selected by `select`.
+    fn set_deadline(
+        &self,
+        id: ResourceId,
+        deadline: Option<Instant>,
+        select: impl FnOnce(&RemoteProperties) -> &Mutex<Option<Instant>>,
+    ) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote) if remote.properties.is_ready() => {
+                    *select(&remote.properties).lock().expect(OTHER_THREAD_ERR) = deadline;
+                    if deadline.is_some() {
+                        self.with_deadlines.lock().expect(OTHER_THREAD_ERR).insert(id);
+                        self.waker.wake(); // The poll thread could be waiting without a timeout.
+                    }
+                    true
+                }
+                _ => false,
+            },
+            ResourceType::Local => false,
+        }
+    }
+
+    /// Registers the watermark crossed by the data queued by the remote, if any,
+    /// to generate its event in the poll thread.
+    fn check_watermarks(&self, remote: &Register<R, RemoteProperties>, id: ResourceId) {
+        let mut tracker = remote.properties.watermarks.lock().expect(OTHER_THREAD_ERR);
+        if let Some(tracker) = tracker.as_mut() {
+            let queued = remote.resource.queued_bytes().unwrap_or(0);
+            if let Some(watermark) = tracker.update(queued) {
+                // Registered with the tracker locked, so the crossings keep their order.
+                self.watermark_events.lock().expect(OTHER_THREAD_ERR).push((id, watermark));
+                self.waker.wake();
+            }
+        }
+    }
+
+    fn send_by_local(&self, endpoint: Endpoint, data: &[u8]) -> SendStatus {
+        match self.local_registry.get(endpoint.resource_id()) {
+            Some(local) if !local.resource.direction().can_write() => SendStatus::NotWritable,
+            Some(local) => local.resource.send_to(endpoint.addr(), data),
+            None => SendStatus::ResourceNotFound,
         }
     }
 }
@@ -140,6 +514,14 @@ impl<R: Remote, L: Local> Clone for Driver<R, L> {
         Driver {
             remote_registry: self.remote_registry.clone(),
             local_registry: self.local_registry.clone(),
+            deferred_writes: self.deferred_writes.clone(),
+            closing: self.closing.clone(),
+            paused_listeners: self.paused_listeners.clone(),
+            with_deadlines: self.with_deadlines.clone(),
+            connecting: self.connecting.clone(),
+            connect_errors: self.connect_errors.clone(),
+            watermark_events: self.watermark_events.clone(),
+            waker: self.waker.clone(),
         }
     }
 }
@@ -149,13 +531,23 @@ impl<R: Remote, L: Local> ActionController for Driver<R, L> {
         &self,
         config: TransportConnect,
         addr: RemoteAddr,
+        target: RemoteAddr,
+        options: ConnectOptions,
     ) -> io::Result<(Endpoint, SocketAddr)> {
         R::connect_with(config, addr).map(|info| {
-            let id = self.remote_registry.register(
-                info.remote,
-                RemoteProperties::new(info.peer_addr, None),
-                true,
-            );
+            let interest = remote_interest(info.remote.direction());
+            let mut properties = RemoteProperties::new(info.peer_addr, None, Some(target));
+            properties.sync = options.sync;
+            properties.announce_failure = options.announce_failure;
+            if options.announce {
+                // Marked before registering it, so it precedes any event of the remote.
+                properties.mark_as_connecting();
+            }
+            let id = self.remote_registry.register(info.remote, properties, interest);
+            if options.announce {
+                self.connecting.lock().expect(OTHER_THREAD_ERR).push(id);
+                self.waker.wake();
+            }
             (Endpoint::new(id, info.peer_addr), info.local_addr)
         })
     }
@@ -164,42 +556,256 @@ impl<R: Remote, L: Local> ActionController for Driver<R, L> {
         &self,
         config: TransportListen,
         addr: SocketAddr,
+        group: Option<ResourceId>,
     ) -> io::Result<(ResourceId, SocketAddr)> {
         L::listen_with(config, addr).map(|info| {
-            let id = self.local_registry.register(info.local, LocalProperties, false);
+            let interest = local_interest(info.local.direction());
+            let properties = LocalProperties::new(group);
+            let id = self.local_registry.register(info.local, properties, interest);
             (id, info.local_addr)
         })
     }
 
     fn send(&self, endpoint: Endpoint, data: &[u8]) -> SendStatus {
         match endpoint.resource_id().resource_type() {
-            ResourceType::Remote => match self.remote_registry.get(endpoint.resource_id()) {
-                Some(remote) => match remote.properties.is_ready() {
-                    true => remote.resource.send(data),
-                    false => SendStatus::ResourceNotAvailable,
-                },
-                None => SendStatus::ResourceNotFound,
-            },
-            ResourceType::Local => match self.local_registry.get(endpoint.resource_id()) {
-                Some(remote) => remote.resource.send_to(endpoint.addr(), data),
-                None => SendStatus::ResourceNotFound,
-            },
+            ResourceType::Remote => {
+                self.send_by_remote(endpoint, data.len(), |remote| remote.send(data))
+            }
+            ResourceType::Local => self.send_by_local(endpoint, data),
         }
     }
 
+    fn send_owned(&self, endpoint: Endpoint, data: Vec<u8>) -> SendStatus {
+        match endpoint.resource_id().resource_type() {
+            ResourceType::Remote => {
+                self.send_by_remote(endpoint, data.len(), |remote| remote.send_owned(data))
+            }
+            ResourceType::Local => self.send_by_local(endpoint, &data),
+        }
+    }
+
+    fn send_tracked(
+        &self,
+        endpoint: Endpoint,
+        data: Vec<u8>,
+        completion: SendCompletion,
+    ) -> SendStatus {
+        match endpoint.resource_id().resource_type() {
+            ResourceType::Remote => self.send_by_remote(endpoint, data.len(), |remote| {
+                remote.send_tracked(data, completion)
+            }),
+            ResourceType::Local => {
+                // The datagram is written by the call.
+                let status = self.send_by_local(endpoint, &data);
+                if status == SendStatus::Sent {
+                    completion.complete();
+                }
+                status
+            }
+        }
+    }
+
+    fn send_ping(&self, endpoint: Endpoint, data: &[u8]) -> SendStatus {
+        // There are no ping frames sent by local resources.
+        // Only the messages are counted as sent data.
+        self.send_by_remote(endpoint, 0, |remote| remote.send_ping(data))
+    }
+
     fn remove(&self, id: ResourceId) -> bool {
         match id.resource_type() {
             ResourceType::Remote => self.remote_registry.deregister(id),
-            ResourceType::Local => self.local_registry.deregister(id),
+            ResourceType::Local => self.remove_listener(id),
+        }
+    }
+
+    fn remove_graceful(&self, id: ResourceId, timeout: Duration) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote) => {
+                    remote.properties.mark_as_closing();
+                    let deadline = Instant::now() + timeout;
+                    self.closing.lock().expect(OTHER_THREAD_ERR).insert(id, (deadline, false));
+                    self.waker.wake(); // The poll thread will close it.
+                    true
+                }
+                None => false,
+            },
+            ResourceType::Local => self.remove_listener(id),
+        }
+    }
+
+    fn reject(&self, id: ResourceId) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote) => {
+                    remote.resource.close();
+                    self.remote_registry.deregister(id)
+                }
+                None => false,
+            },
+            ResourceType::Local => false,
+        }
+    }
+
+    fn resource_ids(&self, resource_type: ResourceType) -> Vec<ResourceId> {
+        match resource_type {
+            ResourceType::Remote => self.remote_registry.ids(),
+            ResourceType::Local => self.local_registry.ids(),
+        }
+    }
+
+    fn connections(&self) -> Vec<Endpoint> {
+        self.remote_registry
+            .ids()
+            .into_iter()
+            .filter_map(|id| {
+                let remote = self.remote_registry.get(id)?;
+                Some(Endpoint::new(id, remote.properties.peer_addr))
+            })
+            .collect()
+    }
+
+    fn take_connect_error(&self, id: ResourceId) -> Option<io::Error> {
+        self.connect_errors.lock().expect(OTHER_THREAD_ERR).remove(&id)
+    }
+
+    fn join_group(&self, id: ResourceId, group: GroupId) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote) => {
+                    remote.properties.groups.lock().expect(OTHER_THREAD_ERR).insert(group);
+                    true
+                }
+                None => false,
+            },
+            ResourceType::Local => false,
+        }
+    }
+
+    fn leave_group(&self, id: ResourceId, group: GroupId) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote) => {
+                    remote.properties.groups.lock().expect(OTHER_THREAD_ERR).remove(&group)
+                }
+                None => false,
+            },
+            ResourceType::Local => false,
         }
     }
 
+    fn group_members(&self, group: GroupId) -> Vec<Endpoint> {
+        self.remote_registry
+            .ids()
+            .into_iter()
+            .filter_map(|id| {
+                let remote = self.remote_registry.get(id)?;
+                let groups = remote.properties.groups.lock().expect(OTHER_THREAD_ERR);
+                match groups.contains(&group) {
+                    true => Some(Endpoint::new(id, remote.properties.peer_addr)),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+
     fn is_ready(&self, id: ResourceId) -> Option<bool> {
         match id.resource_type() {
             ResourceType::Remote => self.remote_registry.get(id).map(|r| r.properties.is_ready()),
             ResourceType::Local => self.local_registry.get(id).map(|_| true),
         }
     }
+
+    fn listener_of(&self, id: ResourceId) -> Option<ResourceId> {
+        match id.resource_type() {
+            ResourceType::Remote => self.remote_registry.get(id).and_then(|r| r.properties.local),
+            ResourceType::Local => self.local_registry.get(id).map(|_| id),
+        }
+    }
+
+    fn connected_at(&self, id: ResourceId) -> Option<Instant> {
+        match id.resource_type() {
+            ResourceType::Remote => self.remote_registry.get(id)?.properties.connected_at(),
+            ResourceType::Local => None,
+        }
+    }
+
+    fn last_activity(&self, id: ResourceId) -> Option<Instant> {
+        match id.resource_type() {
+            ResourceType::Remote => self.remote_registry.get(id)?.properties.last_activity(),
+            ResourceType::Local => None,
+        }
+    }
+
+    fn resume_token(&self, id: ResourceId) -> Option<String> {
+        match id.resource_type() {
+            ResourceType::Remote => self.remote_registry.get(id)?.resource.resume_token(),
+            ResourceType::Local => None,
+        }
+    }
+
+    fn framing_stats(&self, id: ResourceId) -> Option<FramingStats> {
+        match id.resource_type() {
+            ResourceType::Remote => self.remote_registry.get(id)?.resource.framing_stats(),
+            ResourceType::Local => None,
+        }
+    }
+
+    fn set_read_deadline(&self, id: ResourceId, deadline: Option<Instant>) -> bool {
+        self.set_deadline(id, deadline, |properties| &properties.read_deadline)
+    }
+
+    fn set_write_deadline(&self, id: ResourceId, deadline: Option<Instant>) -> bool {
+        self.set_deadline(id, deadline, |properties| &properties.write_deadline)
+    }
+
+    fn set_watermarks(&self, id: ResourceId, watermarks: Option<Watermarks>) -> bool {
+        match id.resource_type() {
+            ResourceType::Remote => match self.remote_registry.get(id) {
+                Some(remote)
+                    if remote.properties.is_ready() && remote.resource.queued_bytes().is_some() =>
+                {
+                    let tracker = watermarks.map(WatermarkTracker::new);
+                    *remote.properties.watermarks.lock().expect(OTHER_THREAD_ERR) = tracker;
+                    self.check_watermarks(&remote, id); // The queued data could be already high.
+                    true
+                }
+                _ => false,
+            },
+            ResourceType::Local => false,
+        }
+    }
+
+    fn set_accept_rate(&self, id: ResourceId, rate: Option<AcceptRate>) -> bool {
+        // Only listeners of connection oriented transports accept connections.
+        if !Transport::from(id.adapter_id()).is_connection_oriented() {
+            return false
+        }
+        match id.resource_type() {
+            ResourceType::Remote => false,
+            ResourceType::Local => {
+                if self.local_registry.get(id).is_none() {
+                    return false
+                }
+                // The rate is applied to each listener of the group.
+                for id in std::iter::once(id).chain(self.group_members(id)) {
+                    if let Some(local) = self.local_registry.get(id) {
+                        let limiter = rate.map(AcceptLimiter::new);
+                        *local.properties.accept_limiter.lock().expect(OTHER_THREAD_ERR) = limiter;
+
+                        // A paused listener is resumed to accept by the new rate.
+                        let mut paused_listeners =
+                            self.paused_listeners.lock().expect(OTHER_THREAD_ERR);
+                        if let Some(resume_time) = paused_listeners.get_mut(&id) {
+                            *resume_time = Instant::now();
+                            self.waker.wake();
+                        }
+                    }
+                }
+                true
+            }
+        }
+    }
 }
 
 impl<R: Remote, L: Local<Remote = R>> EventProcessor for Driver<R, L> {
@@ -215,16 +821,22 @@ impl<R: Remote, L: Local<Remote = R>> EventProcessor for Driver<R, L> {
                     let endpoint = Endpoint::new(id, remote.properties.peer_addr);
                     log::trace!("Processed remote for {}", endpoint);
 
+                    Self::announce_connecting(&remote, endpoint, event_callback);
                     if !remote.properties.is_ready() {
                         self.resolve_pending_remote(&remote, endpoint, readiness, |e| {
                             event_callback(e)
                         });
+                        if self.remote_registry.get(id).is_none() {
+                            return // Rejected or removed by the user in the callback.
+                        }
                     }
                     if remote.properties.is_ready() {
                         match readiness {
                             Readiness::Write => {
                                 self.write_to_remote(&remote, endpoint, event_callback);
                             }
+                            // The errors are reported as read readiness even if not registered.
+                            Readiness::Read if !remote.resource.direction().can_read() => (),
                             Readiness::Read => {
                                 self.read_from_remote(&remote, endpoint, event_callback);
                             }
@@ -237,15 +849,90 @@ impl<R: Remote, L: Local<Remote = R>> EventProcessor for Driver<R, L> {
                     log::trace!("Processed local for {}", id);
                     match readiness {
                         Readiness::Write => (),
+                        Readiness::Read if !local.resource.direction().can_read() => (),
                         Readiness::Read => self.read_from_local(&local, id, event_callback),
                     }
                 }
             }
         }
     }
+
+    fn process_deferred(&self, event_callback: &mut dyn FnMut(NetEvent<'_>)) -> bool {
+        let ids = std::mem::take(&mut *self.connecting.lock().expect(OTHER_THREAD_ERR));
+        for id in ids {
+            if let Some(remote) = self.remote_registry.get(id) {
+                let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+                Self::announce_connecting(&remote, endpoint, &mut *event_callback);
+            }
+        }
+
+        let ids = std::mem::take(&mut *self.deferred_writes.lock().expect(OTHER_THREAD_ERR));
+        for id in ids {
+            if let Some(remote) = self.remote_registry.get(id) {
+                let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+                log::trace!("Processed deferred write for {}", endpoint);
+                self.write_to_remote(&remote, endpoint, &mut *event_callback);
+            }
+        }
+
+        let crossed = std::mem::take(&mut *self.watermark_events.lock().expect(OTHER_THREAD_ERR));
+        for (id, watermark) in crossed {
+            if let Some(remote) = self.remote_registry.get(id) {
+                let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+                event_callback(NetEvent::Watermark(endpoint, watermark));
+            }
+        }
+        self.close_remotes(&mut *event_callback);
+        self.expire_deadlines(&mut *event_callback);
+        self.resume_listeners(event_callback);
+        !self.deferred_writes.lock().expect(OTHER_THREAD_ERR).is_empty()
+    }
+
+    fn sample_throughput(&self, interval: Duration, event_callback: &mut dyn FnMut(NetEvent<'_>)) {
+        for id in self.remote_registry.ids() {
+            if let Some(remote) = self.remote_registry.get(id) {
+                if remote.properties.is_ready() {
+                    let (bytes_in, bytes_out) = remote.properties.take_throughput();
+                    let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+                    let sample = ThroughputSample { endpoint, bytes_in, bytes_out, interval };
+                    event_callback(NetEvent::Throughput(sample));
+                }
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let with_deadlines = self.with_deadlines.lock().expect(OTHER_THREAD_ERR);
+        let deadline = with_deadlines
+            .iter()
+            .filter_map(|&id| self.remote_registry.get(id)?.properties.deadline())
+            .min();
+        drop(with_deadlines);
+
+        let closing = self.closing.lock().expect(OTHER_THREAD_ERR);
+        let closing_deadline = closing.values().map(|&(deadline, _)| deadline).min();
+        drop(closing);
+
+        let paused_listeners = self.paused_listeners.lock().expect(OTHER_THREAD_ERR);
+        let resume_time = paused_listeners.values().min().copied();
+        deadline.into_iter().chain(closing_deadline).chain(resume_time).min()
+    }
 }
 
 impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
+    /// Generates the [`NetEvent::Connecting`] of the remote if it has not been generated yet.
+    fn announce_connecting(
+        remote: &Arc<Register<R, RemoteProperties>>,
+        endpoint: Endpoint,
+        event_callback: &mut dyn FnMut(NetEvent<'_>),
+    ) {
+        if remote.properties.take_connecting() {
+            if let Some(target) = &remote.properties.target {
+                event_callback(NetEvent::Connecting(endpoint, target));
+            }
+        }
+    }
+
     fn resolve_pending_remote(
         &self,
         remote: &Arc<Register<R, RemoteProperties>>,
@@ -264,11 +951,27 @@ impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
                 }
                 remote.resource.ready_to_write();
             }
-            PendingStatus::Incomplete => (),
+            PendingStatus::Incomplete => {
+                let interest = remote_interest(remote.resource.direction());
+                remote.resource.replace_source(&mut |previous, source| {
+                    let id = endpoint.resource_id();
+                    self.remote_registry.replace_source(id, previous, source, interest);
+                });
+            }
             PendingStatus::Disconnected => {
+                if remote.properties.sync {
+                    // Kept before deregistering it, connect_sync() waits for the deregistration.
+                    if let Some(err) = remote.resource.connect_error() {
+                        let mut errors = self.connect_errors.lock().expect(OTHER_THREAD_ERR);
+                        errors.insert(endpoint.resource_id(), err);
+                    }
+                }
                 self.remote_registry.deregister(endpoint.resource_id());
-                if remote.properties.local.is_none() {
+                if let Some(target) = &remote.properties.target {
                     event_callback(NetEvent::Connected(endpoint, false));
+                    if remote.properties.announce_failure {
+                        event_callback(NetEvent::ConnectFailed(endpoint, target));
+                    }
                 }
             }
         }
@@ -281,7 +984,97 @@ impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
         mut event_callback: impl FnMut(NetEvent<'_>),
     ) {
         if !remote.resource.ready_to_write() {
-            event_callback(NetEvent::Disconnected(endpoint));
+            // Checked because, the user in the callback could have removed the same resource.
+            if self.remote_registry.deregister(endpoint.resource_id()) {
+                event_callback(NetEvent::Disconnected(endpoint));
+            }
+            return
+        }
+        if remote.resource.wants_write() {
+            // Already in the poll thread, no need to wake it up.
+            self.deferred_writes.lock().expect(OTHER_THREAD_ERR).insert(endpoint.resource_id());
+        }
+        self.check_watermarks(remote, endpoint.resource_id());
+    }
+
+    /// Closes the remotes removed gracefully whose queued data was written
+    /// or whose deadline has expired.
+    /// The close procedure can queue data too, as the close frame of a WebSocket,
+    /// in that case the remote is removed once it is written or the deadline expires.
+    fn close_remotes(&self, mut event_callback: impl FnMut(NetEvent<'_>)) {
+        let mut to_close = Vec::new();
+        let now = Instant::now();
+        self.closing.lock().expect(OTHER_THREAD_ERR).retain(|&id, &mut (deadline, closed)| {
+            match self.remote_registry.get(id) {
+                Some(remote) if remote.resource.has_queued_data() && now < deadline => true,
+                Some(remote) => {
+                    to_close.push((id, remote, deadline, closed));
+                    false
+                }
+                None => false, // Already disconnected or removed.
+            }
+        });
+
+        // Closed out of the lock, the user could remove gracefully other resource in the callback.
+        // They are closed in the order they were created, to generate the events in that order.
+        to_close.sort_by_key(|(id, ..)| id.raw());
+        for (id, remote, deadline, closed) in to_close {
+            let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+            if !closed {
+                remote.resource.close();
+                if remote.resource.has_queued_data() && now < deadline {
+                    // Written by the next write readiness, without blocking the poll thread.
+                    self.closing.lock().expect(OTHER_THREAD_ERR).insert(id, (deadline, true));
+                    continue
+                }
+            }
+            if remote.resource.has_queued_data() {
+                log::warn!("Graceful removal timeout of {}, queued data will be lost", endpoint);
+            }
+            if self.remote_registry.deregister(id) {
+                event_callback(NetEvent::Disconnected(endpoint));
+            }
+        }
+    }
+
+    /// Closes the remotes whose read or write deadline has expired.
+    /// The read deadline is met by receiving a message,
+    /// and the write deadline by writing all the queued data.
+    fn expire_deadlines(&self, mut event_callback: impl FnMut(NetEvent<'_>)) {
+        let mut expired = Vec::new();
+        let now = Instant::now();
+        self.with_deadlines.lock().expect(OTHER_THREAD_ERR).retain(|&id| {
+            match self.remote_registry.get(id) {
+                Some(remote) => {
+                    let mut write_deadline =
+                        remote.properties.write_deadline.lock().expect(OTHER_THREAD_ERR);
+                    if !remote.resource.has_queued_data() {
+                        *write_deadline = None;
+                    }
+                    drop(write_deadline);
+                    match remote.properties.deadline() {
+                        Some(deadline) if now >= deadline => {
+                            expired.push((id, remote));
+                            false
+                        }
+                        Some(_) => true,
+                        None => false,
+                    }
+                }
+                None => false, // Already disconnected or removed.
+            }
+        });
+
+        // Closed out of the lock, the user could set other deadline in the callback.
+        expired.sort_by_key(|(id, _)| id.raw());
+        for (id, remote) in expired {
+            let endpoint = Endpoint::new(id, remote.properties.peer_addr);
+            log::trace!("Deadline expired for {}", endpoint);
+            remote.resource.close();
+            if self.remote_registry.deregister(id) {
+                event_callback(NetEvent::Error(endpoint, io::ErrorKind::TimedOut));
+                event_callback(NetEvent::Disconnected(endpoint));
+            }
         }
     }
 
@@ -291,14 +1084,29 @@ impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
         endpoint: Endpoint,
         mut event_callback: impl FnMut(NetEvent<'_>),
     ) {
-        let status =
-            remote.resource.receive(|data| event_callback(NetEvent::Message(endpoint, data)));
+        let status = remote.resource.receive_frames(|frame| {
+            remote.properties.mark_activity();
+            match frame {
+                ReceivedFrame::Message(data) => {
+                    remote.properties.count_received(data.len());
+                    *remote.properties.read_deadline.lock().expect(OTHER_THREAD_ERR) = None;
+                    event_callback(NetEvent::Message(endpoint, data))
+                }
+                ReceivedFrame::Control(frame, data) => {
+                    event_callback(NetEvent::Control(endpoint, frame, data))
+                }
+            }
+        });
         log::trace!("Receive status: {:?}", status);
-        if let ReadStatus::Disconnected = status {
-            // Checked because, the user in the callback could have removed the same resource.
-            if self.remote_registry.deregister(endpoint.resource_id()) {
-                event_callback(NetEvent::Disconnected(endpoint));
+        match status {
+            ReadStatus::Disconnected => {
+                // Checked because, the user in the callback could have removed the same resource.
+                if self.remote_registry.deregister(endpoint.resource_id()) {
+                    event_callback(NetEvent::Disconnected(endpoint));
+                }
             }
+            ReadStatus::Error(kind) => event_callback(NetEvent::Error(endpoint, kind)),
+            ReadStatus::WaitNextEvent => (),
         }
     }
 
@@ -308,14 +1116,17 @@ impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
         id: ResourceId,
         mut event_callback: impl FnMut(NetEvent<'_>),
     ) {
-        local.resource.accept(|accepted| {
+        // The remotes accepted by a listener of a group are considered accepted by the group.
+        let listener_id = local.properties.group.unwrap_or(id);
+        let mut accept = |accepted: AcceptedType<'_, R>| {
             log::trace!("Accepted type: {}", accepted);
             match accepted {
                 AcceptedType::Remote(addr, remote) => {
+                    let interest = remote_interest(remote.direction());
                     self.remote_registry.register(
                         remote,
-                        RemoteProperties::new(addr, Some(id)),
-                        true,
+                        RemoteProperties::new(addr, Some(listener_id), None),
+                        interest,
                     );
                 }
                 AcceptedType::Data(addr, data) => {
@@ -323,7 +1134,87 @@ impl<R: Remote, L: Local<Remote = R>> Driver<R, L> {
                     event_callback(NetEvent::Message(endpoint, data));
                 }
             }
-        });
+        };
+
+        // The locks are not held while accepting, the accept can call the event callback.
+        let now = Instant::now();
+        let mut accept_limiter = local.properties.accept_limiter.lock().expect(OTHER_THREAD_ERR);
+        let limit = accept_limiter.as_mut().map(|limiter| limiter.available(now));
+        drop(accept_limiter);
+        if local.is_paused() {
+            let mut paused_listeners = self.paused_listeners.lock().expect(OTHER_THREAD_ERR);
+            let resume_time = paused_listeners.get(&id).copied();
+            if limit.is_some() && resume_time.is_some_and(|resume_time| now < resume_time) {
+                return // Paused, the pending connections will be accepted when resumed.
+            }
+            paused_listeners.remove(&id);
+            drop(paused_listeners);
+            local.resume_source(id, local_interest(local.resource.direction()));
+        }
+
+        match limit {
+            Some(limit) => {
+                let mut accepted = 0;
+                let limit_reached = limit == 0
+                    || local.resource.accept_limited(limit, |remote| {
+                        accepted += 1;
+                        accept(remote)
+                    });
+                let mut accept_limiter =
+                    local.properties.accept_limiter.lock().expect(OTHER_THREAD_ERR);
+                if let Some(limiter) = accept_limiter.as_mut() {
+                    limiter.consume(accepted);
+                    if limit_reached {
+                        log::trace!("Accept rate reached by {}, accepting paused", id);
+                        let resume_time = limiter.next_available();
+                        drop(accept_limiter);
+                        self.paused_listeners
+                            .lock()
+                            .expect(OTHER_THREAD_ERR)
+                            .insert(id, resume_time);
+                        local.pause_source();
+                    }
+                }
+            }
+            None => local.resource.accept(accept),
+        }
+    }
+
+    /// Accepts the pending connections of the listeners paused by its accept rate.
+    fn resume_listeners(&self, mut event_callback: impl FnMut(NetEvent<'_>)) {
+        let now = Instant::now();
+        let to_resume: Vec<ResourceId> = self
+            .paused_listeners
+            .lock()
+            .expect(OTHER_THREAD_ERR)
+            .iter()
+            .filter(|(_, &resume_time)| resume_time <= now)
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in to_resume {
+            match self.local_registry.get(id) {
+                Some(local) => self.read_from_local(&local, id, &mut event_callback),
+                None => {
+                    self.paused_listeners.lock().expect(OTHER_THREAD_ERR).remove(&id);
+                }
+            }
+        }
+    }
+}
+
+/// The remotes are registered for write readiness to know when they are connected.
+fn remote_interest(direction: Direction) -> Interest {
+    match direction {
+        Direction::WriteOnly => Interest::WRITABLE,
+        _ => Interest::READABLE | Interest::WRITABLE,
+    }
+}
+
+fn local_interest(direction: Direction) -> Interest {
+    match direction {
+        Direction::WriteOnly => Interest::WRITABLE,
+        _ => Interest::READABLE,
     }
 }
 
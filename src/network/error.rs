@@ -0,0 +1,132 @@
+use super::adapter::{SendStatus};
+
+use std::io::{self, ErrorKind};
+
+/// Error of the operations that connect or listen, as
+/// [`crate::network::NetworkController::connect()`] or
+/// [`crate::network::NetworkController::listen()`].
+/// The underlying [`io::Error`] is kept in the variants that come from the OS,
+/// and can be recovered converting the error into an [`io::Error`].
+///
+/// The sends still return a [`SendStatus`], that can be converted into this error by
+/// [`SendStatus::into_result()`], and [`crate::network::NetEvent::Error`] still carries
+/// the [`io::ErrorKind`] reported by the OS.
+///
+/// New variants can be added in future versions, so a `match` over it needs a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum NetworkError {
+    /// The address is already used by other socket of the system.
+    AddrInUse(io::Error),
+
+    /// The peer refused the connection, or there was nothing listening at the address.
+    ConnectionRefused(io::Error),
+
+    /// The connection was established but the peer rejected the handshake of the protocol,
+    /// as the HTTP response of a *WebSocket* server,
+    /// see [`crate::adapters::ws::WsHandshakeRejected`].
+    HandshakeFailed(io::Error),
+
+    /// The message is bigger than the transport allows,
+    /// see [`crate::network::Transport::max_message_size()`].
+    MessageTooLarge,
+
+    /// The resource does not exist, or it has been disconnected.
+    ResourceNotFound,
+
+    /// The message was not sent for other reason, given by its [`SendStatus`].
+    NotSent(SendStatus),
+
+    /// Any other error of the OS.
+    Io(io::Error),
+}
+
+impl NetworkError {
+    /// The kind of the error as an [`io::Error`], to handle it as the rest of I/O errors.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::AddrInUse(err)
+            | Self::ConnectionRefused(err)
+            | Self::HandshakeFailed(err)
+            | Self::Io(err) => err.kind(),
+            Self::MessageTooLarge => ErrorKind::InvalidInput,
+            Self::ResourceNotFound => ErrorKind::NotConnected,
+            Self::NotSent(_) => ErrorKind::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddrInUse(err) => write!(f, "Address in use: {err}"),
+            Self::ConnectionRefused(err) => write!(f, "Connection refused: {err}"),
+            Self::HandshakeFailed(err) => write!(f, "Handshake failed: {err}"),
+            Self::MessageTooLarge => write!(f, "Message too large for the transport"),
+            Self::ResourceNotFound => write!(f, "Resource not found"),
+            Self::NotSent(status) => write!(f, "Message not sent: {status:?}"),
+            Self::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::AddrInUse(err)
+            | Self::ConnectionRefused(err)
+            | Self::HandshakeFailed(err)
+            | Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetworkError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            ErrorKind::AddrInUse => Self::AddrInUse(err),
+            ErrorKind::ConnectionRefused if is_handshake_rejection(&err) => {
+                Self::HandshakeFailed(err)
+            }
+            ErrorKind::ConnectionRefused => Self::ConnectionRefused(err),
+            _ => Self::Io(err),
+        }
+    }
+}
+
+#[cfg(feature = "websocket")]
+fn is_handshake_rejection(err: &io::Error) -> bool {
+    use crate::adapters::ws::{WsHandshakeRejected};
+    err.get_ref().is_some_and(|inner| inner.is::<WsHandshakeRejected>())
+}
+
+#[cfg(not(feature = "websocket"))]
+fn is_handshake_rejection(_err: &io::Error) -> bool {
+    false
+}
+
+impl From<NetworkError> for io::Error {
+    fn from(err: NetworkError) -> Self {
+        match err {
+            NetworkError::AddrInUse(err)
+            | NetworkError::ConnectionRefused(err)
+            | NetworkError::HandshakeFailed(err)
+            | NetworkError::Io(err) => err,
+            err => io::Error::new(err.kind(), err),
+        }
+    }
+}
+
+impl SendStatus {
+    /// Converts the status into a result, being an error if the message was not sent.
+    /// [`SendStatus::SentDroppingOldest`] is considered sent.
+    pub fn into_result(self) -> Result<(), NetworkError> {
+        match self {
+            SendStatus::Sent | SendStatus::SentDroppingOldest => Ok(()),
+            SendStatus::MaxPacketSizeExceeded => Err(NetworkError::MessageTooLarge),
+            SendStatus::ResourceNotFound => Err(NetworkError::ResourceNotFound),
+            status => Err(NetworkError::NotSent(status)),
+        }
+    }
+}
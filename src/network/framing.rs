@@ -0,0 +1,19 @@
+/// State of the framing of the data received by a connection, to inspect how the peer
+/// is sending its messages.
+/// It is obtained by [`crate::network::NetworkController::framing_stats()`].
+///
+/// Only the transports whose frames are assembled by the adapter, as
+/// [`crate::network::Transport::FramedTcp`], have framing stats.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FramingStats {
+    /// Bytes received of the frame that is being assembled, including its header.
+    /// Is `0` when no frame is partially received.
+    pub buffered_bytes: usize,
+
+    /// Size declared by the header of the frame that is being assembled,
+    /// or `None` if no header has been completely received.
+    pub frame_size: Option<usize>,
+
+    /// Frames decoded completely since the connection was established.
+    pub frames_decoded: u64,
+}
@@ -0,0 +1,19 @@
+/// Identifier of a group of endpoints, created by
+/// [`crate::network::NetworkController::create_group()`].
+///
+/// The membership is kept by the connections themselves: when a connection is removed
+/// or disconnected, it is no longer a member of any group.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct GroupId(u64);
+
+impl GroupId {
+    pub(crate) fn new(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl std::fmt::Display for GroupId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[G.{}]", self.0)
+    }
+}
@@ -1,14 +1,20 @@
 use crate::network::{TransportConnect, TransportListen};
 
 use super::endpoint::{Endpoint};
-use super::resource_id::{ResourceId};
+use super::resource_id::{ResourceId, ResourceType};
 use super::poll::{Poll, Readiness};
 use super::remote_addr::{RemoteAddr};
-use super::driver::{NetEvent, Driver, ActionController, EventProcessor};
+use super::driver::{NetEvent, Driver, ActionController, ConnectOptions, EventProcessor};
 use super::adapter::{Adapter, SendStatus};
+use super::accept_rate::{AcceptRate};
+use super::framing::{FramingStats};
+use super::group::{GroupId};
+use super::watermarks::{Watermarks};
+use super::ticket::{SendCompletion};
 
 use std::net::{SocketAddr};
 use std::io::{self};
+use std::time::{Duration, Instant};
 use std::panic::{UnwindSafe};
 
 type Controller = Box<dyn ActionController + Send + UnwindSafe>;
@@ -26,8 +32,15 @@ pub struct DriverLoader {
 
 impl Default for DriverLoader {
     fn default() -> DriverLoader {
+        Self::new(Poll::default())
+    }
+}
+
+impl DriverLoader {
+    /// Creates a loader whose drivers register their resources in `poll`.
+    pub fn new(poll: Poll) -> DriverLoader {
         Self {
-            poll: Poll::default(),
+            poll,
             controllers: (0..ResourceId::MAX_ADAPTERS)
                 .map(|_| Box::new(UnimplementedDriver) as Controller)
                 .collect::<Vec<_>>(),
@@ -36,9 +49,7 @@ impl Default for DriverLoader {
                 .collect(),
         }
     }
-}
 
-impl DriverLoader {
     /// Mount an adapter to create its driver associating it with an id.
     pub fn mount(&mut self, adapter_id: u8, adapter: impl Adapter + 'static) {
         let index = adapter_id as usize;
@@ -68,6 +79,8 @@ impl ActionController for UnimplementedDriver {
         &self,
         _: TransportConnect,
         _: RemoteAddr,
+        _: RemoteAddr,
+        _: ConnectOptions,
     ) -> io::Result<(Endpoint, SocketAddr)> {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
@@ -76,6 +89,7 @@ impl ActionController for UnimplementedDriver {
         &self,
         _: TransportListen,
         _: SocketAddr,
+        _: Option<ResourceId>,
     ) -> io::Result<(ResourceId, SocketAddr)> {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
@@ -84,17 +98,107 @@ impl ActionController for UnimplementedDriver {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
 
+    fn send_owned(&self, _: Endpoint, _: Vec<u8>) -> SendStatus {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn send_tracked(&self, _: Endpoint, _: Vec<u8>, _: SendCompletion) -> SendStatus {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn send_ping(&self, _: Endpoint, _: &[u8]) -> SendStatus {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
     fn remove(&self, _: ResourceId) -> bool {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
 
+    fn remove_graceful(&self, _: ResourceId, _: Duration) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn reject(&self, _: ResourceId) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn connections(&self) -> Vec<Endpoint> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn take_connect_error(&self, _: ResourceId) -> Option<io::Error> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn join_group(&self, _: ResourceId, _: GroupId) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn leave_group(&self, _: ResourceId, _: GroupId) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn group_members(&self, _: GroupId) -> Vec<Endpoint> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn resource_ids(&self, _: ResourceType) -> Vec<ResourceId> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
     fn is_ready(&self, _: ResourceId) -> Option<bool> {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
+
+    fn listener_of(&self, _: ResourceId) -> Option<ResourceId> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn connected_at(&self, _: ResourceId) -> Option<Instant> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn last_activity(&self, _: ResourceId) -> Option<Instant> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn resume_token(&self, _: ResourceId) -> Option<String> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn framing_stats(&self, _: ResourceId) -> Option<FramingStats> {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn set_read_deadline(&self, _: ResourceId, _: Option<Instant>) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn set_write_deadline(&self, _: ResourceId, _: Option<Instant>) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn set_accept_rate(&self, _: ResourceId, _: Option<AcceptRate>) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
+
+    fn set_watermarks(&self, _: ResourceId, _: Option<Watermarks>) -> bool {
+        panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
+    }
 }
 
 impl EventProcessor for UnimplementedDriver {
     fn process(&self, _: ResourceId, _: Readiness, _: &mut dyn FnMut(NetEvent<'_>)) {
         panic!("{}", UNIMPLEMENTED_DRIVER_ERR);
     }
+
+    fn process_deferred(&self, _: &mut dyn FnMut(NetEvent<'_>)) -> bool {
+        false
+    }
+
+    fn sample_throughput(&self, _: Duration, _: &mut dyn FnMut(NetEvent<'_>)) {}
+
+    fn next_deadline(&self) -> Option<Instant> {
+        None
+    }
 }
@@ -0,0 +1,122 @@
+use crate::util::thread::{NamespacedThread};
+
+use std::io::{self};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration};
+
+/// Maximum time the monitor waits for a notification before checking if it must stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Watches the interfaces and addresses of the host by the notifications of the OS,
+/// calling `notify` each time they change.
+/// The monitor stops when it is dropped.
+///
+/// Only Linux is supported, by a netlink socket.
+/// In the rest of platforms the monitor does nothing.
+pub(crate) struct NetworkMonitor {
+    running: Arc<AtomicBool>,
+    _thread: Option<NamespacedThread<()>>,
+}
+
+impl NetworkMonitor {
+    #[cfg(target_os = "linux")]
+    pub fn start(notify: impl Fn() + Send + 'static) -> io::Result<Self> {
+        let watcher = netlink::Watcher::new()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = running.clone();
+            NamespacedThread::spawn("network-monitor-thread", move || {
+                while running.load(Ordering::Relaxed) {
+                    match watcher.wait_change(STOP_CHECK_INTERVAL) {
+                        Ok(true) => notify(),
+                        Ok(false) => (),
+                        Err(err) => {
+                            log::error!("Network monitor error: {}", err);
+                            break
+                        }
+                    }
+                }
+            })
+        };
+        Ok(Self { running, _thread: Some(thread) })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn start(_notify: impl Fn() + Send + 'static) -> io::Result<Self> {
+        log::warn!("The network changes are not monitored in this platform");
+        Ok(Self { running: Arc::new(AtomicBool::new(false)), _thread: None })
+    }
+}
+
+impl Drop for NetworkMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod netlink {
+    use nix::errno::{Errno};
+    use nix::poll::{self, PollFd, PollFlags};
+    use nix::sys::socket::{
+        self, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType,
+    };
+    use nix::unistd::{self};
+
+    use std::io::{self};
+    use std::os::unix::io::{RawFd};
+    use std::time::{Duration};
+
+    /// Route netlink socket subscribed to the changes of the links and the addresses.
+    pub struct Watcher(RawFd);
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            let fd = socket::socket(
+                AddressFamily::Netlink,
+                SockType::Raw,
+                SockFlag::SOCK_CLOEXEC,
+                SockProtocol::NetlinkRoute,
+            )?;
+            let watcher = Self(fd); // Closed if the bind fails.
+
+            let groups = libc::RTMGRP_LINK | libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR;
+            socket::bind(fd, &NetlinkAddr::new(0, groups as u32))?;
+            Ok(watcher)
+        }
+
+        /// Waits at most `timeout` for a notification, returning if there was any.
+        /// The notifications are consumed without being parsed,
+        /// only the fact that something changed is needed.
+        pub fn wait_change(&self, timeout: Duration) -> io::Result<bool> {
+            let mut pollfd = [PollFd::new(self.0, PollFlags::POLLIN)];
+            match poll::poll(&mut pollfd, timeout.as_millis() as libc::c_int) {
+                Ok(0) | Err(Errno::EINTR) => return Ok(false),
+                Ok(_) => (),
+                Err(err) => return Err(err.into()),
+            }
+
+            let mut buffer = [0u8; 4096];
+            loop {
+                match socket::recv(self.0, &mut buffer, MsgFlags::MSG_DONTWAIT) {
+                    Ok(_) | Err(Errno::EINTR) => continue,
+                    Err(Errno::EAGAIN) => break Ok(true),
+                    // The notifications overflowed the socket buffer, some were lost.
+                    Err(Errno::ENOBUFS) => break Ok(true),
+                    Err(err) => break Err(err.into()),
+                }
+            }
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            if let Err(err) = unistd::close(self.0) {
+                log::trace!("Network monitor close error: {}", err);
+            }
+        }
+    }
+}
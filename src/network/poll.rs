@@ -1,10 +1,12 @@
 use super::resource_id::{ResourceId, ResourceType, ResourceIdGenerator};
 
+use crate::util::thread::{OTHER_THREAD_ERR};
+
 use mio::{Poll as MioPoll, Interest, Token, Events, Registry, Waker};
 use mio::event::{Source};
 
 use std::time::{Duration};
-use std::sync::{Arc};
+use std::sync::{Arc, Mutex};
 use std::io::{ErrorKind};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -18,6 +20,10 @@ pub enum Readiness {
     Read,
 }
 
+/// Readiness notified by the resources that do not have an OS source,
+/// delivered the next time the poll is woken up.
+type NotifiedReadiness = Arc<Mutex<Vec<(ResourceId, Readiness)>>>;
+
 pub enum PollEvent {
     Network(ResourceId, Readiness),
     Waker,
@@ -35,20 +41,26 @@ impl From<ResourceId> for Token {
     }
 }
 
+const EXTERNAL_POLL_ERR: &str =
+    "The network is registered in an external poll, its events must be processed by it";
+
 pub struct Poll {
-    mio_poll: MioPoll,
+    mio_poll: Option<MioPoll>, // None if the resources are registered in an external poll.
+    registry: Registry,
     events: Events,
-    #[allow(dead_code)] //TODO: remove it with poll native event support
     waker: Arc<Waker>,
+    notified: NotifiedReadiness,
 }
 
 impl Default for Poll {
     fn default() -> Self {
         let mio_poll = MioPoll::new().unwrap();
         Self {
+            registry: mio_poll.registry().try_clone().unwrap(),
             waker: Arc::new(Waker::new(mio_poll.registry(), Self::WAKER_TOKEN).unwrap()),
-            mio_poll,
+            mio_poll: Some(mio_poll),
             events: Events::with_capacity(Self::EVENTS_SIZE),
+            notified: NotifiedReadiness::default(),
         }
     }
 }
@@ -58,41 +70,74 @@ impl Poll {
     const RESERVED_BITS: usize = 1;
     const WAKER_TOKEN: Token = Token(0);
 
-    pub fn process_event<C>(&mut self, timeout: Option<Duration>, mut event_callback: C)
+    /// Creates a poll that registers the resources in the `registry` of an external poll.
+    /// The events of that poll are processed by [`Poll::process_ready()`].
+    /// The resources use the token `0` and the odd tokens, the rest are ignored.
+    pub fn external(registry: &Registry) -> Self {
+        Self {
+            mio_poll: None,
+            registry: registry.try_clone().unwrap(),
+            events: Events::with_capacity(0),
+            waker: Arc::new(Waker::new(registry, Self::WAKER_TOKEN).unwrap()),
+            notified: NotifiedReadiness::default(),
+        }
+    }
+
+    pub fn process_event<C>(&mut self, timeout: Option<Duration>, event_callback: C)
     where C: FnMut(PollEvent) {
+        let mio_poll = self.mio_poll.as_mut().expect(EXTERNAL_POLL_ERR);
         loop {
-            match self.mio_poll.poll(&mut self.events, timeout) {
-                Ok(()) => {
-                    for mio_event in &self.events {
-                        if Self::WAKER_TOKEN == mio_event.token() {
-                            log::trace!("POLL WAKER EVENT");
-                            event_callback(PollEvent::Waker);
-                        }
-                        else {
-                            let id = ResourceId::from(mio_event.token());
-                            if mio_event.is_readable() {
-                                log::trace!("POLL EVENT (R): {}", id);
-                                event_callback(PollEvent::Network(id, Readiness::Read));
-                            }
-                            if mio_event.is_writable() {
-                                log::trace!("POLL EVENT (W): {}", id);
-                                event_callback(PollEvent::Network(id, Readiness::Write));
-                            }
-                        }
-                    }
-                    break
-                }
+            match mio_poll.poll(&mut self.events, timeout) {
+                Ok(()) => break Self::process_events(&self.notified, &self.events, event_callback),
                 Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
                 Err(ref err) => panic!("{}: No error here", err),
             }
         }
     }
 
+    /// Processes the events of the poll that belong to the resources,
+    /// skipping the events of other sources registered in the same poll.
+    pub fn process_ready<C>(&self, events: &Events, event_callback: C)
+    where C: FnMut(PollEvent) {
+        Self::process_events(&self.notified, events, event_callback)
+    }
+
+    fn process_events<C>(notified: &NotifiedReadiness, events: &Events, mut event_callback: C)
+    where C: FnMut(PollEvent) {
+        for mio_event in events {
+            if Self::WAKER_TOKEN == mio_event.token() {
+                log::trace!("POLL WAKER EVENT");
+                let notified = std::mem::take(&mut *notified.lock().expect(OTHER_THREAD_ERR));
+                for (id, readiness) in notified {
+                    log::trace!("POLL NOTIFIED EVENT ({:?}): {}", readiness, id);
+                    event_callback(PollEvent::Network(id, readiness));
+                }
+                event_callback(PollEvent::Waker);
+            }
+            else if mio_event.token().0 & 1 == 1 {
+                let id = ResourceId::from(mio_event.token());
+                // Errors are reported to be read from the resource.
+                if mio_event.is_readable() || mio_event.is_error() {
+                    log::trace!("POLL EVENT (R): {}", id);
+                    event_callback(PollEvent::Network(id, Readiness::Read));
+                }
+                if mio_event.is_writable() {
+                    log::trace!("POLL EVENT (W): {}", id);
+                    event_callback(PollEvent::Network(id, Readiness::Write));
+                }
+            }
+        }
+    }
+
     pub fn create_registry(&mut self, adapter_id: u8, resource_type: ResourceType) -> PollRegistry {
-        PollRegistry::new(adapter_id, resource_type, self.mio_poll.registry().try_clone().unwrap())
+        PollRegistry::new(
+            adapter_id,
+            resource_type,
+            self.registry.try_clone().unwrap(),
+            ResourceNotifier { waker: self.waker.clone(), notified: self.notified.clone() },
+        )
     }
 
-    #[allow(dead_code)] //TODO: remove it with poll native event support
     pub fn create_waker(&mut self) -> PollWaker {
         PollWaker::new(self.waker.clone())
     }
@@ -101,22 +146,30 @@ impl Poll {
 pub struct PollRegistry {
     id_generator: Arc<ResourceIdGenerator>,
     registry: Registry,
+    notifier: ResourceNotifier,
 }
 
 impl PollRegistry {
-    fn new(adapter_id: u8, resource_type: ResourceType, registry: Registry) -> Self {
+    fn new(
+        adapter_id: u8,
+        resource_type: ResourceType,
+        registry: Registry,
+        notifier: ResourceNotifier,
+    ) -> Self {
         Self {
             id_generator: Arc::new(ResourceIdGenerator::new(adapter_id, resource_type)),
             registry,
+            notifier,
         }
     }
 
-    pub fn add(&self, source: &mut dyn Source, write_readiness: bool) -> ResourceId {
+    /// Notifier of the readiness of the resource `id`.
+    pub fn notifier(&self, id: ResourceId) -> ReadinessNotifier {
+        ReadinessNotifier { id, notifier: self.notifier.clone() }
+    }
+
+    pub fn add(&self, source: &mut dyn Source, interest: Interest) -> ResourceId {
         let id = self.id_generator.generate();
-        let interest = match write_readiness {
-            true => Interest::READABLE | Interest::WRITABLE,
-            false => Interest::READABLE,
-        };
         self.registry.register(source, id.into(), interest).unwrap();
         id
     }
@@ -124,6 +177,23 @@ impl PollRegistry {
     pub fn remove(&self, source: &mut dyn Source) {
         self.registry.deregister(source).unwrap()
     }
+
+    /// Registers again a `source` removed by [`PollRegistry::remove()`], with its former `id`.
+    pub fn restore(&self, id: ResourceId, source: &mut dyn Source, interest: Interest) {
+        self.registry.register(source, id.into(), interest).unwrap();
+    }
+
+    /// Registers `source` with the `id` of `previous`, that is removed.
+    pub fn replace(
+        &self,
+        id: ResourceId,
+        previous: &mut dyn Source,
+        source: &mut dyn Source,
+        interest: Interest,
+    ) {
+        self.registry.deregister(previous).unwrap();
+        self.registry.register(source, id.into(), interest).unwrap();
+    }
 }
 
 impl Clone for PollRegistry {
@@ -131,22 +201,45 @@ impl Clone for PollRegistry {
         Self {
             id_generator: self.id_generator.clone(),
             registry: self.registry.try_clone().unwrap(),
+            notifier: self.notifier.clone(),
         }
     }
 }
 
-#[allow(dead_code)] //TODO: remove it with poll native event support
+#[derive(Clone)]
+struct ResourceNotifier {
+    waker: Arc<Waker>,
+    notified: NotifiedReadiness,
+}
+
+/// Used for the adapter implementation.
+/// Generates the readiness events of a resource whose source is not an OS resource
+/// (e.g. a channel in memory), so it is never made ready by the poll.
+/// See [`crate::network::adapter::Resource::set_notifier()`].
+#[derive(Clone)]
+pub struct ReadinessNotifier {
+    id: ResourceId,
+    notifier: ResourceNotifier,
+}
+
+impl ReadinessNotifier {
+    /// Generates a `readiness` event for the resource, waking up the poll.
+    /// It can be called from any thread.
+    pub fn notify(&self, readiness: Readiness) {
+        self.notifier.notified.lock().expect(OTHER_THREAD_ERR).push((self.id, readiness));
+        self.notifier.waker.wake().unwrap();
+    }
+}
+
 pub struct PollWaker {
     waker: Arc<Waker>,
 }
 
 impl PollWaker {
-    #[allow(dead_code)] //TODO: remove it with poll native event support
     fn new(waker: Arc<Waker>) -> Self {
         Self { waker }
     }
 
-    #[allow(dead_code)] //TODO: remove it with poll native event support
     pub fn wake(&self) {
         self.waker.wake().unwrap();
         log::trace!("Wake poll...");
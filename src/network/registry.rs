@@ -1,28 +1,64 @@
 use super::resource_id::{ResourceId};
 use super::poll::{PollRegistry};
-use super::adapter::{Resource};
+use super::adapter::{Resource, Local};
 
 use crate::util::thread::{OTHER_THREAD_ERR};
 
+use mio::{Interest};
+use mio::event::{Source};
+
 use std::collections::{HashMap};
-use std::sync::{Arc, RwLock};
+use std::sync::{
+    Arc, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
 
 pub struct Register<S: Resource, P> {
     pub resource: S,
     pub properties: P,
 
     poll_registry: Arc<PollRegistry>,
+    paused: AtomicBool, // The source is removed from the poll by pause_source().
 }
 
 impl<S: Resource, P> Register<S, P> {
     fn new(resource: S, properties: P, poll_registry: Arc<PollRegistry>) -> Self {
-        Self { resource, properties, poll_registry }
+        Self { resource, properties, poll_registry, paused: AtomicBool::new(false) }
+    }
+}
+
+impl<L: Local, P> Register<L, P> {
+    /// Removes the source of the local resource from the poll,
+    /// so it generates no events until [`Register::resume_source()`] is called.
+    pub fn pause_source(&self) {
+        if !self.paused.load(Ordering::Relaxed) {
+            self.resource.update_source(&mut |source| {
+                self.poll_registry.remove(source);
+                self.paused.store(true, Ordering::Relaxed);
+            });
+        }
+    }
+
+    /// Returns `true` if the source has been removed by [`Register::pause_source()`].
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Registers again in the poll the source removed by [`Register::pause_source()`].
+    pub fn resume_source(&self, id: ResourceId, interest: Interest) {
+        if self.paused.swap(false, Ordering::Relaxed) {
+            self.resource.update_source(&mut |source| {
+                self.poll_registry.restore(id, source, interest);
+            });
+        }
     }
 }
 
 impl<S: Resource, P> Drop for Register<S, P> {
     fn drop(&mut self) {
-        self.poll_registry.remove(self.resource.source());
+        if !*self.paused.get_mut() {
+            self.poll_registry.remove(self.resource.source());
+        }
     }
 }
 
@@ -40,11 +76,12 @@ impl<S: Resource, P> ResourceRegistry<S, P> {
     }
 
     /// Add a resource into the registry.
-    pub fn register(&self, mut resource: S, properties: P, write_readiness: bool) -> ResourceId {
+    pub fn register(&self, mut resource: S, properties: P, interest: Interest) -> ResourceId {
         // The registry must be locked for the entire implementation to avoid the poll
         // to generate events over not yet registered resources.
         let mut registry = self.resources.write().expect(OTHER_THREAD_ERR);
-        let id = self.poll_registry.add(resource.source(), write_readiness);
+        let id = self.poll_registry.add(resource.source(), interest);
+        resource.set_notifier(self.poll_registry.notifier(id));
         let register = Register::new(resource, properties, self.poll_registry.clone());
         registry.insert(id, Arc::new(register));
         id
@@ -58,8 +95,28 @@ impl<S: Resource, P> ResourceRegistry<S, P> {
         self.resources.write().expect(OTHER_THREAD_ERR).remove(&id).is_some()
     }
 
+    /// Registers `source` in the poll as the source of the resource `id`,
+    /// in place of `previous`.
+    pub fn replace_source(
+        &self,
+        id: ResourceId,
+        previous: &mut dyn Source,
+        source: &mut dyn Source,
+        interest: Interest,
+    ) {
+        self.poll_registry.replace(id, previous, source, interest);
+    }
+
     /// Returned a shared reference of the register.
     pub fn get(&self, id: ResourceId) -> Option<Arc<Register<S, P>>> {
         self.resources.read().expect(OTHER_THREAD_ERR).get(&id).cloned()
     }
+
+    /// Returns the ids of the registered resources, in the order they were registered.
+    pub fn ids(&self) -> Vec<ResourceId> {
+        let registry = self.resources.read().expect(OTHER_THREAD_ERR);
+        let mut ids: Vec<_> = registry.keys().copied().collect();
+        ids.sort_by_key(|id| id.raw());
+        ids
+    }
 }
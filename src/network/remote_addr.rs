@@ -43,6 +43,74 @@ impl RemoteAddr {
             _ => panic!("The RemoteAddr must be a String"),
         }
     }
+
+    /// Resolves the address into a [`SocketAddr`] following the given [`ResolvePreference`].
+    /// Only strings with the `'host:port'` format are resolved,
+    /// the rest of strings (e.g. urls) are returned as they are.
+    /// An error is returned if no address of the allowed family could be found.
+    pub(crate) fn resolve(self, preference: ResolvePreference) -> io::Result<RemoteAddr> {
+        let addrs: Vec<SocketAddr> = match &self {
+            RemoteAddr::Socket(addr) => vec![*addr],
+            RemoteAddr::Str(string) if !string.contains("://") => {
+                string.to_socket_addrs()?.collect()
+            }
+            RemoteAddr::Str(_) => return Ok(self),
+        };
+
+        match preference.choose(&addrs) {
+            Some(addr) => Ok(RemoteAddr::Socket(addr)),
+            None => {
+                let family = preference.family();
+                let msg = format!("No {family} address found for '{self}' ({preference:?})");
+                Err(io::Error::new(io::ErrorKind::AddrNotAvailable, msg))
+            }
+        }
+    }
+}
+
+/// Preference about the IP family of the address used to connect when a host name
+/// resolves to several addresses.
+/// It is set by [`NetworkController::set_resolve_preference()`](
+/// crate::network::NetworkController::set_resolve_preference())
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ResolvePreference {
+    /// Use the first address given by the system resolver.
+    #[default]
+    System,
+
+    /// Use the first IPv4 address if any, otherwise, the first address.
+    PreferV4,
+
+    /// Use the first IPv6 address if any, otherwise, the first address.
+    PreferV6,
+
+    /// Use the first IPv4 address. Connecting to a host without IPv4 addresses fails.
+    V4Only,
+
+    /// Use the first IPv6 address. Connecting to a host without IPv6 addresses fails.
+    V6Only,
+}
+
+impl ResolvePreference {
+    fn choose(self, addrs: &[SocketAddr]) -> Option<SocketAddr> {
+        let first_v4 = || addrs.iter().find(|addr| addr.is_ipv4()).copied();
+        let first_v6 = || addrs.iter().find(|addr| addr.is_ipv6()).copied();
+        match self {
+            ResolvePreference::System => addrs.first().copied(),
+            ResolvePreference::PreferV4 => first_v4().or_else(|| addrs.first().copied()),
+            ResolvePreference::PreferV6 => first_v6().or_else(|| addrs.first().copied()),
+            ResolvePreference::V4Only => first_v4(),
+            ResolvePreference::V6Only => first_v6(),
+        }
+    }
+
+    fn family(self) -> &'static str {
+        match self {
+            ResolvePreference::V4Only => "IPv4",
+            ResolvePreference::V6Only => "IPv6",
+            _ => "IP",
+        }
+    }
 }
 
 impl ToSocketAddrs for RemoteAddr {
@@ -183,4 +251,30 @@ mod tests {
         let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
         assert!(socket_addr.to_remote_addr().unwrap().is_socket_addr());
     }
+
+    #[test]
+    fn resolve_preference() {
+        let v4 = SocketAddr::from(([127, 0, 0, 1], 80));
+        let v6 = SocketAddr::from(([0, 0, 0, 0, 0, 0, 0, 1], 80));
+        let addrs = [v6, v4];
+        assert_eq!(ResolvePreference::System.choose(&addrs), Some(v6));
+        assert_eq!(ResolvePreference::PreferV4.choose(&addrs), Some(v4));
+        assert_eq!(ResolvePreference::PreferV6.choose(&addrs), Some(v6));
+        assert_eq!(ResolvePreference::PreferV4.choose(&[v6]), Some(v6));
+        assert_eq!(ResolvePreference::V4Only.choose(&[v6]), None);
+        assert_eq!(ResolvePreference::V6Only.choose(&[v4]), None);
+    }
+
+    #[test]
+    fn resolve_url_untouched() {
+        let addr = "ws://domain:1234/socket".to_remote_addr().unwrap();
+        assert_eq!(addr.clone().resolve(ResolvePreference::V6Only).unwrap(), addr);
+    }
+
+    #[test]
+    fn resolve_v6_only_v4_host() {
+        let addr = RemoteAddr::Str("127.0.0.1:80".into());
+        let err = addr.resolve(ResolvePreference::V6Only).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrNotAvailable);
+    }
 }
@@ -14,6 +14,12 @@ pub enum ResourceType {
 /// - The type, that can be a value of [ResourceType].
 /// - The adapter id, that represents the adapter that creates this id
 /// - The base value: that is an unique identifier of the resource inside of its adapter.
+///
+/// The ids are never reused: the base values are given by a counter of each adapter,
+/// so a resource always has a higher base value than the resources created before it
+/// by the same adapter, even if they were removed or disconnected.
+/// This way, an id identifies a single connection along the whole life of the network,
+/// and it can be used to correlate the logs of that connection.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ResourceId {
     id: usize,
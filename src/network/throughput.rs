@@ -0,0 +1,24 @@
+use super::endpoint::{Endpoint};
+
+use std::time::{Duration};
+
+/// Amount of data exchanged by a connection during an interval of time.
+/// It is generated by [`crate::network::NetEvent::Throughput`] once the sampling is enabled by
+/// [`crate::network::NetworkController::set_throughput_sampling()`].
+///
+/// Only the payload of the messages is counted, not the bytes added by the framing
+/// or the protocol of the transport.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ThroughputSample {
+    /// Endpoint of the connection.
+    pub endpoint: Endpoint,
+
+    /// Bytes received by the connection during the interval.
+    pub bytes_in: u64,
+
+    /// Bytes sent by the connection during the interval.
+    pub bytes_out: u64,
+
+    /// Time elapsed since the previous sample.
+    pub interval: Duration,
+}
@@ -0,0 +1,117 @@
+use super::adapter::{SendStatus};
+
+use crate::util::thread::{OTHER_THREAD_ERR};
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Progress of a message sent by [`crate::network::NetworkController::send_tracked()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TicketStatus {
+    /// The message is queued, or being written, and not completely written yet.
+    Pending,
+
+    /// The last byte of the message was accepted by the OS.
+    /// It does not mean that the peer has received it.
+    Written,
+
+    /// The message will not be written: it was not sent, it was dropped from the queue,
+    /// or the connection was removed before writing it.
+    Failed,
+}
+
+#[derive(Default)]
+struct TicketState {
+    status: Mutex<Option<bool>>, // None while pending, and if it was written when finished.
+    finished: Condvar,
+}
+
+impl TicketState {
+    fn finish(&self, written: bool) {
+        let mut status = self.status.lock().expect(OTHER_THREAD_ERR);
+        if status.is_none() {
+            *status = Some(written);
+            self.finished.notify_all();
+        }
+    }
+}
+
+fn ticket_status(status: Option<bool>) -> TicketStatus {
+    match status {
+        None => TicketStatus::Pending,
+        Some(true) => TicketStatus::Written,
+        Some(false) => TicketStatus::Failed,
+    }
+}
+
+/// Handle to query or wait until a message sent by
+/// [`crate::network::NetworkController::send_tracked()`] is completely written.
+#[derive(Clone)]
+pub struct SendTicket {
+    state: Arc<TicketState>,
+    send_status: SendStatus,
+}
+
+impl SendTicket {
+    pub(crate) fn new() -> (Self, SendCompletion) {
+        let state = Arc::new(TicketState::default());
+        let ticket = Self { state: state.clone(), send_status: SendStatus::Sent };
+        (ticket, SendCompletion { state: Some(state) })
+    }
+
+    pub(crate) fn with_send_status(mut self, send_status: SendStatus) -> Self {
+        self.send_status = send_status;
+        self
+    }
+
+    /// The status returned by the send, as [`crate::network::NetworkController::send()`].
+    pub fn send_status(&self) -> SendStatus {
+        self.send_status
+    }
+
+    /// Current status of the message.
+    pub fn status(&self) -> TicketStatus {
+        ticket_status(*self.state.status.lock().expect(OTHER_THREAD_ERR))
+    }
+
+    /// Blocks until the message is written or fails, or until `timeout` is reached.
+    /// Returns the status of the message, that is [`TicketStatus::Pending`] if the timeout
+    /// was reached.
+    pub fn wait(&self, timeout: Duration) -> TicketStatus {
+        let deadline = Instant::now() + timeout;
+        let mut status = self.state.status.lock().expect(OTHER_THREAD_ERR);
+        while status.is_none() {
+            let now = Instant::now();
+            if now >= deadline {
+                break
+            }
+            let finished = &self.state.finished;
+            status = finished.wait_timeout(status, deadline - now).expect(OTHER_THREAD_ERR).0;
+        }
+        ticket_status(*status)
+    }
+}
+
+/// Side of a [`SendTicket`] held by the resource that writes the message.
+/// The resource calls [`SendCompletion::complete()`] once the last byte of the message is
+/// written. If it is dropped without completing, the ticket fails.
+pub struct SendCompletion {
+    state: Option<Arc<TicketState>>,
+}
+
+impl SendCompletion {
+    /// Marks the message as [`TicketStatus::Written`].
+    pub fn complete(mut self) {
+        if let Some(state) = self.state.take() {
+            state.finish(true);
+        }
+    }
+}
+
+impl Drop for SendCompletion {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.take() {
+            state.finish(false);
+        }
+    }
+}
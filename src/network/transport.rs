@@ -7,10 +7,16 @@ use crate::adapters::framed_tcp::{FramedTcpAdapter, FramedTcpConnectConfig, Fram
 #[cfg(feature = "udp")]
 use crate::adapters::udp::{self, UdpAdapter, UdpConnectConfig, UdpListenConfig};
 #[cfg(feature = "websocket")]
-use crate::adapters::ws::{self, WsAdapter};
+use crate::adapters::ws::{self, WsAdapter, WsConnectConfig, WsListenConfig, WsUpgraded};
+#[cfg(feature = "in-process")]
+use crate::adapters::in_process::{
+    InProcessAdapter, InProcessConnectConfig, InProcessListenConfig,
+};
 
 use serde::{Serialize, Deserialize};
 
+use std::collections::{HashMap};
+
 /// Enum to identified the underlying transport used.
 /// It can be passed to
 /// [`NetworkController::connect()`](crate::network::NetworkController::connect()) and
@@ -22,6 +28,8 @@ pub enum Transport {
     /// As stream protocol, receiving a message from TCP do not imply to read
     /// the entire message.
     /// If you want a packet based way to send over TCP, use `FramedTcp` instead.
+    /// Each message event contains the data of a single read of the socket,
+    /// see [`crate::adapters::tcp::INPUT_BUFFER_SIZE`].
     #[cfg(feature = "tcp")]
     Tcp,
 
@@ -45,9 +53,21 @@ pub enum Transport {
     /// If you use a [`crate::network::RemoteAddr::Str`] in the `connect()` method,
     /// you can specify an URL with `wss` of `ws` schemas to connect with or without security.
     /// If you use a [`crate::network::RemoteAddr::Socket`] the socket will be a normal
-    /// websocket with the following uri: `ws://{SocketAddr}/message-io-default`.
+    /// websocket with the uri `ws://{SocketAddr}{path}`, where the path is
+    /// `/message-io-default` unless other is given by
+    /// [`crate::adapters::ws::WsConnectConfig::with_path()`].
     #[cfg(feature = "websocket")]
     Ws,
+
+    /// In-process transport (available through the *in-process* feature).
+    /// The messages are exchanged in memory between the networks of the same process,
+    /// without using the OS network stack, so it is useful for testing and benchmarking.
+    /// Like `FramedTcp`, it is connection oriented, packet based and reliable.
+    /// The listeners are registered in the process by a virtual address
+    /// (a port `0` gives an unused one) and optionally by a name, see
+    /// [`crate::adapters::in_process::InProcessListenConfig::with_name()`].
+    #[cfg(feature = "in-process")]
+    InProcess,
 }
 
 impl Transport {
@@ -63,6 +83,8 @@ impl Transport {
             Self::Udp => loader.mount(self.id(), UdpAdapter),
             #[cfg(feature = "websocket")]
             Self::Ws => loader.mount(self.id(), WsAdapter),
+            #[cfg(feature = "in-process")]
+            Self::InProcess => loader.mount(self.id(), InProcessAdapter),
         };
     }
 
@@ -72,6 +94,9 @@ impl Transport {
     /// all networks.
     /// You can ensure your message not exceeds `udp::MAX_INTERNET_PAYLOAD_LEN` in order to be
     /// more cross-platform compatible.
+    ///
+    /// Some options reduce this value, as the UDP checksum. Use
+    /// [`TransportConnect::max_message_size()`] to get the value of a specific configuration.
     pub const fn max_message_size(self) -> usize {
         match self {
             #[cfg(feature = "tcp")]
@@ -82,6 +107,8 @@ impl Transport {
             Self::Udp => udp::MAX_LOCAL_PAYLOAD_LEN,
             #[cfg(feature = "websocket")]
             Self::Ws => ws::MAX_PAYLOAD_LEN,
+            #[cfg(feature = "in-process")]
+            Self::InProcess => usize::MAX,
         }
     }
 
@@ -97,6 +124,8 @@ impl Transport {
             Transport::Udp => false,
             #[cfg(feature = "websocket")]
             Transport::Ws => true,
+            #[cfg(feature = "in-process")]
+            Transport::InProcess => true,
         }
     }
 
@@ -116,6 +145,8 @@ impl Transport {
             Transport::Udp => true,
             #[cfg(feature = "websocket")]
             Transport::Ws => true,
+            #[cfg(feature = "in-process")]
+            Transport::InProcess => true,
         }
     }
 
@@ -131,6 +162,8 @@ impl Transport {
             Transport::Udp => 2,
             #[cfg(feature = "websocket")]
             Transport::Ws => 3,
+            #[cfg(feature = "in-process")]
+            Transport::InProcess => 4,
         }
     }
 }
@@ -146,6 +179,8 @@ impl From<u8> for Transport {
             2 => Transport::Udp,
             #[cfg(feature = "websocket")]
             3 => Transport::Ws,
+            #[cfg(feature = "in-process")]
+            4 => Transport::InProcess,
             _ => panic!("Not available transport"),
         }
     }
@@ -166,7 +201,13 @@ pub enum TransportConnect {
     #[cfg(feature = "udp")]
     Udp(UdpConnectConfig),
     #[cfg(feature = "websocket")]
-    Ws,
+    Ws(WsConnectConfig),
+    /// Registers a WebSocket connection already established outside message-io.
+    /// See [`WsUpgraded`].
+    #[cfg(feature = "websocket")]
+    WsUpgraded(WsUpgraded),
+    #[cfg(feature = "in-process")]
+    InProcess(InProcessConnectConfig),
 }
 
 impl TransportConnect {
@@ -179,11 +220,47 @@ impl TransportConnect {
             #[cfg(feature = "udp")]
             Self::Udp(_) => Transport::Udp,
             #[cfg(feature = "websocket")]
-            Self::Ws => Transport::Ws,
+            Self::Ws(_) => Transport::Ws,
+            #[cfg(feature = "websocket")]
+            Self::WsUpgraded(_) => Transport::Ws,
+            #[cfg(feature = "in-process")]
+            Self::InProcess(_) => Transport::InProcess,
         };
 
         transport.id()
     }
+
+    /// Copy of the options to be used by another connection.
+    /// It returns `None` for the options that can only be used once,
+    /// as [`TransportConnect::WsUpgraded`].
+    fn try_clone(&self) -> Option<Self> {
+        Some(match self {
+            #[cfg(feature = "tcp")]
+            Self::Tcp(config) => Self::Tcp(config.clone()),
+            #[cfg(feature = "tcp")]
+            Self::FramedTcp(config) => Self::FramedTcp(config.clone()),
+            #[cfg(feature = "udp")]
+            Self::Udp(config) => Self::Udp(config.clone()),
+            #[cfg(feature = "websocket")]
+            Self::Ws(config) => Self::Ws(config.clone()),
+            #[cfg(feature = "websocket")]
+            Self::WsUpgraded(_) => return None,
+            #[cfg(feature = "in-process")]
+            Self::InProcess(config) => Self::InProcess(config.clone()),
+        })
+    }
+
+    /// Maximum message size that can be sent with this configuration.
+    /// It is the [`Transport::max_message_size()`] of the transport,
+    /// reduced by the options that use part of the message, as the UDP checksum.
+    pub fn max_message_size(&self) -> usize {
+        match self {
+            #[cfg(feature = "udp")]
+            Self::Udp(config) => config.max_message_size(),
+            #[allow(unreachable_patterns)]
+            _ => Transport::from(self.id()).max_message_size(),
+        }
+    }
 }
 
 impl From<Transport> for TransportConnect {
@@ -196,12 +273,14 @@ impl From<Transport> for TransportConnect {
             #[cfg(feature = "udp")]
             Transport::Udp => Self::Udp(UdpConnectConfig::default()),
             #[cfg(feature = "websocket")]
-            Transport::Ws => Self::Ws,
+            Transport::Ws => Self::Ws(WsConnectConfig::default()),
+            #[cfg(feature = "in-process")]
+            Transport::InProcess => Self::InProcess(InProcessConnectConfig::default()),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum TransportListen {
     #[cfg(feature = "tcp")]
     Tcp(TcpListenConfig),
@@ -210,7 +289,9 @@ pub enum TransportListen {
     #[cfg(feature = "udp")]
     Udp(UdpListenConfig),
     #[cfg(feature = "websocket")]
-    Ws,
+    Ws(WsListenConfig),
+    #[cfg(feature = "in-process")]
+    InProcess(InProcessListenConfig),
 }
 
 impl TransportListen {
@@ -223,11 +304,25 @@ impl TransportListen {
             #[cfg(feature = "udp")]
             Self::Udp(_) => Transport::Udp,
             #[cfg(feature = "websocket")]
-            Self::Ws => Transport::Ws,
+            Self::Ws(_) => Transport::Ws,
+            #[cfg(feature = "in-process")]
+            Self::InProcess(_) => Transport::InProcess,
         };
 
         transport.id()
     }
+
+    /// Maximum message size that can be sent with this configuration.
+    /// It is the [`Transport::max_message_size()`] of the transport,
+    /// reduced by the options that use part of the message, as the UDP checksum.
+    pub fn max_message_size(&self) -> usize {
+        match self {
+            #[cfg(feature = "udp")]
+            Self::Udp(config) => config.max_message_size(),
+            #[allow(unreachable_patterns)]
+            _ => Transport::from(self.id()).max_message_size(),
+        }
+    }
 }
 
 impl From<Transport> for TransportListen {
@@ -240,7 +335,57 @@ impl From<Transport> for TransportListen {
             #[cfg(feature = "udp")]
             Transport::Udp => Self::Udp(UdpListenConfig::default()),
             #[cfg(feature = "websocket")]
-            Transport::Ws => Self::Ws,
+            Transport::Ws => Self::Ws(WsListenConfig::default()),
+            #[cfg(feature = "in-process")]
+            Transport::InProcess => Self::InProcess(InProcessListenConfig::default()),
+        }
+    }
+}
+
+/// Options used by a network for the transports used without explicit options,
+/// as in [`NetworkController::connect()`](crate::network::NetworkController::connect()) or
+/// [`NetworkController::listen()`](crate::network::NetworkController::listen()).
+///
+/// The options of a connection or listener are chosen with the following precedence:
+/// 1. The options given in the call, as in
+///    [`NetworkController::connect_with()`](crate::network::NetworkController::connect_with()).
+///    They replace the defaults, they are not merged with them.
+/// 2. The defaults of the network for the transport, set here.
+/// 3. The default options of the transport, as `TcpConnectConfig::default()`.
+#[derive(Debug, Default)]
+pub struct TransportDefaults {
+    connect: HashMap<Transport, TransportConnect>,
+    listen: HashMap<Transport, TransportListen>,
+}
+
+impl TransportDefaults {
+    /// Sets the default options for the connections of the transport of `config`.
+    /// It panics if the options can only be used once, as [`TransportConnect::WsUpgraded`].
+    pub fn with_connect(mut self, config: TransportConnect) -> Self {
+        assert!(config.try_clone().is_some(), "The options can not be used as default");
+        self.connect.insert(Transport::from(config.id()), config);
+        self
+    }
+
+    /// Sets the default options for the listeners of the transport of `config`.
+    pub fn with_listen(mut self, config: TransportListen) -> Self {
+        self.listen.insert(Transport::from(config.id()), config);
+        self
+    }
+
+    /// Options to connect by `transport`.
+    pub fn connect(&self, transport: Transport) -> TransportConnect {
+        match self.connect.get(&transport) {
+            Some(config) => config.try_clone().expect("Reusable options"),
+            None => transport.into(),
+        }
+    }
+
+    /// Options to listen by `transport`.
+    pub fn listen(&self, transport: Transport) -> TransportListen {
+        match self.listen.get(&transport) {
+            Some(config) => config.clone(),
+            None => transport.into(),
         }
     }
 }
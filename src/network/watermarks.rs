@@ -0,0 +1,65 @@
+/// Thresholds of the data queued by a connection to be written,
+/// used to pause and resume the source of that data.
+/// When the queued bytes reach `high`, a [`crate::network::NetEvent::Watermark`] with
+/// [`Watermark::High`] is generated, and once they drop to `low` or below,
+/// other one with [`Watermark::Low`].
+/// It is set by [`crate::network::NetworkController::set_watermarks()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Watermarks {
+    low: usize,
+    high: usize,
+}
+
+impl Watermarks {
+    /// Creates the watermarks of `low` and `high` queued bytes.
+    /// It panics if `low` is not lower than `high`.
+    pub fn new(low: usize, high: usize) -> Self {
+        assert!(low < high, "The low watermark must be lower than the high one");
+        Self { low, high }
+    }
+
+    /// Queued bytes at or below which the source of data can be resumed.
+    pub fn low(&self) -> usize {
+        self.low
+    }
+
+    /// Queued bytes at or above which the source of data should be paused.
+    pub fn high(&self) -> usize {
+        self.high
+    }
+}
+
+/// Watermark crossed by the queued data of a connection.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Watermark {
+    /// The queued data has reached the high watermark: the source of data should be paused.
+    High,
+
+    /// The queued data has dropped to the low watermark, after reaching the high one:
+    /// the source of data can be resumed.
+    Low,
+}
+
+pub(crate) struct WatermarkTracker {
+    watermarks: Watermarks,
+    above_high: bool, // The high watermark was reached and the low one not yet.
+}
+
+impl WatermarkTracker {
+    pub fn new(watermarks: Watermarks) -> Self {
+        Self { watermarks, above_high: false }
+    }
+
+    /// Returns the watermark crossed by the queue with `queued` bytes, if any.
+    pub fn update(&mut self, queued: usize) -> Option<Watermark> {
+        if !self.above_high && queued >= self.watermarks.high {
+            self.above_high = true;
+            return Some(Watermark::High)
+        }
+        if self.above_high && queued <= self.watermarks.low {
+            self.above_high = false;
+            return Some(Watermark::Low)
+        }
+        None
+    }
+}
@@ -1,13 +1,26 @@
 use crate::network::{self, NetworkController, NetworkProcessor, NetEvent, Endpoint, ResourceId};
+use crate::network::{TransportConnect, TransportListen, TransportDefaults, ControlFrame};
+use crate::network::{Transport, ThroughputSample, RemoteAddr, Watermark};
 use crate::events::{self, EventSender, EventReceiver};
 use crate::util::thread::{NamespacedThread, OTHER_THREAD_ERR};
 
+use strum::{IntoEnumIterator};
+
 use std::sync::{
     Arc, Mutex,
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
 };
-use std::time::{Duration};
+use std::time::{Duration, Instant};
 use std::collections::{VecDeque};
+use std::collections::hash_map::{DefaultHasher};
+use std::hash::{Hash, Hasher};
+use std::io::{self};
+use std::panic::{self, AssertUnwindSafe};
+
+#[cfg(all(feature = "udp", unix))]
+use crate::adapters::udp::{UdpListenConfig};
+#[cfg(all(feature = "udp", unix))]
+use std::net::{SocketAddr, ToSocketAddrs};
 
 lazy_static::lazy_static! {
     static ref SAMPLING_TIMEOUT: Duration = Duration::from_millis(50);
@@ -111,19 +124,37 @@ impl<S> From<NodeEvent<'_, S>> for StoredNodeEvent<S> {
 /// and can be easily stored in any container.
 #[derive(Debug, Clone)]
 pub enum StoredNetEvent {
+    Connecting(Endpoint, RemoteAddr),
     Connected(Endpoint, bool),
+    ConnectFailed(Endpoint, RemoteAddr),
     Accepted(Endpoint, ResourceId),
     Message(Endpoint, Vec<u8>),
     Disconnected(Endpoint),
+    Error(Endpoint, io::ErrorKind),
+    Control(Endpoint, ControlFrame, Vec<u8>),
+    Throughput(ThroughputSample),
+    NetworkChanged,
+    Watermark(Endpoint, Watermark),
 }
 
 impl From<NetEvent<'_>> for StoredNetEvent {
     fn from(net_event: NetEvent<'_>) -> Self {
         match net_event {
+            NetEvent::Connecting(endpoint, target) => Self::Connecting(endpoint, target.clone()),
             NetEvent::Connected(endpoint, status) => Self::Connected(endpoint, status),
+            NetEvent::ConnectFailed(endpoint, target) => {
+                Self::ConnectFailed(endpoint, target.clone())
+            }
             NetEvent::Accepted(endpoint, id) => Self::Accepted(endpoint, id),
             NetEvent::Message(endpoint, data) => Self::Message(endpoint, Vec::from(data)),
             NetEvent::Disconnected(endpoint) => Self::Disconnected(endpoint),
+            NetEvent::Error(endpoint, kind) => Self::Error(endpoint, kind),
+            NetEvent::Control(endpoint, frame, data) => {
+                Self::Control(endpoint, frame, Vec::from(data))
+            }
+            NetEvent::Throughput(sample) => Self::Throughput(sample),
+            NetEvent::NetworkChanged => Self::NetworkChanged,
+            NetEvent::Watermark(endpoint, watermark) => Self::Watermark(endpoint, watermark),
         }
     }
 }
@@ -132,10 +163,17 @@ impl StoredNetEvent {
     /// Use this `StoredNetEvent` as a `NetEvent` referencing its data.
     pub fn borrow(&self) -> NetEvent<'_> {
         match self {
+            Self::Connecting(endpoint, target) => NetEvent::Connecting(*endpoint, target),
             Self::Connected(endpoint, status) => NetEvent::Connected(*endpoint, *status),
+            Self::ConnectFailed(endpoint, target) => NetEvent::ConnectFailed(*endpoint, target),
             Self::Accepted(endpoint, id) => NetEvent::Accepted(*endpoint, *id),
             Self::Message(endpoint, data) => NetEvent::Message(*endpoint, data),
             Self::Disconnected(endpoint) => NetEvent::Disconnected(*endpoint),
+            Self::Error(endpoint, kind) => NetEvent::Error(*endpoint, *kind),
+            Self::Control(endpoint, frame, data) => NetEvent::Control(*endpoint, *frame, data),
+            Self::Throughput(sample) => NetEvent::Throughput(*sample),
+            Self::NetworkChanged => NetEvent::NetworkChanged,
+            Self::Watermark(endpoint, watermark) => NetEvent::Watermark(*endpoint, *watermark),
         }
     }
 }
@@ -178,25 +216,141 @@ impl StoredNetEvent {
 /// let (handler, listener) = node::split::<()>();
 /// ```
 pub fn split<S: Send>() -> (NodeHandler<S>, NodeListener<S>) {
-    let (network_controller, network_processor) = network::split();
-    let (signal_sender, signal_receiver) = events::split();
-    let running = AtomicBool::new(true);
+    NodeBuilder::new().split()
+}
+
+/// Creates a node with custom options.
+/// Calling [`split()`] is equivalent to `NodeBuilder::new().split()`.
+///
+/// It allows setting the default options of each transport for the node,
+/// used by the connections and listeners created without explicit options,
+/// as in [`NetworkController::connect()`] or [`NetworkController::listen()`].
+/// The options given in a call, as in [`NetworkController::connect_with()`],
+/// replace the default options of the node, that replace the default options of the transport.
+/// See [`TransportDefaults`].
+///
+/// # Example
+/// ```
+/// use message_io::node::{NodeBuilder};
+/// use message_io::network::{Transport, TransportConnect, TransportListen};
+/// use message_io::adapters::tcp::{TcpConnectConfig, TcpListenConfig};
+///
+/// let (handler, listener) = NodeBuilder::new()
+///     .with_connect_default(TransportConnect::Tcp(TcpConnectConfig::default().with_nodelay()))
+///     .with_listen_default(TransportListen::Tcp(TcpListenConfig::default().with_nodelay()))
+///     .split::<()>();
+///
+/// // Both use the nodelay option.
+/// let (_, addr) = handler.network().listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+/// handler.network().connect(Transport::Tcp, addr).unwrap();
+/// ```
+#[derive(Debug, Default)]
+pub struct NodeBuilder {
+    transport_defaults: TransportDefaults,
+}
+
+impl NodeBuilder {
+    /// Creates a builder with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    let handler = NodeHandler(Arc::new(NodeHandlerImpl {
-        network: network_controller,
-        signals: signal_sender,
-        running,
-    }));
+    /// Sets the default options of the node for the connections of the transport of `config`.
+    /// See [`TransportDefaults::with_connect()`].
+    pub fn with_connect_default(mut self, config: TransportConnect) -> Self {
+        self.transport_defaults = self.transport_defaults.with_connect(config);
+        self
+    }
+
+    /// Sets the default options of the node for the listeners of the transport of `config`.
+    /// See [`TransportDefaults::with_listen()`].
+    pub fn with_listen_default(mut self, config: TransportListen) -> Self {
+        self.transport_defaults = self.transport_defaults.with_listen(config);
+        self
+    }
+
+    /// Creates the node, as [`split()`] does.
+    pub fn split<S: Send>(self) -> (NodeHandler<S>, NodeListener<S>) {
+        let (network_controller, network_processor) = network::split_with(self.transport_defaults);
+        let (signal_sender, signal_receiver) = events::split();
+        let running = AtomicBool::new(true);
+
+        let handler = NodeHandler(Arc::new(NodeHandlerImpl {
+            network: network_controller,
+            signals: signal_sender,
+            running,
+            stop_deadline: Mutex::new(None),
+            panic_reason: Mutex::new(None),
+        }));
+
+        let listener = NodeListener::new(network_processor, signal_receiver, handler.clone());
+
+        (handler, listener)
+    }
+}
+
+/// Creates a group of `count` nodes listening by UDP at the same address.
+/// Each node owns its own socket, bound with `SO_REUSEPORT`, so the datagrams received
+/// at `addr` are processed concurrently by the nodes, each one in its own thread.
+/// If the port of `addr` is `0`, the port chosen for the first socket is used by the rest.
+///
+/// For each node, the function returns its [`NodeHandler`], its [`NodeListener`] and the
+/// id of its listening resource. The last value is the address where the group listens.
+///
+/// Note that the datagrams are only balanced among the sockets on Linux, where the kernel
+/// distributes them by the hash of the sender address.
+/// Other unix systems will deliver all the datagrams to one of the sockets.
+///
+/// # Example
+/// ```rust,no_run
+/// use message_io::node::{self, NodeEvent};
+///
+/// let (nodes, addr) = node::split_udp_group::<()>("0.0.0.0:0", 4).unwrap();
+/// println!("Listening at {}", addr);
+///
+/// let tasks: Vec<_> = nodes
+///     .into_iter()
+///     .map(|(_handler, listener, _id)| {
+///         listener.for_each_async(move |event| match event {
+///             NodeEvent::Network(_) => { /* Processed in parallel */ }
+///             NodeEvent::Signal(_) => (),
+///         })
+///     })
+///     .collect();
+/// ```
+#[cfg(all(feature = "udp", unix))]
+#[allow(clippy::type_complexity)]
+pub fn split_udp_group<S: Send>(
+    addr: impl ToSocketAddrs,
+    count: usize,
+) -> io::Result<(Vec<(NodeHandler<S>, NodeListener<S>, ResourceId)>, SocketAddr)> {
+    let mut addr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No address to listen"))?;
+
+    let config = UdpListenConfig::default().with_reuse_port();
+
+    let mut nodes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (handler, listener) = split();
+        let (id, listening_addr) =
+            handler.network().listen_with(TransportListen::Udp(config.clone()), addr)?;
 
-    let listener = NodeListener::new(network_processor, signal_receiver, handler.clone());
+        // The following sockets reuse the port assigned to the first one.
+        addr = listening_addr;
+        nodes.push((handler, listener, id));
+    }
 
-    (handler, listener)
+    Ok((nodes, addr))
 }
 
 struct NodeHandlerImpl<S> {
     network: NetworkController,
     signals: EventSender<S>,
     running: AtomicBool,
+    stop_deadline: Mutex<Option<Instant>>, // Set by a graceful stop.
+    panic_reason: Mutex<Option<String>>,
 }
 
 /// A shareable and clonable entity that allows to deal with
@@ -224,12 +378,77 @@ impl<S> NodeHandler<S> {
         self.0.running.store(false, Ordering::Relaxed);
     }
 
+    /// Finalizes the [`NodeListener`] once the network is shut down.
+    /// The network is shut down by [`NetworkController::remove_all()`] with `timeout`,
+    /// and the node keeps processing its events until every connection is disconnected,
+    /// so the data already sent is written and the [`NetEvent::Disconnected`] are generated.
+    /// If some connection is not disconnected after `timeout`, the node is stopped anyway,
+    /// as [`NodeHandler::stop()`] does.
+    pub fn stop_graceful(&self, timeout: Duration) {
+        self.network().remove_all(timeout);
+        *self.0.stop_deadline.lock().expect(OTHER_THREAD_ERR) = Some(Instant::now() + timeout);
+    }
+
+    /// Stops the node if a graceful stop has finished.
+    /// It is checked by the network thread each time the events are processed,
+    /// so the last [`NetEvent::Disconnected`] has been already dispatched.
+    fn check_graceful_stop(&self) {
+        let stop_deadline = *self.0.stop_deadline.lock().expect(OTHER_THREAD_ERR);
+        if let Some(stop_deadline) = stop_deadline {
+            let disconnected = Transport::iter()
+                .all(|transport| self.network().connections_by_transport(transport).is_empty());
+            if disconnected || Instant::now() >= stop_deadline {
+                self.stop();
+            }
+        }
+    }
+
     /// Check if the node is running.
     /// Note that the node is running and listening events from its creation,
     /// not only once you call to [`NodeListener::for_each()`].
+    ///
+    /// If any of the internal threads of the node panics (for example, because the
+    /// event callback panics), the node is stopped and this function returns `false`.
+    /// See [`NodeHandler::panic_reason()`].
     pub fn is_running(&self) -> bool {
         self.0.running.load(Ordering::Relaxed)
     }
+
+    /// Returns the message of the panic that stopped the node, if it was stopped by a panic.
+    /// Only the first panic is stored, the following ones are usually a consequence of it.
+    ///
+    /// The panic is still propagated: by [`NodeListener::for_each()`] to its caller,
+    /// or when the [`NodeTask`] of [`NodeListener::for_each_async()`] is waited or dropped.
+    /// This allows a supervisor thread to check the health of the node and restart it.
+    pub fn panic_reason(&self) -> Option<String> {
+        self.0.panic_reason.lock().expect(OTHER_THREAD_ERR).clone()
+    }
+
+    /// Runs a task of the node.
+    /// If it panics, the node is stopped and the panic reason stored before continuing with
+    /// the unwinding. This way, the rest of the node threads finish instead of waiting forever.
+    fn run_guarded<T>(&self, task: impl FnOnce() -> T) -> T {
+        match panic::catch_unwind(AssertUnwindSafe(task)) {
+            Ok(value) => value,
+            Err(payload) => {
+                let reason = match payload.downcast_ref::<&str>() {
+                    Some(message) => message.to_string(),
+                    None => match payload.downcast_ref::<String>() {
+                        Some(message) => message.clone(),
+                        None => String::from("Unknown panic"),
+                    },
+                };
+                log::error!("Node stopped by a panic: {}", reason);
+
+                // A poisoned lock here means that other thread panicked storing its reason.
+                if let Ok(mut panic_reason) = self.0.panic_reason.lock() {
+                    panic_reason.get_or_insert(reason);
+                }
+                self.stop();
+                panic::resume_unwind(payload)
+            }
+        }
+    }
 }
 
 impl<S: Send + 'static> Clone for NodeHandler<S> {
@@ -242,6 +461,8 @@ impl<S: Send + 'static> Clone for NodeHandler<S> {
 pub struct NodeListener<S: Send + 'static> {
     network_cache_thread: NamespacedThread<(NetworkProcessor, VecDeque<StoredNetEvent>)>,
     cache_running: Arc<AtomicBool>,
+    cached_len: Arc<AtomicUsize>, // Events cached by the cache thread while it is running.
+    network: Option<(NetworkProcessor, VecDeque<StoredNetEvent>)>,
     signal_receiver: EventReceiver<S>,
     handler: NodeHandler<S>,
 }
@@ -256,21 +477,44 @@ impl<S: Send + 'static> NodeListener<S> {
         // for_each() call. Any generated event would be cached and offered to the user when they
         // call for_each().
         let cache_running = Arc::new(AtomicBool::new(true));
+        let cached_len = Arc::new(AtomicUsize::new(0));
         let network_cache_thread = {
             let cache_running = cache_running.clone();
+            let cached_len = cached_len.clone();
+            let handler = handler.clone();
             let mut cache = VecDeque::new();
             NamespacedThread::spawn("node-network-cache-thread", move || {
-                while cache_running.load(Ordering::Relaxed) {
-                    network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
-                        log::trace!("Cached {:?}", net_event);
-                        cache.push_back(net_event.into());
-                    });
-                }
+                handler.run_guarded(|| {
+                    while cache_running.load(Ordering::Relaxed) {
+                        network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
+                            log::trace!("Cached {:?}", net_event);
+                            cache.push_back(net_event.into());
+                            cached_len.fetch_add(1, Ordering::Relaxed);
+                        });
+                        handler.check_graceful_stop();
+                    }
+                });
                 (network_processor, cache)
             })
         };
 
-        NodeListener { network_cache_thread, cache_running, signal_receiver, handler }
+        NodeListener {
+            network_cache_thread,
+            cache_running,
+            cached_len,
+            network: None,
+            signal_receiver,
+            handler,
+        }
+    }
+
+    /// Stops caching the network events and takes the processor with the events cached.
+    fn take_network(&mut self) -> (NetworkProcessor, VecDeque<StoredNetEvent>) {
+        self.cache_running.store(false, Ordering::Relaxed);
+        match self.network.take() {
+            Some(network) => network, // Already taken by a recv() call.
+            None => self.network_cache_thread.join(),
+        }
     }
 
     /// Iterate indefinitely over all generated `NetEvent`.
@@ -296,17 +540,22 @@ impl<S: Send + 'static> NodeListener<S> {
     /// ```
     pub fn for_each(mut self, mut event_callback: impl FnMut(NodeEvent<S>)) {
         // Stop cache events
-        self.cache_running.store(false, Ordering::Relaxed);
-        let (mut network_processor, mut cache) = self.network_cache_thread.join();
+        let (mut network_processor, mut cache) = self.take_network();
 
         // Dispatch the catched events first.
-        while let Some(event) = cache.pop_front() {
-            let net_event = event.borrow();
-            log::trace!("Read from cache {:?}", net_event);
-            event_callback(NodeEvent::Network(net_event));
-            if !self.handler.is_running() {
-                return
+        let dispatched = self.handler.run_guarded(|| {
+            while let Some(event) = cache.pop_front() {
+                let net_event = event.borrow();
+                log::trace!("Read from cache {:?}", net_event);
+                event_callback(NodeEvent::Network(net_event));
+                if !self.handler.is_running() {
+                    return false
+                }
             }
+            true
+        });
+        if !dispatched {
+            return
         }
 
         crossbeam_utils::thread::scope(|scope| {
@@ -333,28 +582,33 @@ impl<S: Send + 'static> NodeListener<S> {
                     .builder()
                     .name(String::from("node-network-thread"))
                     .spawn(move |_| {
-                        while handler.is_running() {
-                            if let Some(signal) = signal_receiver.receive_timeout(*SAMPLING_TIMEOUT)
-                            {
-                                let mut event_callback =
-                                    multiplexed.0.lock().expect(OTHER_THREAD_ERR);
-                                if handler.is_running() {
-                                    event_callback(NodeEvent::Signal(signal));
+                        handler.run_guarded(|| {
+                            while handler.is_running() {
+                                let timeout = *SAMPLING_TIMEOUT;
+                                if let Some(signal) = signal_receiver.receive_timeout(timeout) {
+                                    let mut event_callback =
+                                        multiplexed.0.lock().expect(OTHER_THREAD_ERR);
+                                    if handler.is_running() {
+                                        event_callback(NodeEvent::Signal(signal));
+                                    }
                                 }
                             }
-                        }
+                        })
                     })
                     .unwrap()
             };
 
-            while self.handler.is_running() {
-                network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
-                    let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
-                    if self.handler.is_running() {
-                        event_callback(NodeEvent::Network(net_event));
-                    }
-                });
-            }
+            self.handler.run_guarded(|| {
+                while self.handler.is_running() {
+                    network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
+                        let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
+                        if self.handler.is_running() {
+                            event_callback(NodeEvent::Network(net_event));
+                        }
+                    });
+                    self.handler.check_graceful_stop();
+                }
+            });
         })
         .unwrap();
     }
@@ -397,8 +651,7 @@ impl<S: Send + 'static> NodeListener<S> {
         event_callback: impl FnMut(NodeEvent<S>) + Send + 'static,
     ) -> NodeTask {
         // Stop cache events
-        self.cache_running.store(false, Ordering::Relaxed);
-        let (mut network_processor, mut cache) = self.network_cache_thread.join();
+        let (mut network_processor, mut cache) = self.take_network();
 
         let multiplexed = Arc::new(Mutex::new(event_callback));
 
@@ -411,24 +664,27 @@ impl<S: Send + 'static> NodeListener<S> {
             let handler = self.handler.clone();
 
             NamespacedThread::spawn("node-network-thread", move || {
-                while let Some(event) = cache.pop_front() {
-                    let net_event = event.borrow();
-                    log::trace!("Read from cache {:?}", net_event);
-                    let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
-                    event_callback(NodeEvent::Network(net_event));
-                    if !handler.is_running() {
-                        return
-                    }
-                }
-
-                while handler.is_running() {
-                    network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
+                handler.run_guarded(|| {
+                    while let Some(event) = cache.pop_front() {
+                        let net_event = event.borrow();
+                        log::trace!("Read from cache {:?}", net_event);
                         let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
-                        if handler.is_running() {
-                            event_callback(NodeEvent::Network(net_event));
+                        event_callback(NodeEvent::Network(net_event));
+                        if !handler.is_running() {
+                            return
                         }
-                    });
-                }
+                    }
+
+                    while handler.is_running() {
+                        network_processor.process_poll_event(Some(*SAMPLING_TIMEOUT), |net_event| {
+                            let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
+                            if handler.is_running() {
+                                event_callback(NodeEvent::Network(net_event));
+                            }
+                        });
+                        handler.check_graceful_stop();
+                    }
+                })
             })
         };
 
@@ -438,18 +694,101 @@ impl<S: Send + 'static> NodeListener<S> {
             let handler = self.handler.clone();
 
             NamespacedThread::spawn("node-signal-thread", move || {
-                while handler.is_running() {
-                    if let Some(signal) = signal_receiver.receive_timeout(*SAMPLING_TIMEOUT) {
-                        let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
-                        if handler.is_running() {
-                            event_callback(NodeEvent::Signal(signal));
+                handler.run_guarded(|| {
+                    while handler.is_running() {
+                        if let Some(signal) = signal_receiver.receive_timeout(*SAMPLING_TIMEOUT) {
+                            let mut event_callback = multiplexed.lock().expect(OTHER_THREAD_ERR);
+                            if handler.is_running() {
+                                event_callback(NodeEvent::Signal(signal));
+                            }
                         }
                     }
-                }
+                })
             })
         };
 
-        NodeTask { network_thread, signal_thread }
+        NodeTask { network_thread, signal_thread, workers: Vec::new() }
+    }
+
+    /// Similar to [`NodeListener::for_each_async()`] but the events are processed by a pool
+    /// of `workers` threads, so several events can be processed at the same time.
+    /// It is useful when processing an event is expensive.
+    ///
+    /// All the events of an endpoint are processed by the same worker, in the order they
+    /// were generated. Events of different endpoints can be processed in parallel.
+    /// The [`NetEvent::NetworkChanged`] events, that have no endpoint, are processed
+    /// by the first worker.
+    /// Signals are distributed among the workers, so they may be processed
+    /// in parallel with other events.
+    /// As in [`NodeListener::enqueue()`], the events are copied to be moved to the workers.
+    ///
+    /// # Example
+    /// ```
+    /// use message_io::node::{self, StoredNodeEvent as NodeEvent};
+    /// use message_io::network::{Transport, NetEvent};
+    ///
+    /// let (handler, listener) = node::split();
+    /// handler.signals().send_with_timer((), std::time::Duration::from_secs(1));
+    /// let (id, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+    ///
+    /// let inner_handler = handler.clone();
+    /// let task = listener.for_each_parallel(4, move |event| match event {
+    ///     NodeEvent::Network(net_event) => match net_event.borrow() {
+    ///         NetEvent::Message(endpoint, data) => { /* Expensive processing here */ },
+    ///         _ => (),
+    ///     },
+    ///     NodeEvent::Signal(_) => inner_handler.stop(),
+    /// });
+    /// drop(task); // Blocked here until handler.stop() is called (1 sec).
+    /// ```
+    pub fn for_each_parallel(
+        self,
+        workers: usize,
+        event_callback: impl Fn(StoredNodeEvent<S>) + Send + Sync + 'static,
+    ) -> NodeTask {
+        assert!(workers > 0, "The node needs at least one worker");
+        let event_callback = Arc::new(event_callback);
+
+        let (senders, worker_threads): (Vec<_>, Vec<_>) = (0..workers)
+            .map(|index| {
+                let (sender, receiver) = crossbeam_channel::unbounded::<StoredNodeEvent<S>>();
+                let event_callback = event_callback.clone();
+                let handler = self.handler.clone();
+
+                let name = format!("node-worker-thread-{}", index);
+                let thread = NamespacedThread::spawn(&name, move || {
+                    handler.run_guarded(|| {
+                        // Finished once the node stops, when the senders are dropped.
+                        for event in receiver {
+                            if handler.is_running() {
+                                event_callback(event);
+                            }
+                        }
+                    })
+                });
+                (sender, thread)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .unzip();
+
+        let mut next_signal_worker = 0;
+        let mut task = self.for_each_async(move |event| {
+            let worker = match &event {
+                NodeEvent::Network(net_event) => match endpoint_of(net_event) {
+                    Some(endpoint) => worker_of(endpoint, workers),
+                    None => 0, // Events of the whole network, as NetworkChanged.
+                },
+                NodeEvent::Signal(_) => {
+                    next_signal_worker = (next_signal_worker + 1) % workers;
+                    next_signal_worker
+                }
+            };
+            // It only fails if the worker has panicked, and then the node is already stopped.
+            senders[worker].send(event.into()).ok();
+        });
+        task.workers = worker_threads;
+        task
     }
 
     /// Consumes the listener to create a `NodeTask` and an `EventReceiver` where the events
@@ -484,6 +823,117 @@ impl<S: Send + 'static> NodeListener<S> {
         let task = self.for_each_async(move |node_event| sender.send(node_event.into()));
         (task, receiver)
     }
+
+    /// Blocks the current thread until the next event is received.
+    /// It is an imperative alternative to [`NodeListener::for_each()`], useful for simple
+    /// scripts and tests. As with [`NodeListener::enqueue()`], the event is copied in order
+    /// to not reference the internal buffers.
+    ///
+    /// Any event generated before the first call will be also returned, in order.
+    /// Returns `None` if the node is stopped by [`NodeHandler::stop()`].
+    ///
+    /// # Example
+    /// ```
+    /// use message_io::node::{self, StoredNodeEvent as NodeEvent};
+    /// use message_io::network::{Transport, NetEvent};
+    ///
+    /// let (handler, mut listener) = node::split::<()>();
+    /// let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+    /// handler.network().connect(Transport::FramedTcp, addr).unwrap();
+    ///
+    /// while let Some(NodeEvent::Network(net_event)) = listener.recv() {
+    ///     if let NetEvent::Connected(..) = net_event.borrow() {
+    ///         handler.stop();
+    ///     }
+    /// }
+    /// assert!(listener.recv().is_none());
+    /// ```
+    pub fn recv(&mut self) -> Option<StoredNodeEvent<S>> {
+        self.recv_until(None)
+    }
+
+    /// Similar to [`NodeListener::recv()`] but it waits at most `timeout` for the event.
+    /// Returns `None` if the timeout is reached or if the node is stopped.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<StoredNodeEvent<S>> {
+        self.recv_until(Some(Instant::now() + timeout))
+    }
+
+    /// Returns the number of events waiting to be processed.
+    /// They are the signals sent and not yet received and the network events already read
+    /// from the OS and not yet returned: the events cached since the node was created,
+    /// before the first [`NodeListener::recv()`] call, and the ones read by that call.
+    /// Network events are read from the OS as they are processed,
+    /// so usually only signals are waiting.
+    /// This value is also available from the handler through [`EventSender::pending_len()`],
+    /// in order to decide, for example, to drop non-critical work when the node is busy.
+    pub fn pending_len(&self) -> usize {
+        let cached = match &self.network {
+            Some((_, cache)) => cache.len(),
+            None => self.cached_len.load(Ordering::Relaxed),
+        };
+        cached + self.signal_receiver.sender().pending_len()
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Option<StoredNodeEvent<S>> {
+        if self.network.is_none() {
+            self.network = Some(self.take_network());
+        }
+        let (network_processor, cache) = self.network.as_mut().unwrap();
+
+        while self.handler.is_running() {
+            if let Some(net_event) = cache.pop_front() {
+                return Some(StoredNodeEvent::Network(net_event))
+            }
+            if let Some(signal) = self.signal_receiver.try_receive() {
+                return Some(StoredNodeEvent::Signal(signal))
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) => remaining.min(*SAMPLING_TIMEOUT),
+                    None => return None,
+                },
+                None => *SAMPLING_TIMEOUT,
+            };
+
+            network_processor.process_poll_event(Some(timeout), |net_event| {
+                log::trace!("Cached {:?}", net_event);
+                cache.push_back(net_event.into());
+            });
+            self.handler.check_graceful_stop();
+        }
+        None
+    }
+}
+
+fn endpoint_of(net_event: &NetEvent<'_>) -> Option<Endpoint> {
+    match net_event {
+        NetEvent::Connecting(endpoint, _) => Some(*endpoint),
+        NetEvent::Connected(endpoint, _) => Some(*endpoint),
+        NetEvent::ConnectFailed(endpoint, _) => Some(*endpoint),
+        NetEvent::Accepted(endpoint, _) => Some(*endpoint),
+        NetEvent::Message(endpoint, _) => Some(*endpoint),
+        NetEvent::Disconnected(endpoint) => Some(*endpoint),
+        NetEvent::Error(endpoint, _) => Some(*endpoint),
+        NetEvent::Control(endpoint, ..) => Some(*endpoint),
+        NetEvent::Throughput(sample) => Some(sample.endpoint),
+        NetEvent::NetworkChanged => None,
+        NetEvent::Watermark(endpoint, _) => Some(*endpoint),
+    }
+}
+
+/// Worker of [`NodeListener::for_each_parallel()`] that processes the events of the endpoint.
+fn worker_of(endpoint: Endpoint, workers: usize) -> usize {
+    match endpoint.resource_id().is_remote() {
+        // The ids are consecutive, so the remotes are spread among the workers.
+        true => endpoint.resource_id().base_value() % workers,
+        // Endpoints of a listener (non connection oriented) are identified by its address.
+        false => {
+            let mut hasher = DefaultHasher::new();
+            endpoint.addr().hash(&mut hasher);
+            hasher.finish() as usize % workers
+        }
+    }
 }
 
 impl<S: Send + 'static> Drop for NodeListener<S> {
@@ -501,6 +951,7 @@ impl<S: Send + 'static> Drop for NodeListener<S> {
 pub struct NodeTask {
     network_thread: NamespacedThread<()>,
     signal_thread: NamespacedThread<()>,
+    workers: Vec<NamespacedThread<()>>, // Used by for_each_parallel(), finished after the rest.
 }
 
 impl NodeTask {
@@ -511,6 +962,9 @@ impl NodeTask {
     pub fn wait(&mut self) {
         self.network_thread.try_join();
         self.signal_thread.try_join();
+        for worker in &mut self.workers {
+            worker.try_join();
+        }
     }
 }
 
@@ -603,4 +1057,250 @@ mod tests {
         task.wait();
         assert!(!handler.is_running());
     }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn recv_connected() {
+        use crate::network::{Transport};
+
+        let (handler, mut listener) = split::<()>();
+        let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        let (endpoint, _) = handler.network().connect(Transport::FramedTcp, addr).unwrap();
+
+        loop {
+            let event = listener.recv_timeout(Duration::from_millis(2000)).unwrap();
+            match event.network() {
+                StoredNetEvent::Connected(net_endpoint, status) => {
+                    assert_eq!(net_endpoint, endpoint);
+                    assert!(status);
+                    break
+                }
+                StoredNetEvent::Accepted(..) => (),
+                _ => unreachable!(),
+            }
+        }
+
+        handler.stop();
+        assert!(listener.recv().is_none());
+    }
+
+    #[test]
+    fn recv_signal() {
+        let (handler, mut listener) = split();
+        assert!(listener.recv_timeout(Duration::from_millis(100)).is_none());
+
+        handler.signals().send("recv");
+        assert_eq!(listener.recv_timeout(Duration::from_millis(1000)).unwrap().signal(), "recv");
+
+        // The listener can still be used after receiving.
+        handler.signals().send("stop");
+        let inner_handler = handler.clone();
+        listener.for_each(move |event| match event.signal() {
+            "stop" => inner_handler.stop(),
+            _ => unreachable!(),
+        });
+        assert!(!handler.is_running());
+    }
+
+    #[test]
+    fn pending_len() {
+        let (handler, mut listener) = split();
+        assert_eq!(listener.pending_len(), 0);
+
+        for _ in 0..1000 {
+            handler.signals().send("flood");
+        }
+        assert_eq!(listener.pending_len(), 1000);
+        assert_eq!(handler.signals().pending_len(), 1000);
+
+        let mut received = 0;
+        while listener.recv_timeout(Duration::from_millis(100)).is_some() {
+            received += 1;
+            assert_eq!(listener.pending_len(), 1000 - received);
+        }
+        assert_eq!(received, 1000);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn pending_len_of_cached_events() {
+        use crate::network::{Transport};
+
+        let (handler, mut listener) = split::<()>();
+        let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        handler.network().connect(Transport::FramedTcp, addr).unwrap();
+
+        // The Connected and Accepted events are cached before any recv() call.
+        let start = Instant::now();
+        while listener.pending_len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(listener.pending_len(), 2);
+
+        listener.recv_timeout(Duration::from_millis(100)).unwrap();
+        assert_eq!(listener.pending_len(), 1);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn async_node_panic() {
+        use crate::network::{Transport};
+
+        let (handler, listener) = split::<()>();
+        let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        handler.network().connect(Transport::FramedTcp, addr).unwrap();
+
+        let task = listener.for_each_async(|event| {
+            if let NetEvent::Accepted(..) = event.network() {
+                panic!("Forced panic processing")
+            }
+        });
+
+        let start = Instant::now();
+        while handler.is_running() && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!handler.is_running());
+        assert_eq!(handler.panic_reason().as_deref(), Some("Forced panic processing"));
+
+        // The panic is propagated once the task is finished.
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| drop(task))).is_err());
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn parallel_node() {
+        use crate::network::{Transport};
+        use std::sync::atomic::{AtomicUsize};
+
+        const MESSAGES: u8 = 3;
+
+        let (handler, listener) = split::<()>();
+        let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let (inner_active, inner_max) = (active.clone(), max_active.clone());
+        let inner_received = received.clone();
+        let task = listener.for_each_parallel(2, move |event| {
+            if let StoredNodeEvent::Network(StoredNetEvent::Message(endpoint, data)) = event {
+                let current = inner_active.fetch_add(1, Ordering::SeqCst) + 1;
+                inner_max.fetch_max(current, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(100)); // Slow processing.
+                inner_received.lock().unwrap().push((endpoint.addr(), data[0]));
+                inner_active.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        let (client_handler, _client_listener) = split::<()>();
+        let mut connections = Vec::new();
+        for _ in 0..2 {
+            let (endpoint, local_addr) =
+                client_handler.network().connect_sync(Transport::FramedTcp, addr).unwrap();
+            connections.push((endpoint, local_addr));
+        }
+        for index in 0..MESSAGES {
+            for (endpoint, _) in &connections {
+                client_handler.network().send(*endpoint, &[index]);
+            }
+        }
+
+        let start = Instant::now();
+        while received.lock().unwrap().len() < 2 * MESSAGES as usize {
+            assert!(start.elapsed() < Duration::from_secs(5), "Timeout receiving the messages");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        handler.stop();
+        drop(task);
+
+        // The endpoints are processed in parallel but each one keeps its order.
+        assert_eq!(max_active.load(Ordering::SeqCst), 2);
+        let received = received.lock().unwrap();
+        for (_, local_addr) in &connections {
+            let indexes = received
+                .iter()
+                .filter(|(addr, _)| addr == local_addr)
+                .map(|(_, index)| *index)
+                .collect::<Vec<_>>();
+            assert_eq!(indexes, (0..MESSAGES).collect::<Vec<_>>());
+        }
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn transport_defaults() {
+        use crate::network::{Transport};
+        use crate::adapters::tcp::{TcpConnectConfig};
+
+        // Records the nodelay option of each socket connected with the config.
+        let nodelays = Arc::new(Mutex::new(Vec::new()));
+        let recording = |config: TcpConnectConfig| {
+            let nodelays = nodelays.clone();
+            config.with_socket(move |socket| {
+                nodelays.lock().unwrap().push(socket.nodelay()?);
+                Ok(())
+            })
+        };
+
+        let default_config = recording(TcpConnectConfig::default().with_nodelay());
+        let (handler, _listener) = NodeBuilder::new()
+            .with_connect_default(TransportConnect::Tcp(default_config))
+            .split::<()>();
+        let (_, addr) = handler.network().listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+
+        handler.network().connect(Transport::Tcp, addr).unwrap();
+        assert_eq!(*nodelays.lock().unwrap(), [true]);
+
+        // The options of the call replace the default ones.
+        let config = TransportConnect::Tcp(recording(TcpConnectConfig::default()));
+        handler.network().connect_with(config, addr).unwrap();
+        assert_eq!(*nodelays.lock().unwrap(), [true, false]);
+
+        // Other nodes use the default options of the transport.
+        let (other_handler, _other_listener) = split::<()>();
+        other_handler.network().connect(Transport::Tcp, addr).unwrap();
+        assert_eq!(*nodelays.lock().unwrap(), [true, false]);
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn stop_graceful() {
+        let (handler, listener) = split::<()>();
+        let (_, addr) = handler.network().listen(Transport::FramedTcp, "127.0.0.1:0").unwrap();
+        handler.network().connect(Transport::FramedTcp, addr).unwrap();
+
+        let start = Instant::now();
+        let mut ready = 0;
+        let mut disconnected = 0;
+        listener.for_each(|event| match event.network() {
+            NetEvent::Connected(..) | NetEvent::Accepted(..) => {
+                ready += 1;
+                if ready == 2 {
+                    handler.stop_graceful(Duration::from_secs(5));
+                }
+            }
+            NetEvent::Disconnected(_) => disconnected += 1,
+            _ => (),
+        });
+
+        // The node stops once both sides of the connection are disconnected.
+        assert_eq!(disconnected, 2);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn sync_node_panic() {
+        let (handler, listener) = split();
+        assert_eq!(handler.panic_reason(), None);
+        handler.signals().send_with_timer((), Duration::from_millis(100));
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            listener.for_each(|_| panic!("Forced panic {}", 42));
+        }));
+        assert!(result.is_err());
+        assert!(!handler.is_running());
+        assert_eq!(handler.panic_reason().as_deref(), Some("Forced panic 42"));
+    }
 }
@@ -0,0 +1,136 @@
+use crate::node::{self, NodeHandler, NodeListener, StoredNodeEvent, StoredNetEvent};
+use crate::network::{Endpoint, Transport};
+
+use std::time::{Duration};
+
+/// Maximum time to wait for each event of the connection.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A node created by the testing helpers, as returned by [`node::split()`].
+pub struct Node<S: Send + 'static> {
+    /// Used to perform the network actions of the node.
+    pub handler: NodeHandler<S>,
+
+    /// Used to receive the events of the node.
+    pub listener: NodeListener<S>,
+}
+
+impl<S: Send + 'static> Node<S> {
+    fn new() -> Self {
+        let (handler, listener) = node::split();
+        Self { handler, listener }
+    }
+
+    fn next_net_event(&mut self) -> StoredNetEvent {
+        match self.listener.recv_timeout(CONNECTION_TIMEOUT) {
+            Some(StoredNodeEvent::Network(net_event)) => net_event,
+            Some(StoredNodeEvent::Signal(_)) => panic!("Signal received while connecting"),
+            None => panic!("Timeout waiting the connection event"),
+        }
+    }
+}
+
+/// Creates two nodes connected through localhost by `transport`.
+/// It returns a *server* node, listening, with the endpoint of its client,
+/// and a *client* node with the endpoint of the server it is connected to.
+///
+/// The `Connected` and `Accepted` events have already been consumed,
+/// so the first events received by the nodes come from the use of the connection.
+/// In the case of non connection oriented transports as UDP, the endpoint of the server
+/// is created by [`Endpoint::from_listener()`] with the client address,
+/// so both nodes can send to each other.
+///
+/// This function panics if the connection can not be established.
+///
+/// Example
+/// ```
+/// use message_io::network::{Transport, NetEvent};
+/// use message_io::node::{StoredNodeEvent as NodeEvent};
+/// use message_io::testing::{self};
+///
+/// let (mut server, client_endpoint, client, server_endpoint) =
+///     testing::connected_pair::<()>(Transport::FramedTcp);
+///
+/// client.handler.network().send(server_endpoint, b"hello");
+/// match server.listener.recv() {
+///     Some(NodeEvent::Network(net_event)) => match net_event.borrow() {
+///         NetEvent::Message(endpoint, data) => {
+///             assert_eq!(endpoint, client_endpoint);
+///             assert_eq!(data, b"hello");
+///         }
+///         _ => unreachable!(),
+///     },
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn connected_pair<S: Send + 'static>(
+    transport: Transport,
+) -> (Node<S>, Endpoint, Node<S>, Endpoint) {
+    let mut server = Node::new();
+    let (listener_id, server_addr) =
+        server.handler.network().listen(transport, "127.0.0.1:0").expect("Could not listen");
+
+    let mut client = Node::new();
+    let (server_endpoint, client_addr) =
+        client.handler.network().connect_sync(transport, server_addr).expect("Could not connect");
+
+    match client.next_net_event() {
+        StoredNetEvent::Connected(endpoint, true) if endpoint == server_endpoint => (),
+        net_event => panic!("Unexpected event connecting the client: {:?}", net_event),
+    }
+
+    let client_endpoint = match transport.is_connection_oriented() {
+        true => match server.next_net_event() {
+            StoredNetEvent::Accepted(endpoint, id) if id == listener_id => endpoint,
+            net_event => panic!("Unexpected event accepting the client: {:?}", net_event),
+        },
+        false => Endpoint::from_listener(listener_id, client_addr),
+    };
+
+    (server, client_endpoint, client, server_endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{NetEvent, SendStatus};
+
+    use test_case::test_case;
+
+    fn next_message(node: &mut Node<()>) -> (Endpoint, Vec<u8>) {
+        match node.next_net_event() {
+            StoredNetEvent::Message(endpoint, data) => (endpoint, data),
+            net_event => panic!("Unexpected event: {:?}", net_event),
+        }
+    }
+
+    #[cfg_attr(feature = "tcp", test_case(Transport::Tcp))]
+    #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp))]
+    #[cfg_attr(feature = "udp", test_case(Transport::Udp))]
+    #[cfg_attr(feature = "websocket", test_case(Transport::Ws))]
+    fn connected_pair_exchange(transport: Transport) {
+        let (mut server, client_endpoint, mut client, server_endpoint) =
+            connected_pair(transport);
+
+        let status = client.handler.network().send(server_endpoint, b"ping");
+        assert_eq!(status, SendStatus::Sent);
+        assert_eq!(next_message(&mut server), (client_endpoint, b"ping".to_vec()));
+
+        let status = server.handler.network().send(client_endpoint, b"pong");
+        assert_eq!(status, SendStatus::Sent);
+        assert_eq!(next_message(&mut client), (server_endpoint, b"pong".to_vec()));
+    }
+
+    #[cfg(feature = "tcp")]
+    #[test]
+    fn connected_pair_disconnection() {
+        let (mut server, client_endpoint, client, server_endpoint) =
+            connected_pair::<()>(Transport::FramedTcp);
+
+        assert!(client.handler.network().remove(server_endpoint.resource_id()));
+        match server.next_net_event().borrow() {
+            NetEvent::Disconnected(endpoint) => assert_eq!(endpoint, client_endpoint),
+            net_event => panic!("Unexpected event: {:?}", net_event),
+        }
+    }
+}
@@ -4,3 +4,7 @@ pub mod thread;
 /// Frame encoding to convert a data stream into packets.
 /// It can be used as a utility to build adapters.
 pub mod encoding;
+
+/// Helpers to split the data stream of a [`Transport::Tcp`](crate::network::Transport::Tcp)
+/// connection into messages.
+pub mod framing;
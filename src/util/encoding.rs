@@ -112,6 +112,12 @@ impl Decoder {
     pub fn stored_size(&self) -> usize {
         self.stored.len()
     }
+
+    /// Returns the size of the message whose bytes are stored in this decoder,
+    /// or `None` if there are not enough bytes stored to decode it.
+    pub fn stored_message_size(&self) -> Option<usize> {
+        decode_size(&self.stored).map(|(expected_size, _)| expected_size)
+    }
 }
 
 #[cfg(test)]
@@ -121,13 +127,13 @@ mod tests {
     const MESSAGE_SIZE: usize = 20; // only works if (X + PADDING ) % 6 == 0
     const ENCODED_MESSAGE_SIZE: usize = 1 + MESSAGE_SIZE; // 1 = log_2(20)/7
     const MESSAGE: [u8; MESSAGE_SIZE] = [42; MESSAGE_SIZE];
-    const MESSAGE_A: [u8; MESSAGE_SIZE] = ['A' as u8; MESSAGE_SIZE];
-    const MESSAGE_B: [u8; MESSAGE_SIZE] = ['B' as u8; MESSAGE_SIZE];
-    const MESSAGE_C: [u8; MESSAGE_SIZE] = ['C' as u8; MESSAGE_SIZE];
+    const MESSAGE_A: [u8; MESSAGE_SIZE] = [b'A'; MESSAGE_SIZE];
+    const MESSAGE_B: [u8; MESSAGE_SIZE] = [b'B'; MESSAGE_SIZE];
+    const MESSAGE_C: [u8; MESSAGE_SIZE] = [b'C'; MESSAGE_SIZE];
 
     fn encode_message(buffer: &mut Vec<u8>, message: &[u8]) {
         let mut buf = [0; MAX_ENCODED_SIZE];
-        buffer.extend_from_slice(&*encode_size(message, &mut buf));
+        buffer.extend_from_slice(encode_size(message, &mut buf));
         buffer.extend_from_slice(message);
     }
 
@@ -247,7 +253,7 @@ mod tests {
         let mut decoder = Decoder::default();
 
         let mut times_called = 0;
-        decoder.decode(&first, |_decoded| {
+        decoder.decode(first, |_decoded| {
             // Should not be called
             times_called += 1;
         });
@@ -255,7 +261,7 @@ mod tests {
         assert_eq!(0, times_called);
         assert_eq!(SPLIT, decoder.stored.len());
 
-        decoder.decode(&second, |decoded| {
+        decoder.decode(second, |decoded| {
             times_called += 1;
             assert_eq!(MESSAGE, decoded);
         });
@@ -278,7 +284,7 @@ mod tests {
         let mut decoder = Decoder::default();
 
         let mut times_called = 0;
-        decoder.decode(&first, |_decoded| {
+        decoder.decode(first, |_decoded| {
             // Should not be called
             times_called += 1;
         });
@@ -286,7 +292,7 @@ mod tests {
         assert_eq!(0, times_called);
         assert_eq!(SPLIT, decoder.stored.len());
 
-        decoder.decode(&second, |decoded| {
+        decoder.decode(second, |decoded| {
             times_called += 1;
             assert_eq!(MESSAGE, decoded);
         });
@@ -334,7 +340,7 @@ mod tests {
         let mut decoder = Decoder::default();
 
         let mut times_called = 0;
-        decoder.decode(&start_1b, |_decoded| {
+        decoder.decode(start_1b, |_decoded| {
             // Should not be called
             times_called += 1;
         });
@@ -342,7 +348,7 @@ mod tests {
         assert_eq!(0, times_called);
         assert_eq!(2, decoder.stored.len());
 
-        decoder.decode(&remaining, |decoded| {
+        decoder.decode(remaining, |decoded| {
             times_called += 1;
             assert_eq!(msg, decoded);
         });
@@ -364,7 +370,7 @@ mod tests {
         let mut decoder = Decoder::default();
 
         let mut times_called = 0;
-        decoder.decode(&start_1b, |_decoded| {
+        decoder.decode(start_1b, |_decoded| {
             // Should not be called
             times_called += 1;
         });
@@ -375,7 +381,7 @@ mod tests {
         let (next_1b, remaining) = remaining.split_at(1);
 
         let mut times_called = 0;
-        decoder.decode(&next_1b, |_decoded| {
+        decoder.decode(next_1b, |_decoded| {
             // Should not be called
             times_called += 1;
         });
@@ -383,7 +389,7 @@ mod tests {
         assert_eq!(0, times_called);
         assert_eq!(2, decoder.stored.len());
 
-        decoder.decode(&remaining, |decoded| {
+        decoder.decode(remaining, |decoded| {
             times_called += 1;
             assert_eq!(msg, decoded);
         });
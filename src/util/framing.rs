@@ -0,0 +1,267 @@
+use crate::network::{Endpoint};
+
+use std::collections::{HashMap};
+use std::convert::{TryFrom, TryInto};
+use std::io::{self, ErrorKind};
+
+/// Defines how the messages are delimited inside a data stream.
+pub trait Framer {
+    /// Message type produced by the framer.
+    type Frame;
+
+    /// Tries to extract the first complete frame of `buf`.
+    /// If there is one, the implementator must remove its bytes (including delimiters or headers)
+    /// from the front of `buf` and return the frame.
+    /// If `buf` does not contain a complete frame yet, it must return `None` and keep `buf`
+    /// untouched, the decoding will be retried when more data arrives.
+    /// If the data can never be decoded, as a frame bigger than accepted,
+    /// it must return an error.
+    fn decode(&self, buf: &mut Vec<u8>) -> io::Result<Option<Self::Frame>>;
+}
+
+/// Used to get messages from [`Transport::Tcp`](crate::network::Transport::Tcp) connections.
+/// Since `Tcp` is a stream, a [`crate::network::NetEvent::Message`] can contain a partial
+/// message or several of them.
+/// The `StreamDecoder` stores the data received by each endpoint and delivers the complete
+/// messages found by its [`Framer`].
+/// If the framer fails, the data of the endpoint is discarded and the error is returned:
+/// the stream can not be decoded anymore, so the endpoint should be removed.
+///
+/// Example
+/// ```
+/// use message_io::node::{self, NodeEvent};
+/// use message_io::network::{Transport, NetEvent};
+/// use message_io::util::framing::{StreamDecoder, LineFramer};
+///
+/// let (handler, listener) = node::split();
+/// handler.signals().send_with_timer((), std::time::Duration::from_secs(1));
+/// handler.network().listen(Transport::Tcp, "127.0.0.1:0").unwrap();
+///
+/// let mut decoder = StreamDecoder::new(LineFramer);
+/// listener.for_each(move |event| match event {
+///     NodeEvent::Network(NetEvent::Message(endpoint, data)) => {
+///         let decoded = decoder.decode(endpoint, data, |endpoint, line| {
+///             println!("{} sent: {}", endpoint, String::from_utf8_lossy(&line));
+///         });
+///         if let Err(err) = decoded {
+///             println!("{} sent wrong data: {}", endpoint, err);
+///             handler.network().remove(endpoint.resource_id());
+///         }
+///     }
+///     NodeEvent::Network(NetEvent::Disconnected(endpoint)) => {
+///         decoder.remove(endpoint);
+///     }
+///     NodeEvent::Network(_) => (),
+///     NodeEvent::Signal(_) => handler.stop(),
+/// });
+/// ```
+pub struct StreamDecoder<F: Framer> {
+    framer: F,
+    buffers: HashMap<Endpoint, Vec<u8>>,
+}
+
+impl<F: Framer> StreamDecoder<F> {
+    /// Creates a decoder that splits the messages with `framer`.
+    pub fn new(framer: F) -> Self {
+        Self { framer, buffers: HashMap::new() }
+    }
+
+    /// Adds the data received by the endpoint.
+    /// `decoded_callback` will be called for each complete message found.
+    /// The remaining data is stored until more data of the endpoint is decoded.
+    /// If the framer fails, the stored data is discarded and the error is returned,
+    /// after calling `decoded_callback` for the messages found before.
+    pub fn decode(
+        &mut self,
+        endpoint: Endpoint,
+        data: &[u8],
+        mut decoded_callback: impl FnMut(Endpoint, F::Frame),
+    ) -> io::Result<()> {
+        let buf = self.buffers.entry(endpoint).or_default();
+        buf.extend_from_slice(data);
+        loop {
+            match self.framer.decode(buf) {
+                Ok(Some(frame)) => decoded_callback(endpoint, frame),
+                Ok(None) => break,
+                Err(err) => {
+                    self.buffers.remove(&endpoint);
+                    return Err(err)
+                }
+            }
+        }
+        if buf.is_empty() {
+            self.buffers.remove(&endpoint);
+        }
+        Ok(())
+    }
+
+    /// Returns the bytes stored for the endpoint, waiting to complete a message.
+    pub fn stored_size(&self, endpoint: Endpoint) -> usize {
+        self.buffers.get(&endpoint).map(|buf| buf.len()).unwrap_or(0)
+    }
+
+    /// Removes the data stored for the endpoint.
+    /// It should be called when the endpoint is disconnected.
+    /// Returns the incomplete data that was stored.
+    pub fn remove(&mut self, endpoint: Endpoint) -> Option<Vec<u8>> {
+        self.buffers.remove(&endpoint)
+    }
+}
+
+/// Framer of messages delimited by a newline (`\n`).
+/// The delimiter, and a `\r` before it, are not included in the frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LineFramer;
+
+impl Framer for LineFramer {
+    type Frame = Vec<u8>;
+
+    fn decode(&self, buf: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        let position = match buf.iter().position(|&byte| byte == b'\n') {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let mut line: Vec<u8> = buf.drain(..=position).collect();
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+}
+
+/// Size of the header used by [`LengthPrefixedFramer`].
+pub const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Maximum size of the frames accepted by a default [`LengthPrefixedFramer`].
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024; // 16 MiB
+
+/// Framer of messages preceded by its size, as a 4 bytes big-endian integer.
+/// Use [`LengthPrefixedFramer::encode()`] to create the data to send.
+///
+/// The size comes from the peer, so it is limited to not store any size it claims:
+/// a header bigger than the maximum frame size fails the decoding
+/// with [`std::io::ErrorKind::InvalidData`].
+#[derive(Clone, Copy, Debug)]
+pub struct LengthPrefixedFramer {
+    max_frame_size: usize,
+}
+
+impl Default for LengthPrefixedFramer {
+    fn default() -> Self {
+        Self { max_frame_size: DEFAULT_MAX_FRAME_SIZE }
+    }
+}
+
+impl LengthPrefixedFramer {
+    /// Creates a framer that accepts frames of at most `size` bytes, without the header.
+    /// By default it is [`DEFAULT_MAX_FRAME_SIZE`].
+    pub fn with_max_frame_size(size: usize) -> Self {
+        Self { max_frame_size: size }
+    }
+
+    /// Creates the frame to send for the message.
+    pub fn encode(message: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(message.len()).expect("Message too big to be length-prefixed");
+        let mut frame = Vec::with_capacity(LENGTH_PREFIX_SIZE + message.len());
+        frame.extend_from_slice(&size.to_be_bytes());
+        frame.extend_from_slice(message);
+        frame
+    }
+}
+
+impl Framer for LengthPrefixedFramer {
+    type Frame = Vec<u8>;
+
+    fn decode(&self, buf: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        let header: [u8; LENGTH_PREFIX_SIZE] = match buf.get(..LENGTH_PREFIX_SIZE) {
+            Some(header) => header.try_into().unwrap(),
+            None => return Ok(None),
+        };
+        let size = u32::from_be_bytes(header) as usize;
+        if size > self.max_frame_size {
+            let message =
+                format!("Frame of {} bytes, the maximum is {}", size, self.max_frame_size);
+            return Err(io::Error::new(ErrorKind::InvalidData, message))
+        }
+        if buf.len() < LENGTH_PREFIX_SIZE + size {
+            return Ok(None)
+        }
+        let frame = buf[LENGTH_PREFIX_SIZE..LENGTH_PREFIX_SIZE + size].to_vec();
+        buf.drain(..LENGTH_PREFIX_SIZE + size);
+        Ok(Some(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{ResourceId};
+
+    fn endpoint(port: u16) -> Endpoint {
+        Endpoint::new(ResourceId::from(0), ([127, 0, 0, 1], port).into())
+    }
+
+    #[test]
+    fn line_split_message() {
+        let mut decoder = StreamDecoder::new(LineFramer);
+        let mut lines = Vec::new();
+        decoder.decode(endpoint(1), b"first\r\nsec", |_, line| lines.push(line)).unwrap();
+        assert_eq!(lines, vec![b"first".to_vec()]);
+        assert_eq!(decoder.stored_size(endpoint(1)), 3);
+
+        decoder.decode(endpoint(1), b"ond\nthird\n", |_, line| lines.push(line)).unwrap();
+        assert_eq!(lines, vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()]);
+        assert_eq!(decoder.stored_size(endpoint(1)), 0);
+    }
+
+    #[test]
+    fn length_prefixed_split_message() {
+        let mut data = LengthPrefixedFramer::encode(&[42; 100]);
+        data.extend(LengthPrefixedFramer::encode(&[]));
+
+        let mut decoder = StreamDecoder::new(LengthPrefixedFramer::default());
+        let mut frames = Vec::new();
+        decoder.decode(endpoint(1), &data[..2], |_, frame| frames.push(frame)).unwrap();
+        decoder.decode(endpoint(1), &data[2..50], |_, frame| frames.push(frame)).unwrap();
+        assert!(frames.is_empty());
+
+        decoder.decode(endpoint(1), &data[50..], |_, frame| frames.push(frame)).unwrap();
+        assert_eq!(frames, vec![vec![42; 100], vec![]]);
+        assert_eq!(decoder.stored_size(endpoint(1)), 0);
+    }
+
+    #[test]
+    fn length_prefixed_frame_too_big() {
+        let mut data = LengthPrefixedFramer::encode(&[42; 100]);
+        data.extend(LengthPrefixedFramer::encode(&[42; 101]));
+
+        let mut decoder = StreamDecoder::new(LengthPrefixedFramer::with_max_frame_size(100));
+        let mut frames = Vec::new();
+        let err = decoder.decode(endpoint(1), &data[..110], |_, frame| frames.push(frame));
+        assert_eq!(err.unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(frames, vec![vec![42; 100]]);
+
+        // The data of the endpoint is discarded, the rest of the frame is not awaited.
+        assert_eq!(decoder.stored_size(endpoint(1)), 0);
+
+        // Rejected by the header alone, before its data arrives.
+        let header = u32::MAX.to_be_bytes();
+        assert!(decoder.decode(endpoint(2), &header, |_, _| unreachable!()).is_err());
+    }
+
+    #[test]
+    fn endpoints_stored_apart() {
+        let mut decoder = StreamDecoder::new(LineFramer);
+        decoder.decode(endpoint(1), b"one", |_, _| unreachable!()).unwrap();
+        decoder.decode(endpoint(2), b"two", |_, _| unreachable!()).unwrap();
+        decoder
+            .decode(endpoint(1), b"\n", |endpoint_1, line| {
+                assert_eq!(endpoint_1, endpoint(1));
+                assert_eq!(line, b"one");
+            })
+            .unwrap();
+        assert_eq!(decoder.remove(endpoint(2)), Some(b"two".to_vec()));
+        assert_eq!(decoder.stored_size(endpoint(2)), 0);
+    }
+}
@@ -1,10 +1,35 @@
 use std::thread::{self, JoinHandle};
+use std::cell::{Cell};
 
 /// A comprensive error message to notify that the error shown is from other thread.
 pub const OTHER_THREAD_ERR: &str = "Avoid this 'panicked_at' error. \
                                    This error is shown because other thread has panicked \
                                    You can safety skip this error.";
 
+thread_local! {
+    static PROCESSING_NETWORK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `f` marking the current thread as the thread that processes the network,
+/// see [`is_network_thread()`].
+pub fn process_network<T>(f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            PROCESSING_NETWORK.with(|processing| processing.set(self.0));
+        }
+    }
+
+    let _restore = Restore(PROCESSING_NETWORK.with(|processing| processing.replace(true)));
+    f()
+}
+
+/// Returns `true` if the current thread is processing the network,
+/// as when it is called from an event callback.
+pub fn is_network_thread() -> bool {
+    PROCESSING_NETWORK.with(|processing| processing.get())
+}
+
 /// Thread similar to the std, but with a name that can be nested.
 pub struct NamespacedThread<T: Send + 'static> {
     namespace: String,
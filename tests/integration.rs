@@ -11,9 +11,9 @@ use std::collections::{HashSet};
 use std::net::{SocketAddr};
 use std::time::{Duration};
 
-const LOCAL_ADDR: &'static str = "127.0.0.1:0";
-const MIN_MESSAGE: &'static [u8] = &[42];
-const SMALL_MESSAGE: &'static str = "Integration test message";
+const LOCAL_ADDR: &str = "127.0.0.1:0";
+const MIN_MESSAGE: &[u8] = &[42];
+const SMALL_MESSAGE: &str = "Integration test message";
 const BIG_MESSAGE_SIZE: usize = 1024 * 1024 * 8; // 8MB
 
 lazy_static::lazy_static! {
@@ -22,7 +22,7 @@ lazy_static::lazy_static! {
 }
 
 // Common error messages
-const TIMEOUT_EVENT_RECV_ERR: &'static str = "Timeout, but an event was expected.";
+const TIMEOUT_EVENT_RECV_ERR: &str = "Timeout, but an event was expected.";
 
 mod util {
     use std::sync::{Once};
@@ -94,7 +94,7 @@ fn start_echo_server(
                 NetEvent::Message(endpoint, data) => {
                     assert_eq!(MIN_MESSAGE, data);
 
-                    let status = node.network().send(endpoint, &data);
+                    let status = node.network().send(endpoint, data);
                     assert_eq!(SendStatus::Sent, status);
 
                     messages_received += 1;
@@ -123,6 +123,14 @@ fn start_echo_server(
                         false => unreachable!(),
                     }
                 }
+                NetEvent::Error(..) => unreachable!(),
+                NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+                NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+                NetEvent::ConnectFailed(..) => unreachable!(), // There is no connect() calls
+                NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+                NetEvent::NetworkChanged => unreachable!(), // Not monitored
+                NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+                _ => (), // Events added by newer versions
             },
         });
     });
@@ -168,6 +176,14 @@ fn start_echo_client_manager(
                 }
                 NetEvent::Accepted(..) => unreachable!(),
                 NetEvent::Disconnected(_) => unreachable!(),
+                NetEvent::Error(..) => unreachable!(),
+                NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+                NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+                NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+                NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+                NetEvent::NetworkChanged => unreachable!(), // Not monitored
+                NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+                _ => (), // Events added by newer versions
             },
         });
     })
@@ -193,13 +209,21 @@ fn start_burst_receiver(
                 NetEvent::Accepted(..) => (),
                 NetEvent::Message(_, data) => {
                     let expected_message = format!("{}: {}", SMALL_MESSAGE, count);
-                    assert_eq!(expected_message, String::from_utf8_lossy(&data));
+                    assert_eq!(expected_message, String::from_utf8_lossy(data));
                     count += 1;
                     if count == expected_count {
                         node.stop();
                     }
                 }
                 NetEvent::Disconnected(_) => (),
+                NetEvent::Error(..) => unreachable!(),
+                NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+                NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+                NetEvent::ConnectFailed(..) => unreachable!(), // There is no connect() calls
+                NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+                NetEvent::NetworkChanged => unreachable!(), // Not monitored
+                NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+                _ => (), // Events added by newer versions
             },
         });
     });
@@ -257,6 +281,8 @@ fn start_burst_sender(
 #[cfg_attr(feature = "udp", test_case(Transport::Udp, 100))]
 #[cfg_attr(feature = "websocket", test_case(Transport::Ws, 1))]
 #[cfg_attr(feature = "websocket", test_case(Transport::Ws, 100))]
+#[cfg_attr(feature = "in-process", test_case(Transport::InProcess, 1))]
+#[cfg_attr(feature = "in-process", test_case(Transport::InProcess, 100))]
 // NOTE: A medium-high `clients` value can exceeds the "open file" limits of an OS in CI
 // with an obfuscated error message.
 fn echo(transport: Transport, clients: usize) {
@@ -270,6 +296,7 @@ fn echo(transport: Transport, clients: usize) {
 #[cfg_attr(feature = "udp", test_case(Transport::Udp, 2000))]
 #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp, 200000))]
 #[cfg_attr(feature = "websocket", test_case(Transport::Ws, 200000))]
+#[cfg_attr(feature = "in-process", test_case(Transport::InProcess, 200000))]
 fn burst(transport: Transport, messages_count: usize) {
     //util::init_logger(LogThread::Enabled); // Enable it for better debugging
 
@@ -281,6 +308,7 @@ fn burst(transport: Transport, messages_count: usize) {
 #[cfg_attr(feature = "tcp", test_case(Transport::FramedTcp, BIG_MESSAGE_SIZE))]
 #[cfg_attr(feature = "udp", test_case(Transport::Udp, udp::MAX_LOCAL_PAYLOAD_LEN))]
 #[cfg_attr(feature = "websocket", test_case(Transport::Ws, BIG_MESSAGE_SIZE))]
+#[cfg_attr(feature = "in-process", test_case(Transport::InProcess, BIG_MESSAGE_SIZE))]
 fn message_size(transport: Transport, message_size: usize) {
     //util::init_logger(LogThread::Disabled); // Enable it for better debugging
 
@@ -324,7 +352,7 @@ fn message_size(transport: Transport, message_size: usize) {
                     node.stop();
                 }
                 else {
-                    received_message.extend_from_slice(&data);
+                    received_message.extend_from_slice(data);
                 }
             }
             NetEvent::Disconnected(_) => {
@@ -332,6 +360,343 @@ fn message_size(transport: Transport, message_size: usize) {
                 assert_eq!(sent_message, received_message);
                 node.stop();
             }
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+}
+
+/// Events received by each side of a connection that exchanges `MESSAGES` messages
+/// and is removed by the client: `(client events, server events)`.
+#[cfg(all(feature = "tcp", feature = "in-process"))]
+fn exchange_events(transport: Transport) -> (Vec<String>, Vec<String>) {
+    const MESSAGES: usize = 3;
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    let (_, server_addr) = node.network().listen(transport, LOCAL_ADDR).unwrap();
+    let (client, _) = node.network().connect(transport, server_addr).unwrap();
+
+    let mut client_events = Vec::new();
+    let mut server_events = Vec::new();
+    listener.for_each(|event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert_eq!(endpoint, client);
+                client_events.push(format!("connected({status})"));
+                for i in 0..MESSAGES {
+                    let status = node.network().send(client, format!("message {i}").as_bytes());
+                    assert_eq!(status, SendStatus::Sent);
+                }
+            }
+            NetEvent::Accepted(..) => server_events.push("accepted".to_string()),
+            NetEvent::Message(endpoint, data) => {
+                let message = String::from_utf8_lossy(data);
+                if endpoint == client {
+                    client_events.push(format!("received({message})"));
+                    if client_events.len() == MESSAGES + 1 {
+                        assert!(node.network().remove(client.resource_id()));
+                    }
+                }
+                else {
+                    server_events.push(format!("received({message})"));
+                    assert_eq!(node.network().send(endpoint, data), SendStatus::Sent);
+                }
+            }
+            NetEvent::Disconnected(endpoint) => {
+                assert_ne!(endpoint, client);
+                server_events.push("disconnected".to_string());
+                node.stop();
+            }
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => unreachable!(),
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+    (client_events, server_events)
+}
+
+#[cfg(all(feature = "tcp", feature = "in-process"))]
+#[test]
+fn in_process_event_parity() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    let (client_events, server_events) = exchange_events(Transport::InProcess);
+    assert_eq!(client_events[0], "connected(true)");
+    assert_eq!(server_events[0], "accepted");
+    assert_eq!(server_events.last().unwrap(), "disconnected");
+
+    assert_eq!((client_events, server_events), exchange_events(Transport::FramedTcp));
+}
+
+#[cfg(feature = "tcp")]
+#[test]
+fn framed_tcp_chunked_write() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    use message_io::network::{TransportListen};
+    use message_io::adapters::framed_tcp::{FramedTcpListenConfig};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let big_message: Vec<u8> = (0..BIG_MESSAGE_SIZE).map(|_| rng.gen()).collect();
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    let config = FramedTcpListenConfig::default().with_write_chunk_size(64 * 1024);
+    let transport = TransportListen::FramedTcp(config);
+    let (_, server_addr) = node.network().listen_with(transport, LOCAL_ADDR).unwrap();
+    let (big_receiver, big_addr) =
+        node.network().connect(Transport::FramedTcp, server_addr).unwrap();
+    node.network().connect(Transport::FramedTcp, server_addr).unwrap();
+
+    let mut exchanged_messages = 0;
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Accepted(endpoint, _) => {
+                let message = match endpoint.addr() == big_addr {
+                    true => &big_message,
+                    false => MIN_MESSAGE,
+                };
+                // The big message is queued, so this call never blocks the node.
+                let status = node.network().send(endpoint, message);
+                assert_eq!(status, SendStatus::Sent);
+            }
+            NetEvent::Message(endpoint, data) if endpoint == big_receiver => {
+                assert_eq!(big_message, data);
+                // The small messages were exchanged while the big one was being written.
+                assert!(exchanged_messages > 0);
+                node.stop();
+            }
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(MIN_MESSAGE, data);
+                exchanged_messages += 1;
+                let status = node.network().send(endpoint, data);
+                assert_eq!(status, SendStatus::Sent);
+            }
+            NetEvent::Disconnected(_) => unreachable!(),
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+}
+
+#[cfg(feature = "tcp")]
+#[test]
+fn framed_tcp_coalesced_writes() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    use message_io::network::{TransportListen};
+    use message_io::adapters::framed_tcp::{FramedTcpListenConfig};
+
+    const MESSAGES: usize = 100;
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    let config = FramedTcpListenConfig::default().with_coalesced_writes();
+    let transport = TransportListen::FramedTcp(config);
+    let (_, server_addr) = node.network().listen_with(transport, LOCAL_ADDR).unwrap();
+    node.network().connect(Transport::FramedTcp, server_addr).unwrap();
+
+    let mut received = 0usize;
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Accepted(endpoint, _) => {
+                // Written together in the next iteration of the network thread.
+                for i in 0..MESSAGES {
+                    let status = node.network().send(endpoint, &i.to_le_bytes());
+                    assert_eq!(status, SendStatus::Sent);
+                }
+            }
+            NetEvent::Message(_, data) => {
+                assert_eq!(data, received.to_le_bytes());
+                received += 1;
+                if received == MESSAGES {
+                    node.stop();
+                }
+            }
+            NetEvent::Disconnected(_) => unreachable!(),
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+}
+
+#[cfg(feature = "tcp")]
+#[test]
+fn framed_tcp_remove_graceful() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    use message_io::network::{TransportListen};
+    use message_io::adapters::framed_tcp::{FramedTcpListenConfig};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let big_message: Vec<u8> = (0..BIG_MESSAGE_SIZE).map(|_| rng.gen()).collect();
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    let config = FramedTcpListenConfig::default().with_write_chunk_size(64 * 1024);
+    let transport = TransportListen::FramedTcp(config);
+    let (_, server_addr) = node.network().listen_with(transport, LOCAL_ADDR).unwrap();
+    let (receiver, _) = node.network().connect(Transport::FramedTcp, server_addr).unwrap();
+
+    let mut received = false;
+    let mut disconnections = 0;
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(_, status) => assert!(status),
+            NetEvent::Accepted(endpoint, _) => {
+                let status = node.network().send(endpoint, &big_message);
+                assert_eq!(status, SendStatus::Sent);
+                assert!(node.network().remove_graceful(endpoint.resource_id(), *TIMEOUT));
+
+                let status = node.network().send(endpoint, MIN_MESSAGE);
+                assert_eq!(status, SendStatus::ResourceNotAvailable);
+            }
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(endpoint, receiver);
+                assert_eq!(big_message, data);
+                received = true;
+            }
+            NetEvent::Disconnected(endpoint) => {
+                if endpoint == receiver {
+                    // The close is received after the whole message.
+                    assert!(received);
+                }
+                disconnections += 1;
+                if disconnections == 2 {
+                    node.stop();
+                }
+            }
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+}
+
+#[cfg(feature = "tcp")]
+#[test]
+fn tcp_stream_decoder_split_message() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    use message_io::util::framing::{StreamDecoder, LengthPrefixedFramer};
+    use std::io::{Write};
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    let (_, server_addr) = node.network().listen(Transport::Tcp, LOCAL_ADDR).unwrap();
+
+    let frame = LengthPrefixedFramer::encode(SMALL_MESSAGE.as_bytes());
+    let mut client_thread = NamespacedThread::spawn("test-client", move || {
+        let mut stream = std::net::TcpStream::connect(server_addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let (first, second) = frame.split_at(frame.len() / 2);
+        stream.write_all(first).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        stream.write_all(second).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+    });
+
+    let mut decoder = StreamDecoder::new(LengthPrefixedFramer::default());
+    let mut reads = 0;
+    let mut messages = Vec::new();
+    listener.for_each(|event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(..) => unreachable!(),
+            NetEvent::Accepted(..) => (),
+            NetEvent::Message(endpoint, data) => {
+                reads += 1;
+                decoder.decode(endpoint, data, |_, message| messages.push(message)).unwrap();
+            }
+            NetEvent::Disconnected(endpoint) => {
+                assert_eq!(decoder.remove(endpoint), None);
+                node.stop();
+            }
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => unreachable!(), // There is no connect() calls
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
+        },
+    });
+
+    client_thread.join();
+    assert_eq!(reads, 2);
+    assert_eq!(messages, vec![SMALL_MESSAGE.as_bytes().to_vec()]);
+}
+
+#[cfg(feature = "udp")]
+#[test]
+fn udp_port_unreachable() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    let (node, listener) = node::split();
+    node.signals().send_with_timer((), *TIMEOUT);
+
+    // Ensure that there is nobody listening at this port.
+    let closed_addr = std::net::UdpSocket::bind(LOCAL_ADDR).unwrap().local_addr().unwrap();
+    let (target, _) = node.network().connect(Transport::Udp, closed_addr).unwrap();
+
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(_) => panic!("{}", TIMEOUT_EVENT_RECV_ERR),
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(endpoint, status) => {
+                assert!(status);
+                assert_eq!(endpoint, target);
+                let status = node.network().send(target, MIN_MESSAGE);
+                assert_eq!(status, SendStatus::Sent);
+            }
+            NetEvent::Error(endpoint, kind) => {
+                assert_eq!(endpoint, target);
+                assert_eq!(kind, std::io::ErrorKind::ConnectionRefused);
+                // The resource is kept.
+                assert_eq!(node.network().is_ready(target.resource_id()), Some(true));
+                node.stop();
+            }
+            _ => unreachable!(),
         },
     });
 }
@@ -376,6 +741,70 @@ fn multicast_reuse_addr() {
             }
             NetEvent::Accepted(..) => unreachable!(),
             NetEvent::Disconnected(_) => unreachable!(),
+            NetEvent::Error(..) => unreachable!(),
+            NetEvent::Control(..) => unreachable!(), // Only enabled by ws config
+            NetEvent::Throughput(_) => unreachable!(), // Only enabled by sampling
+            NetEvent::ConnectFailed(..) => (), // Only enabled by set_connect_failed_events
+            NetEvent::Connecting(..) => unreachable!(), // Only enabled by set_connecting_events
+            NetEvent::NetworkChanged => unreachable!(), // Not monitored
+            NetEvent::Watermark(..) => unreachable!(), // Only enabled by set_watermarks
+            _ => (), // Events added by newer versions
         },
     });
 }
+
+#[cfg(all(feature = "udp", target_os = "linux"))]
+#[test]
+fn udp_reuse_port_group() {
+    //util::init_logger(LogThread::Disabled); // Enable it for better debugging
+
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use std::time::{Instant};
+
+    const SENDERS: usize = 64;
+
+    let (nodes, addr) = node::split_udp_group::<()>(LOCAL_ADDR, 2).unwrap();
+
+    let mut counters = Vec::new();
+    let mut tasks = Vec::new();
+    for (node, listener, id) in nodes {
+        let counter = Arc::new(AtomicUsize::new(0));
+        counters.push(counter.clone());
+        let task = listener.for_each_async(move |event| match event.network() {
+            NetEvent::Message(endpoint, data) => {
+                assert_eq!(endpoint.resource_id(), id);
+                assert_eq!(data, MIN_MESSAGE);
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => unreachable!(),
+        });
+        tasks.push((node, task));
+    }
+
+    // Each sender has its own port, so the kernel will spread them among the group sockets.
+    let senders: Vec<_> = (0..SENDERS)
+        .map(|_| {
+            let socket = std::net::UdpSocket::bind(LOCAL_ADDR).unwrap();
+            socket.send_to(MIN_MESSAGE, addr).unwrap();
+            socket
+        })
+        .collect();
+
+    let start = Instant::now();
+    while counters.iter().map(|counter| counter.load(Ordering::Relaxed)).sum::<usize>() < SENDERS {
+        assert!(start.elapsed() < *TIMEOUT_SMALL, "{}", TIMEOUT_EVENT_RECV_ERR);
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    for counter in counters {
+        assert!(counter.load(Ordering::Relaxed) > 0);
+    }
+
+    for (node, _task) in tasks {
+        node.stop();
+    }
+    drop(senders);
+}